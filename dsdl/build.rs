@@ -1,35 +1,54 @@
 extern crate dsdl_compiler;
-#[macro_use]
-extern crate quote;
 
 use std::env;
-use std::fs::File;
-use std::io::Write;
+use std::fs;
 use std::path::Path;
 
 use dsdl_compiler::DSDL;
-use dsdl_compiler::Compile;
+use dsdl_compiler::Builder;
 use dsdl_compiler::CompileConfig;
+use dsdl_compiler::config::TypeIdMap;
+use dsdl_compiler::features::{cargo_features, check_manifest, namespace_features};
 
 fn main() {
-    let out_dir = env::var("OUT_DIR").unwrap();
     let cargo_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let dsdl_path = Path::new(&cargo_dir).join("dsdl");
-    let out_path = Path::new(&out_dir).join("dsdl.rs");
-
-    let dsdl = DSDL::read(dsdl_path.clone()).unwrap();
 
     let mut compile_config = CompileConfig::default();
     compile_config.data_type_signature = true;
-    
-    let items = dsdl.compile(&compile_config);
-
-    let mut file = File::create(&out_path).unwrap();
-
-    let tokens = quote!{#(#items)*};
-    
-    file.write_all(tokens.as_str().as_bytes()).unwrap();
-
-    // only recompile DSDL if it has changed
-    println!("cargo:rerun-if-changed={}", dsdl_path.to_str().unwrap());
+    compile_config.namespace_features = true;
+    compile_config.constructors = true;
+    compile_config.serde = env::var_os("CARGO_FEATURE_SERDE").is_some();
+    compile_config.arbitrary = env::var_os("CARGO_FEATURE_ARBITRARY").is_some();
+    compile_config.reflect = true;
+    compile_config.text = true;
+
+    // data type IDs can be assigned or overridden with `$DSDL_ID_MAP` or `$(CARGO_MANIFEST_DIR)/id_map.toml`
+    println!("cargo:rerun-if-env-changed=DSDL_ID_MAP");
+    let id_map_path = match env::var("DSDL_ID_MAP") {
+        Ok(path) => Some(Path::new(&path).to_path_buf()),
+        Err(_) => Some(Path::new(&cargo_dir).join("id_map.toml")).filter(|path| path.exists()),
+    };
+    if let Some(id_map_path) = id_map_path {
+        compile_config.type_ids = TypeIdMap::read(&id_map_path).unwrap_or_else(|error| panic!("{}: {}", id_map_path.display(), error));
+        println!("cargo:rerun-if-changed={}", id_map_path.display());
+    }
+
+    // every namespace is behind a feature, these must be declared in Cargo.toml (`dsdlc --cargo-features` prints them)
+    let manifest_path = Path::new(&cargo_dir).join("Cargo.toml");
+    let dsdl = DSDL::read(&dsdl_path).unwrap_or_else(|error| panic!("{}: {}", dsdl_path.display(), error));
+    let features = namespace_features(&dsdl, &compile_config);
+    let manifest = fs::read_to_string(&manifest_path).unwrap_or_else(|error| panic!("{}: {}", manifest_path.display(), error));
+    if let Err(error) = check_manifest(&manifest, &features) {
+        panic!("{}, update the features in {} to:\n\n{}", error, manifest_path.display(), cargo_features(&features));
+    }
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+
+    // every type gets its own formatted file, which keeps compile errors in the generated code readable
+    Builder::new()
+        .include(dsdl_path)
+        .module_per_file(true)
+        .config(compile_config)
+        .compile()
+        .unwrap_or_else(|error| panic!("{}", error));
 }
@@ -0,0 +1,25 @@
+#!/bin/bash
+# Replaces the vendored `uavcan` namespace with the upstream definitions at a revision of UAVCAN/dsdl,
+# and records the revision in `REVISION`.
+#
+# Usage: ./update.sh <commit SHA on the legacy-v0 branch>
+set -euo pipefail
+
+if [ $# -ne 1 ]; then
+    echo "usage: $0 <commit SHA>" >&2
+    exit 1
+fi
+
+REVISION=$1
+DIR=$(cd "$(dirname "$0")" && pwd)
+CHECKOUT=$(mktemp -d)
+trap 'rm -rf "$CHECKOUT"' EXIT
+
+git clone --quiet https://github.com/UAVCAN/dsdl.git "$CHECKOUT"
+git -C "$CHECKOUT" checkout --quiet "$REVISION"
+
+rm -rf "$DIR/uavcan"
+cp -R "$CHECKOUT/uavcan" "$DIR/uavcan"
+git -C "$CHECKOUT" rev-parse HEAD > "$DIR/REVISION"
+
+echo "vendored uavcan from $(cat "$DIR/REVISION"), run \`cargo test\` in the dsdl crate to check the data type signatures"
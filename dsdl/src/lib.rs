@@ -3,12 +3,23 @@
 //! For full description of DSDL, have a look at the [specification](http://uavcan.org/Specification/3._Data_structure_description_language/)
 //! 
 //! # Usage
-//! This crate will look for DSDL definitions at `$(CARGO_MANIFEST_DIR)/dsdl` and make the compiled Rust definitions available inside this crate.
+//! This crate compiles the DSDL definitions at `$(CARGO_MANIFEST_DIR)/dsdl` and makes the compiled Rust definitions available inside this crate.
 //!
-//! If you wish to use the standard DSDL definitions add them as a git submodule inside the crate, `git submodule add https://github.com/UAVCAN/dsdl.git`.
+//! The standard UAVCAN v0 definitions (the `uavcan` namespace) are bundled with the crate, vendored from the `legacy-v0` branch of the [upstream definitions](https://github.com/UAVCAN/dsdl) (see `dsdl/README.md`).
+//!
+//! Data type IDs can be assigned to types without a default ID, or overridden, with a TOML file mapping full type names to IDs (like `"uavcan.protocol.NodeStatus" = 341`).
+//! The file is read from the path in the `DSDL_ID_MAP` environment variable, or from `$(CARGO_MANIFEST_DIR)/id_map.toml` when the variable is not set.
+//!
+//! Every DSDL namespace is behind a cargo feature, like `equipment-esc` for `uavcan.equipment.esc` or `uavcan` for the types directly in `uavcan`.
+//! All of them are enabled by default, and a namespace feature enables the namespaces it uses types from.
+//! To only compile the namespaces you need, disable the default features:
+//! ```toml
+//! [dependencies]
+//! dsdl = {version = "*", default-features = false, features = ["equipment-esc", "protocol"]}
+//! ```
+//! The features are declared in `Cargo.toml`, and the build fails when they don't match the DSDL definitions. `dsdlc --cargo-features` prints the up to date `[features]` table.
 //! 
 //! ## Examples
-//! The following examples assumes that the standard DSDL definition is located at `$(CARGO_MANIFEST_DIR)/dsdl`.
 //! ### Basic usage
 //! ```
 //! extern crate dsdl;
@@ -22,6 +33,61 @@
 //! # }
 //! 
 //! ```
+//!
+//! ### Constructing messages
+//! Every struct has a `new` constructor and a builder taking native Rust types, the values are checked to fit their fields.
+//! ```
+//! extern crate dsdl;
+//! extern crate uavcan;
+//!
+//! use dsdl::uavcan::protocol::NodeStatus;
+//! use uavcan::types::FieldError;
+//!
+//! # fn main() {
+//! #
+//! let status = NodeStatus::new(120, 0, 0, 0, 0).unwrap();
+//! assert_eq!(status.uptime_sec, 120);
+//!
+//! // `health` is a `uint2`
+//! assert_eq!(NodeStatus::new(120, 4, 0, 0, 0).unwrap_err(), FieldError::OutOfRange("health"));
+//!
+//! let status = NodeStatus::builder()
+//!     .uptime_sec(120)
+//!     .health(1)
+//!     .mode(0)
+//!     .sub_mode(0)
+//!     .vendor_specific_status_code(0)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(status.uptime_sec, 120);
+//! # }
+//!
+//! ```
+//!
+//! ### Serde
+//! With the `serde` feature, every type implements `Serialize` and `Deserialize`. Fields keep their DSDL names,
+//! `uint2` fields are serialized as `u8`, `float16` as `f32`, arrays as sequences, and unions are externally tagged enums.
+//! ```toml
+//! [dependencies]
+//! dsdl = {version = "*", features = ["serde"]}
+//! ```
+//!
+//! ### Arbitrary
+//! With the `arbitrary` feature, every type implements `Arbitrary`, with values in range of their fields, and
+//! `visit_arbitrary_types` visits all compiled types with a `uavcan::arbitrary_field::TypeVisitor`.
+//! Together with `uavcan::arbitrary_field::check_round_trip` this checks that every type survives being split into frames and assembled again.
+//!
+//! ### Reflection
+//! Every type implements `uavcan::reflect::Reflect`, which lists its fields and constants as they're defined in DSDL
+//! and walks the fields of a value with a `uavcan::reflect::Visitor`, without knowing the type.
+//!
+//! ### Text
+//! Every type also implements `Display` and `FromStr` with the YAML like text format of `uavcan::text`, where
+//! `uint8` strings are quoted, unions show their active variant and nested types are indented.
 #![no_std]
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+
 include!(concat!(env!("OUT_DIR"), "/dsdl.rs"));
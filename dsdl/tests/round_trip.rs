@@ -0,0 +1,42 @@
+#![cfg(feature = "arbitrary")]
+
+extern crate dsdl;
+extern crate uavcan;
+
+use uavcan::Struct;
+use uavcan::arbitrary_field::{check_round_trip, Arbitrary, TypeVisitor, Unstructured};
+
+/// Pseudo-random bytes for `Unstructured`, the same for every run
+fn random_data(seed: u64, length: usize) -> Vec<u8> {
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    (0..length).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    }).collect()
+}
+
+struct RoundTrip {
+    seeds: u64,
+    types: usize,
+}
+
+impl TypeVisitor for RoundTrip {
+    fn visit<T: Struct + for<'a> Arbitrary<'a>>(&mut self) {
+        for seed in 0..self.seeds {
+            // large enough for the longest arrays, so most values aren't cut short by running out of data
+            let data = random_data(seed, 4096);
+            check_round_trip::<T>(&mut Unstructured::new(&data)).unwrap();
+        }
+        self.types += 1;
+    }
+}
+
+// every compiled type, sent in frames of every length from a single frame to CAN FD frames, including the tail array optimization cases
+#[test]
+fn test_round_trip() {
+    let mut visitor = RoundTrip{seeds: 64, types: 0};
+    dsdl::visit_arbitrary_types(&mut visitor);
+    assert!(visitor.types > 0);
+}
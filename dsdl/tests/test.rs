@@ -4,6 +4,7 @@ extern crate uavcan;
 use uavcan::Message;
 use uavcan::Response;
 use uavcan::Request;
+use uavcan::Struct;
 
 #[test]
 fn test_existence() {
@@ -11,3 +12,46 @@ fn test_existence() {
     assert_eq!(dsdl::uavcan::protocol::GetNodeInfoRequest::TYPE_ID, Some(1));
     assert_eq!(dsdl::uavcan::protocol::GetNodeInfoResponse::TYPE_ID, Some(1));
 }
+
+// the data type signatures from the specification, these pin the bundled definitions
+#[test]
+fn test_data_type_signatures() {
+    assert_eq!(dsdl::uavcan::protocol::NodeStatus::DATA_TYPE_SIGNATURE, 0x0f0868d0c1a7c6f1);
+    assert_eq!(dsdl::uavcan::protocol::GetNodeInfoRequest::DATA_TYPE_SIGNATURE, 0xee468a8121c46a9e);
+    assert_eq!(dsdl::uavcan::protocol::GetNodeInfoResponse::DATA_TYPE_SIGNATURE, 0xee468a8121c46a9e);
+    assert_eq!(dsdl::uavcan::protocol::GlobalTimeSync::DATA_TYPE_SIGNATURE, 0x20271116a793c2db);
+    assert_eq!(dsdl::uavcan::protocol::debug::LogMessage::DATA_TYPE_SIGNATURE, 0xd654a48e0c049d75);
+    assert_eq!(dsdl::uavcan::protocol::dynamic_node_id::Allocation::DATA_TYPE_SIGNATURE, 0x0b2a812620a11d40);
+    assert_eq!(dsdl::uavcan::protocol::param::GetSetRequest::DATA_TYPE_SIGNATURE, 0xa7b622f939d1a4d5);
+    assert_eq!(dsdl::uavcan::protocol::file::ReadResponse::DATA_TYPE_SIGNATURE, 0x8dcdca939f33f678);
+    assert_eq!(dsdl::uavcan::equipment::esc::RawCommand::DATA_TYPE_SIGNATURE, 0x217f5c87d7ec951d);
+    assert_eq!(dsdl::uavcan::equipment::esc::Status::DATA_TYPE_SIGNATURE, 0xa9af28aea2fbb254);
+    assert_eq!(dsdl::uavcan::equipment::actuator::ArrayCommand::DATA_TYPE_SIGNATURE, 0xd8a7486238ec3af3);
+    assert_eq!(dsdl::uavcan::equipment::power::BatteryInfo::DATA_TYPE_SIGNATURE, 0x249c26548a711966);
+}
+
+// the DSDL signature leaves out nested types, which the data type signature includes
+#[test]
+fn test_dsdl_signatures() {
+    assert_eq!(dsdl::uavcan::protocol::NodeStatus::DSDL_SIGNATURE, 0x0f0868d0c1a7c6f1);
+    assert_eq!(dsdl::uavcan::protocol::debug::LogMessage::DSDL_SIGNATURE, 0xe9862b78d38762ba);
+}
+
+#[test]
+fn test_constructors() {
+    use dsdl::uavcan::protocol::debug::{LogLevel, LogMessage};
+    use dsdl::uavcan::equipment::esc::RawCommand;
+    use uavcan::types::FieldError;
+
+    let level = LogLevel::new(LogLevel::INFO.into()).unwrap();
+    let message = LogMessage::new(level.clone(), "node", "hello").unwrap();
+    assert_eq!(message.text.as_ref(), "hello".as_bytes());
+
+    let too_long = [b'a'; 32];
+    assert_eq!(LogMessage::new(level.clone(), &too_long[..], "hello").unwrap_err(), FieldError::WrongLength("source"));
+
+    assert_eq!(RawCommand::new(&[8191, -8192]).unwrap().cmd.length(), 2);
+    assert_eq!(RawCommand::new(&[8192]).unwrap_err(), FieldError::OutOfRange("cmd"));
+
+    assert_eq!(LogMessage::builder().level(level).text("hello").build().unwrap_err(), FieldError::Missing("source"));
+}
@@ -9,17 +9,25 @@ extern crate quote;
 
 mod opts;
 
+use std::env;
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
 
 use std::str::FromStr;
 
 use opts::InputFlags;
 
-use dsdl_parser::DSDL;
-
-use dsdl_compiler::Compile;
+use dsdl_compiler::compile_all;
 use dsdl_compiler::CompileConfig;
+use dsdl_compiler::CompileError;
+use dsdl_compiler::validate::validate;
+use dsdl_compiler::roots;
+use dsdl_compiler::modules::{compile_modules, write_files};
+use dsdl_compiler::features::{cargo_features, namespace_features};
 
 use dsdl_compiler::config::*;
 
@@ -38,51 +46,140 @@ fn main() {
         return;
     }
 
-    let input = if let Some(path) = flags.input.clone() {
-        path
-    } else {
+    if flags.export {
+        export(&flags);
+        return;
+    }
+
+    if flags.inputs.is_empty() {
         opts::print_usage();
         println!("\nInput needs to be specified");
         return;
-    };
+    }
 
-    let output = if let Some(path) = flags.output.clone() {
-        path
+    // several inputs are merged into one directory, which is also where errors are reported from
+    let input = if flags.inputs.len() == 1 {
+        PathBuf::from(&flags.inputs[0])
     } else {
-        opts::print_usage();
-        println!("\nOutput needs to be specified");
-        return;
+        env::temp_dir().join(format!("dsdlc-{}", process::id()))
     };
 
-    let dsdl = match DSDL::read(input) {
+    let dsdl = match roots::read(&flags.inputs, &input) {
         Ok(dsdl) => dsdl,
         Err(error) => {
             error!("errored when reading DSDL: {}", error);
-            return;
+            process::exit(1);
         },
     };
 
     let mut compile_config = CompileConfig::default();
     compile_config.data_type_signature = flags.data_type_signature;
+    compile_config.constant_enums = flags.constant_enums;
+    compile_config.constructors = flags.constructors;
+    compile_config.serde = flags.serde;
+    compile_config.reflect = flags.reflect;
+    compile_config.text = flags.text;
+    compile_config.optional_fields = flags.optional_fields;
+    compile_config.bit_arrays = flags.bit_arrays;
+    compile_config.arbitrary = flags.arbitrary;
+    compile_config.namespace_features = flags.namespace_features;
     compile_config.derive_default = if let Some(s) = flags.derive_default {
         if let Ok(derive_default) = DeriveDefault::from_str(&s) {
             derive_default
         } else {
             error!("Error reading setting `derive-default`");
             opts::print_usage();
-            return;
+            process::exit(1);
         }
     } else {
         DeriveDefault::default()
     };
 
-    let items = dsdl.compile(&compile_config);
+    for extern_path in &flags.extern_paths {
+        match extern_path.split_once('=') {
+            Some((namespace, path)) => compile_config.extern_paths.push((String::from(namespace), String::from(path))),
+            None => {
+                error!("Error reading setting `extern-path`, expected `NAMESPACE=PATH`");
+                opts::print_usage();
+                process::exit(1);
+            },
+        }
+    }
+
+    compile_config.derives = flags.derives;
+
+    for type_attribute in &flags.type_attributes {
+        match type_attribute.split_once('=') {
+            Some((pattern, attribute)) => compile_config.type_attributes.push((String::from(pattern), String::from(attribute))),
+            None => {
+                error!("Error reading setting `type-attribute`, expected `PATTERN=ATTRIBUTE`");
+                opts::print_usage();
+                process::exit(1);
+            },
+        }
+    }
+
+    if let Some(path) = flags.id_map {
+        compile_config.type_ids = match TypeIdMap::read(path) {
+            Ok(type_ids) => type_ids,
+            Err(error) => {
+                error!("{}", error);
+                process::exit(1);
+            },
+        };
+    }
+
+    if let Err(errors) = validate(&dsdl, &compile_config) {
+        print_compile_errors(&errors, &input);
+        process::exit(1);
+    }
+
+    if flags.check {
+        return;
+    }
+
+    if flags.cargo_features {
+        print!("{}", cargo_features(&namespace_features(&dsdl, &compile_config)));
+        return;
+    }
+
+    let output = if let Some(path) = flags.output.clone() {
+        path
+    } else {
+        opts::print_usage();
+        println!("\nOutput needs to be specified");
+        return;
+    };
+
+    if flags.module_per_file {
+        let files = match compile_modules(&dsdl, &compile_config) {
+            Ok(files) => files,
+            Err(errors) => {
+                print_compile_errors(&errors, &input);
+                process::exit(1);
+            },
+        };
+
+        if let Err(error) = write_files(Path::new(&output), &files) {
+            error!("errored when writing to output directory: {}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let items = match compile_all(&dsdl, &compile_config) {
+        Ok(items) => items,
+        Err(errors) => {
+            print_compile_errors(&errors, &input);
+            process::exit(1);
+        },
+    };
     
     let mut file = match File::create(output) {
         Ok(file) => file,
         Err(error) => {
             error!("errored when creating output file: {}", error);
-            return;
+            process::exit(1);
         },
     };
 
@@ -91,7 +188,74 @@ fn main() {
     
     match file.write_all(tokens.as_str().as_bytes()) {
         Ok(_) => (),
-        Err(error) => error!("errored when writing to output file: {}", error),
+        Err(error) => {
+            error!("errored when writing to output file: {}", error);
+            process::exit(1);
+        },
     }
     
 }
+
+/// Runs the example registering the types to export, which writes them to the output directory and verifies them
+fn export(flags: &InputFlags) {
+    let (example, output) = match (&flags.example, &flags.output) {
+        (Some(example), Some(output)) => (example, output),
+        _ => {
+            opts::print_usage();
+            println!("\n`export` needs an example and an output directory");
+            return;
+        },
+    };
+
+    let mut cargo = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cargo.args(["run", "--quiet", "--example", example]);
+    if let Some(ref manifest_path) = flags.manifest_path {
+        cargo.arg("--manifest-path").arg(manifest_path);
+    }
+    cargo.arg("--").arg(output);
+
+    match cargo.status() {
+        Ok(status) if status.success() => (),
+        Ok(_) => {
+            error!("exporting with the example `{}` failed", example);
+            process::exit(1);
+        },
+        Err(error) => {
+            error!("errored when running cargo: {}", error);
+            process::exit(1);
+        },
+    }
+}
+
+/// Prints every compile error, followed by how many there were
+fn print_compile_errors(errors: &[CompileError], input: &Path) {
+    for error in errors {
+        print_compile_error(error, input);
+        eprintln!();
+    }
+    eprintln!("error: aborting due to {} previous error{}", errors.len(), if errors.len() == 1 {""} else {"s"});
+}
+
+/// Prints a compile error the way rustc does, including the offending DSDL line when it can be read
+fn print_compile_error(error: &CompileError, input: &Path) {
+    eprintln!("{}", error);
+
+    let (file, line) = match (error.file(), error.line()) {
+        (Some(file), Some(line)) => (file, line),
+        _ => return,
+    };
+
+    // `input` is either the DSDL root or a single file
+    let path = if input.is_dir() { input.join(file) } else { input.to_path_buf() };
+
+    let source = File::open(path).ok()
+        .and_then(|f| BufReader::new(f).lines().nth(line - 1))
+        .and_then(|source| source.ok());
+
+    if let Some(source) = source {
+        let gutter = " ".repeat(line.to_string().len());
+        eprintln!("{} |", gutter);
+        eprintln!("{} | {}", line, source);
+        eprintln!("{} |", gutter);
+    }
+}
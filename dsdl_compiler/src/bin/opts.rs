@@ -3,10 +3,29 @@ use getopts::Options;
 
 #[derive(Debug)]
 pub(crate) struct InputFlags {
-    pub input: Option<String>,
+    pub inputs: Vec<String>,
     pub output: Option<String>,
     pub data_type_signature: bool,
     pub derive_default: Option<String>,
+    pub derives: Vec<String>,
+    pub type_attributes: Vec<String>,
+    pub constant_enums: bool,
+    pub constructors: bool,
+    pub serde: bool,
+    pub reflect: bool,
+    pub text: bool,
+    pub optional_fields: bool,
+    pub bit_arrays: bool,
+    pub arbitrary: bool,
+    pub id_map: Option<String>,
+    pub extern_paths: Vec<String>,
+    pub check: bool,
+    pub module_per_file: bool,
+    pub namespace_features: bool,
+    pub cargo_features: bool,
+    pub export: bool,
+    pub example: Option<String>,
+    pub manifest_path: Option<String>,
     pub help: bool,
     pub version: bool,
 }
@@ -14,11 +33,47 @@ pub(crate) struct InputFlags {
 fn options() -> Options {
     let mut opts = Options::new();
     opts.optopt("o", "output", "set output file name", "NAME");
-    opts.optopt("i", "input", "set input dir/file name", "NAME");
+    opts.optmulti("i", "input", "set input dir/file name, can be given multiple times to read several DSDL roots", "NAME");
     
     opts.optflag("", "data-type-signature", "inserts data type signatures");
     
-    opts.optopt("", "derive-default", "Set how the default trait should be derived", "<primitive-types>");
+    opts.optopt("", "derive-default", "Set how the default trait should be derived", "<primitive-types|all|none>");
+
+    opts.optmulti("", "derive", "derive a trait for every type it can be derived for, can be given multiple times", "TRAIT");
+
+    opts.optmulti("", "type-attribute", "add an attribute to the types matching a full name, `namespace.*` or `*`", "PATTERN=ATTRIBUTE");
+
+    opts.optflag("", "constant-enums", "compiles constant groups into enums with typed accessors");
+
+    opts.optflag("", "constructors", "compiles range checked constructors and builders taking native Rust types");
+
+    opts.optflag("", "serde", "derives serde's Serialize and Deserialize, requires the serde feature of uavcan");
+
+    opts.optflag("", "reflect", "implements uavcan's Reflect, describing the fields and constants of every type at runtime");
+
+    opts.optflag("", "text", "implements Display and FromStr with the YAML like text format of uavcan::text, implies --reflect");
+
+    opts.optflag("", "optional-fields", "compiles dynamic arrays holding at most one element (`T[<=1]`) into `Option<T>`");
+
+    opts.optflag("", "bit-arrays", "compiles arrays of bool into uavcan's BitArray and DynamicBitArray, which store the bits packed");
+
+    opts.optflag("", "arbitrary", "implements Arbitrary for every type (needs uavcan's arbitrary feature) and adds visit_arbitrary_types for round trip tests");
+    
+    opts.optopt("", "id-map", "assign or override data type IDs with a TOML file mapping full type names to IDs", "FILE");
+    
+    opts.optmulti("", "extern-path", "refer to types in a namespace with a Rust path instead of compiling them", "NAMESPACE=PATH");
+    
+    opts.optflag("", "module-per-file", "write every type to its own file, in a directory tree mirroring the namespaces, the output is then a directory");
+    
+    opts.optflag("", "namespace-features", "compile the types of every namespace behind a cargo feature");
+    
+    opts.optflag("", "cargo-features", "only print the `[features]` table declaring the features used by --namespace-features");
+    
+    opts.optflag("", "check", "only validate the DSDL, reporting every error without generating code");
+    
+    opts.optopt("", "example", "with `export`, the example registering the types to export with `Export::run`", "NAME");
+    
+    opts.optopt("", "manifest-path", "with `export`, the Cargo.toml of the crate containing the example, defaults to the crate in the current directory", "PATH");
     
     opts.optflag("", "version", "print the version of this software");
     opts.optflag("h", "help", "print this help menu");
@@ -29,7 +84,7 @@ pub(crate) fn print_usage() {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
     let opts = options();
-    let brief = format!("Usage: {} [options]", program);
+    let brief = format!("Usage: {0} [options]\n       {0} export --example NAME -o DIR [--manifest-path PATH]", program);
     print!("{}", opts.usage(&brief));
 }
 
@@ -50,10 +105,29 @@ impl InputFlags {
         };
 
         InputFlags{
-            input: matches.opt_str("i"),
+            inputs: matches.opt_strs("i"),
             output: matches.opt_str("o"),
             data_type_signature: matches.opt_present("data-type-signature"),
             derive_default: matches.opt_str("derive-default"),
+            derives: matches.opt_strs("derive"),
+            type_attributes: matches.opt_strs("type-attribute"),
+            constant_enums: matches.opt_present("constant-enums"),
+            constructors: matches.opt_present("constructors"),
+            serde: matches.opt_present("serde"),
+            reflect: matches.opt_present("reflect"),
+            text: matches.opt_present("text"),
+            optional_fields: matches.opt_present("optional-fields"),
+            bit_arrays: matches.opt_present("bit-arrays"),
+            arbitrary: matches.opt_present("arbitrary"),
+            id_map: matches.opt_str("id-map"),
+            extern_paths: matches.opt_strs("extern-path"),
+            check: matches.opt_present("check"),
+            module_per_file: matches.opt_present("module-per-file"),
+            namespace_features: matches.opt_present("namespace-features"),
+            cargo_features: matches.opt_present("cargo-features"),
+            export: matches.free.first().map(String::as_str) == Some("export"),
+            example: matches.opt_str("example"),
+            manifest_path: matches.opt_str("manifest-path"),
             help: matches.opt_present("h"),
             version: matches.opt_present("version"),
         }            
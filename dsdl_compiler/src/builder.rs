@@ -0,0 +1,276 @@
+//! Compiling DSDL from build scripts
+//!
+//! The `Builder` reads one or more DSDL directories, validates and compiles them, and writes the result to a file (or with `module_per_file`, a tree of files) in `OUT_DIR`.
+//! Every DSDL file that is read is reported to cargo with `cargo:rerun-if-changed`, so the bindings are regenerated when the DSDL changes.
+//!
+//! Types can refer to types in any of the included directories. Namespaces that are already compiled in another crate can be given with `extern_path`,
+//! they're still read (and must be included) but not compiled again.
+//!
+//! ```no_run
+//! // build.rs
+//! extern crate dsdl_compiler;
+//!
+//! use dsdl_compiler::Builder;
+//! use dsdl_compiler::CompileConfig;
+//!
+//! fn main() {
+//!     let mut config = CompileConfig::default();
+//!     config.data_type_signature = true;
+//!
+//!     Builder::new()
+//!         .include("dsdl")
+//!         .include("vendor_dsdl")
+//!         .extern_path("uavcan", "::dsdl::uavcan")
+//!         .root_namespace("com.example")
+//!         .config(config)
+//!         .compile()
+//!         .unwrap_or_else(|error| panic!("{}", error));
+//! }
+//! ```
+//!
+//! The crate then includes the generated code, this requires a dependency on `uavcan` (and on the crates given in `extern_path`).
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate uavcan;
+//! extern crate dsdl;
+//!
+//! include!(concat!(env!("OUT_DIR"), "/dsdl.rs"));
+//! ```
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use syn;
+
+use error::CompileError;
+use config::CompileConfig;
+use config::in_namespace;
+use validate::validate;
+use modules::{module_tree, write_files};
+use roots;
+use roots::ReadError;
+
+use super::{add_preamble, arbitrary_types_visitor, compile_files};
+
+/// The name of the file (in the output directory) the compiled DSDL is written to
+pub const OUTPUT_FILE_NAME: &str = "dsdl.rs";
+
+/// The name of the directory (in the output directory) the included directories are merged into, when there's more than one
+const MERGE_DIR_NAME: &str = "dsdl_roots";
+
+/// Compiles DSDL into a Rust file, meant to be used from build scripts
+#[derive(Default)]
+pub struct Builder {
+    includes: Vec<PathBuf>,
+    root_namespaces: Vec<String>,
+    extern_paths: Vec<(String, String)>,
+    derives: Vec<String>,
+    type_attributes: Vec<(String, String)>,
+    config: CompileConfig,
+    out_dir: Option<PathBuf>,
+    module_per_file: bool,
+}
+
+/// An error that occurred when building DSDL
+#[derive(Debug)]
+pub enum BuildError {
+    /// No output directory was given, and `OUT_DIR` is not set
+    MissingOutDir,
+
+    /// Reading DSDL or writing the output failed
+    Io(io::Error),
+
+    /// Reading the included directories failed
+    Read(ReadError),
+
+    /// The DSDL (in the included directory, or the directory they were merged into) contains errors
+    Compile(PathBuf, Vec<CompileError>),
+}
+
+impl Builder {
+    /// Creates a builder without any included DSDL, using `CompileConfig::default()`
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Includes a DSDL root directory, like the directory containing the `uavcan` namespace directory
+    pub fn include<P: AsRef<Path>>(&mut self, path: P) -> &mut Builder {
+        self.includes.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Only generates code for types in this namespace (or its nested namespaces), like `com.example`
+    ///
+    /// Can be called multiple times to generate code for more than one namespace. When no root namespace is given, code is generated for every type.
+    /// Types outside the root namespaces are still read and validated.
+    pub fn root_namespace<S: Into<String>>(&mut self, namespace: S) -> &mut Builder {
+        self.root_namespaces.push(namespace.into());
+        self
+    }
+
+    /// Uses the Rust path `path` for types in `namespace` (and its nested namespaces), instead of compiling them
+    ///
+    /// This is used when a namespace is already compiled in another crate, like `.extern_path("uavcan", "::dsdl::uavcan")`.
+    pub fn extern_path<S: Into<String>, T: Into<String>>(&mut self, namespace: S, path: T) -> &mut Builder {
+        self.extern_paths.push((namespace.into(), path.into()));
+        self
+    }
+
+    /// Derives `derive` for every type it can be derived for, like `.derive("PartialEq")`
+    ///
+    /// See `CompileConfig::derives` for which types a trait is skipped for.
+    pub fn derive<S: Into<String>>(&mut self, derive: S) -> &mut Builder {
+        self.derives.push(derive.into());
+        self
+    }
+
+    /// Adds `attribute` to the types matching `pattern`, like `.type_attribute("uavcan.protocol.*", "#[non_exhaustive]")`
+    ///
+    /// `pattern` is a full type name, a namespace followed by `.*`, or `*` for every type.
+    pub fn type_attribute<S: Into<String>, T: Into<String>>(&mut self, pattern: S, attribute: T) -> &mut Builder {
+        self.type_attributes.push((pattern.into(), attribute.into()));
+        self
+    }
+
+    /// Sets the configuration used when compiling
+    pub fn config(&mut self, config: CompileConfig) -> &mut Builder {
+        self.config = config;
+        self
+    }
+
+    /// Sets the directory the compiled DSDL is written to, defaults to `OUT_DIR`
+    pub fn out_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Builder {
+        self.out_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Writes every type to its own file, in a directory tree mirroring the namespaces, instead of writing everything to `dsdl.rs`
+    ///
+    /// `dsdl.rs` is still written, and is included the same way. See [`modules`](../modules/index.html) for the layout of the files.
+    pub fn module_per_file(&mut self, module_per_file: bool) -> &mut Builder {
+        self.module_per_file = module_per_file;
+        self
+    }
+
+    /// Compiles the included DSDL and writes it to `dsdl.rs` in the output directory, returning the path of the written file
+    pub fn compile(&self) -> Result<PathBuf, BuildError> {
+        let out_dir = match self.out_dir {
+            Some(ref out_dir) => out_dir.clone(),
+            None => env::var_os("OUT_DIR").map(PathBuf::from).ok_or(BuildError::MissingOutDir)?,
+        };
+
+        let mut config = self.config.clone();
+        config.extern_paths.extend(self.extern_paths.iter().cloned());
+        config.derives.extend(self.derives.iter().cloned());
+        config.type_attributes.extend(self.type_attributes.iter().cloned());
+
+        for include in &self.includes {
+            rerun_if_changed(include).map_err(BuildError::Io)?;
+        }
+        let dsdl = roots::read(&self.includes, &out_dir.join(MERGE_DIR_NAME)).map_err(BuildError::Read)?;
+        let root = if self.includes.len() == 1 { self.includes[0].clone() } else { out_dir.join(MERGE_DIR_NAME) };
+
+        validate(&dsdl, &config).map_err(|errors| BuildError::Compile(root.clone(), errors))?;
+        let out_path = out_dir.join(OUTPUT_FILE_NAME);
+
+        if self.module_per_file {
+            let files = module_tree(&dsdl, &config, |file| self.is_root_namespace(&file.name.namespace))
+                .map_err(|errors| BuildError::Compile(root, errors))?;
+            write_files(&out_dir, &files).map_err(BuildError::Io)?;
+        } else {
+            let mut items = compile_files(&dsdl, &config, |file| self.is_root_namespace(&file.name.namespace))
+                .map_err(|errors| BuildError::Compile(root.clone(), errors))?;
+            if config.arbitrary {
+                items.push(arbitrary_types_visitor(&dsdl, &config, |file| self.is_root_namespace(&file.name.namespace))
+                    .map_err(|error| BuildError::Compile(root, vec![error]))?);
+            }
+            add_preamble(&mut items);
+            write_items(&out_path, &items).map_err(BuildError::Io)?;
+        }
+
+        Ok(out_path)
+    }
+
+    fn is_root_namespace(&self, namespace: &str) -> bool {
+        self.root_namespaces.is_empty() || self.root_namespaces.iter().any(|root| in_namespace(namespace, root))
+    }
+}
+
+fn write_items(path: &Path, items: &[syn::Item]) -> io::Result<()> {
+    let tokens = quote!{#(#items)*};
+    File::create(path)?.write_all(tokens.as_str().as_bytes())
+}
+
+/// Tells cargo to rerun the build script when a DSDL file, or a directory containing them, changes
+fn rerun_if_changed(path: &Path) -> io::Result<()> {
+    println!("cargo:rerun-if-changed={}", path.display());
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() || entry_path.extension().is_some_and(|extension| extension == "uavcan") {
+                rerun_if_changed(&entry_path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::MissingOutDir => write!(f, "no output directory was given and `OUT_DIR` is not set"),
+            BuildError::Io(ref error) => write!(f, "{}", error),
+            BuildError::Read(ref error) => write!(f, "{}", error),
+            BuildError::Compile(ref include, ref errors) => {
+                write!(f, "failed to compile the DSDL in `{}`", include.display())?;
+                for error in errors {
+                    write!(f, "\n\n{}", error)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl ::std::error::Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_namespaces() {
+        assert!(in_namespace("com.example", "com.example"));
+        assert!(in_namespace("com.example.sensors", "com.example"));
+        assert!(!in_namespace("com.examples", "com.example"));
+        assert!(!in_namespace("com", "com.example"));
+
+        let mut builder = Builder::new();
+        assert!(builder.is_root_namespace("uavcan.protocol"));
+        builder.root_namespace("com.example").root_namespace("org.example");
+        assert!(builder.is_root_namespace("org.example.sensors"));
+        assert!(!builder.is_root_namespace("uavcan.protocol"));
+    }
+
+    #[test]
+    fn build_dsdl() {
+        let out_dir = env::temp_dir().join("dsdl_compiler_build_dsdl");
+        fs::create_dir_all(&out_dir).unwrap();
+
+        let out_path = Builder::new()
+            .include("tests/dsdl/")
+            .out_dir(&out_dir)
+            .compile()
+            .unwrap();
+
+        assert_eq!(out_path, out_dir.join(OUTPUT_FILE_NAME));
+        assert!(fs::read_to_string(&out_path).unwrap().contains("NodeStatus"));
+    }
+}
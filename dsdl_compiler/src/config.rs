@@ -1,14 +1,122 @@
 //! Configurable options relating to compilation
 
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
 use std::str::FromStr;
 
+use toml;
+
+use ident;
+
 /// Makes certain things in the compilation process configurable. `CompileConfig::default()` is generally safe to use.
+#[derive(Debug, Clone)]
 pub struct CompileConfig {
     /// Compile data type signatures for types `#[DataTypeSignature = "0x12345678"]`
     pub data_type_signature: bool,
     
     /// Sets strategy for deriving the `Default` trait
     pub derive_default: DeriveDefault,
+
+    /// Traits derived for the compiled types in addition to `Debug`, `Clone` and `UavcanStruct`, like `PartialEq`.
+    ///
+    /// A trait is only derived where it's valid, taking the types a type contains into account:
+    /// `Copy` is not derived for types containing dynamic arrays, and `Eq` and `Hash` are not derived for types containing floats.
+    pub derives: Vec<String>,
+
+    /// Attributes (like `#[repr(C)]`) added to the types whose full name matches a pattern, given as `(pattern, attribute)`.
+    ///
+    /// A pattern is either a full name (`uavcan.protocol.NodeStatus`), a namespace followed by `.*` (`uavcan.protocol.*`, matching the types in the namespace and its nested namespaces) or `*` (matching every type).
+    pub type_attributes: Vec<(String, String)>,
+
+    /// Compile groups of constants prefixed by the upper case name of a field (like `HEALTH_OK` for `uint2 health`)
+    /// into an enum, together with typed getters and setters for the field.
+    pub constant_enums: bool,
+
+    /// Compile a `new` constructor and a builder (like `NodeStatusBuilder`) for every struct, taking native Rust types
+    /// (`u8` for `uint2`, `&str` for `uint8[<=80]`, slices for arrays) and checking that the values fit the fields.
+    /// The generated code uses `FromNative` from the `uavcan` crate.
+    pub constructors: bool,
+
+    /// Derive `Serialize` and `Deserialize` for the compiled types, this requires `serde_derive` and the `serde` feature of the `uavcan` crate.
+    /// Fields of primitive types are serialized with `uavcan::serde_field` (`uint2` as `u8`, `float16` as `f32`, arrays as sequences),
+    /// void fields are skipped and unions are externally tagged enums. Fields and variants are named like in DSDL.
+    pub serde: bool,
+
+    /// Implement `uavcan::reflect::Reflect` for the compiled types, describing their fields and constants as they're defined in DSDL
+    /// and letting a `Visitor` walk the fields of a value.
+    pub reflect: bool,
+
+    /// Implement `Display` and `FromStr` for the compiled types with the YAML like text format of `uavcan::text`.
+    /// This also implements `uavcan::reflect::Reflect`, which the formatting is based on.
+    pub text: bool,
+
+    /// Compile dynamic arrays holding at most one element (`T[<=1]` and `T[<2]`, the way DSDL expresses optional values) into `Option<T>`
+    /// instead of `Dynamic<[T; 1]>`. `Option<T>` is serialized like the dynamic array, including tail array optimization.
+    pub optional_fields: bool,
+
+    /// Compile arrays of `bool` into `BitArray<[bool; N]>` and `DynamicBitArray<[bool; N]>`, which store the bits packed
+    /// and serialize them a word at a time. The wire format is the same as for `[bool; N]` and `Dynamic<[bool; N]>`.
+    pub bit_arrays: bool,
+
+    /// Implement `Arbitrary` for the compiled types, this requires the `arbitrary` feature of the `uavcan` crate.
+    /// A `visit_arbitrary_types` function visiting every compiled type is added as well, which lets a test send
+    /// arbitrary values of every type through the transport with `uavcan::arbitrary_field::check_round_trip`.
+    pub arbitrary: bool,
+
+    /// Data type IDs that are assigned to types without a default ID, or that override the default ID
+    pub type_ids: TypeIdMap,
+
+    /// Namespaces that are compiled in another crate, together with the Rust path of the module they're compiled into,
+    /// like `("uavcan", "::dsdl::uavcan")`. Types in these namespaces are not compiled, and composite types referring to them use the given path.
+    pub extern_paths: Vec<(String, String)>,
+
+    /// Compile the types of every namespace behind `#[cfg(feature = "...")]`, see [`features`](../features/index.html) for how the features are named
+    pub namespace_features: bool,
+}
+
+impl CompileConfig {
+    /// Returns the Rust path of the module a namespace is compiled into, if the namespace is compiled in another crate
+    ///
+    /// ```
+    /// use dsdl_compiler::CompileConfig;
+    ///
+    /// let mut config = CompileConfig::default();
+    /// config.extern_paths.push((String::from("uavcan"), String::from("::dsdl::uavcan")));
+    ///
+    /// assert_eq!(config.extern_path("uavcan.protocol"), Some(String::from("::dsdl::uavcan::protocol")));
+    /// assert_eq!(config.extern_path("com.example"), None);
+    /// ```
+    pub fn extern_path(&self, namespace: &str) -> Option<String> {
+        self.extern_paths.iter()
+            .filter(|(extern_namespace, _)| in_namespace(namespace, extern_namespace))
+            .max_by_key(|(extern_namespace, _)| extern_namespace.len())
+            .map(|(extern_namespace, path)| {
+                namespace[extern_namespace.len()..].split('.')
+                    .filter(|segment| !segment.is_empty())
+                    .fold(path.clone(), |path, segment| format!("{}::{}", path, ident::escape(segment)))
+            })
+    }
+}
+
+/// Returns true if `namespace` is `root` or nested inside it
+pub(crate) fn in_namespace(namespace: &str, root: &str) -> bool {
+    namespace == root || (namespace.starts_with(root) && namespace[root.len()..].starts_with('.'))
+}
+
+/// Returns true if the type with the given full name matches a pattern in `type_attributes`
+pub(crate) fn matches_type_pattern(full_name: &str, pattern: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match (pattern.strip_suffix(".*"), full_name.rfind('.')) {
+        (Some(namespace), Some(name_start)) => in_namespace(&full_name[..name_start], namespace),
+        (Some(_), None) => false,
+        (None, _) => full_name == pattern,
+    }
 }
 
 impl Default for CompileConfig {
@@ -16,6 +124,19 @@ impl Default for CompileConfig {
         CompileConfig {
             data_type_signature: false,
             derive_default: DeriveDefault::default(),
+            derives: Vec::new(),
+            type_attributes: Vec::new(),
+            constant_enums: false,
+            constructors: false,
+            serde: false,
+            reflect: false,
+            text: false,
+            optional_fields: false,
+            bit_arrays: false,
+            arbitrary: false,
+            type_ids: TypeIdMap::default(),
+            extern_paths: Vec::new(),
+            namespace_features: false,
         }
     }
 }
@@ -26,6 +147,12 @@ pub enum DeriveDefault {
 
     /// Derive default for structs only when the struct consists of primitive types or arrays of primitive types.
     PrimitiveTypes,
+
+    /// Implement default for every type. Structs default every field, unions default to their first variant.
+    All,
+
+    /// Don't implement default for any type.
+    None,
 }
 
 pub enum ParseDeriveDefaultError {
@@ -44,7 +171,147 @@ impl FromStr for DeriveDefault {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s{
             "primitive-types" => Ok(DeriveDefault::PrimitiveTypes),
+            "all" => Ok(DeriveDefault::All),
+            "none" => Ok(DeriveDefault::None),
             _ => Err(ParseDeriveDefaultError::NotVariant),
         }
     }
 }
+
+
+/// Data type IDs given by the full name of the type, like `uavcan.protocol.NodeStatus`
+///
+/// The map is usually read from a TOML file, where names can be given either in full or as keys in a table named after the namespace.
+///
+/// ```toml
+/// "uavcan.protocol.NodeStatus" = 341
+///
+/// [com.example]
+/// Status = 20000
+/// SetMode = 200
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TypeIdMap(BTreeMap<String, u64>);
+
+/// An error that occurred when reading a `TypeIdMap`
+#[derive(Debug)]
+pub enum ParseTypeIdMapError {
+    /// The file couldn't be read
+    Io(io::Error),
+
+    /// The file is not valid TOML
+    Toml(toml::de::Error),
+
+    /// A type (full name) that's not mapped to a positive integer
+    InvalidId(String),
+}
+
+impl TypeIdMap {
+    /// Creates an empty map
+    pub fn new() -> TypeIdMap {
+        TypeIdMap(BTreeMap::new())
+    }
+
+    /// Assigns `id` to the type with the given full name, returning the ID that was replaced
+    pub fn insert<T: Into<String>>(&mut self, full_name: T, id: u64) -> Option<u64> {
+        self.0.insert(full_name.into(), id)
+    }
+
+    /// Returns the ID assigned to the type with the given full name
+    pub fn get(&self, full_name: &str) -> Option<u64> {
+        self.0.get(full_name).cloned()
+    }
+
+    /// Iterates over the full names and IDs in the map, ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.0.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+
+    /// Reads a map from a TOML file
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<TypeIdMap, ParseTypeIdMapError> {
+        let mut content = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut content)).map_err(ParseTypeIdMapError::Io)?;
+        content.parse()
+    }
+
+    fn insert_table(&mut self, namespace: Option<&str>, table: toml::value::Table) -> Result<(), ParseTypeIdMapError> {
+        for (key, value) in table {
+            let name = match namespace {
+                Some(namespace) => format!("{}.{}", namespace, key),
+                None => key,
+            };
+            match value {
+                toml::Value::Table(table) => self.insert_table(Some(&name), table)?,
+                toml::Value::Integer(id) if id >= 0 => { self.insert(name, id as u64); },
+                _ => return Err(ParseTypeIdMapError::InvalidId(name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TypeIdMap {
+    type Err = ParseTypeIdMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table = s.parse::<toml::Value>().map_err(ParseTypeIdMapError::Toml)?;
+        let mut map = TypeIdMap::new();
+        match table {
+            toml::Value::Table(table) => map.insert_table(None, table)?,
+            _ => unreachable!("TOML documents are always tables"),
+        }
+        Ok(map)
+    }
+}
+
+impl fmt::Display for ParseTypeIdMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseTypeIdMapError::Io(ref error) => write!(f, "failed to read type ID map: {}", error),
+            ParseTypeIdMapError::Toml(ref error) => write!(f, "failed to parse type ID map: {}", error),
+            ParseTypeIdMapError::InvalidId(ref name) => write!(f, "the ID of `{}` is not a positive integer", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_type_id_map() {
+        let map: TypeIdMap = "
+            \"uavcan.protocol.NodeStatus\" = 341
+
+            [com.example]
+            Status = 20000
+            SetMode = 200
+        ".parse().unwrap();
+
+        assert_eq!(map.get("uavcan.protocol.NodeStatus"), Some(341));
+        assert_eq!(map.get("com.example.Status"), Some(20000));
+        assert_eq!(map.get("com.example.SetMode"), Some(200));
+        assert_eq!(map.get("com.example.Unknown"), None);
+        assert_eq!(map.iter().count(), 3);
+
+        match "\"com.example.Status\" = -1".parse::<TypeIdMap>() {
+            Err(ParseTypeIdMapError::InvalidId(name)) => assert_eq!(name, "com.example.Status"),
+            _ => panic!("negative IDs are invalid"),
+        }
+        match "\"com.example.Status\" = \"high\"".parse::<TypeIdMap>() {
+            Err(ParseTypeIdMapError::InvalidId(name)) => assert_eq!(name, "com.example.Status"),
+            _ => panic!("IDs must be integers"),
+        }
+    }
+
+    #[test]
+    fn type_patterns() {
+        assert!(matches_type_pattern("uavcan.protocol.NodeStatus", "*"));
+        assert!(matches_type_pattern("uavcan.protocol.NodeStatus", "uavcan.protocol.NodeStatus"));
+        assert!(matches_type_pattern("uavcan.protocol.NodeStatus", "uavcan.protocol.*"));
+        assert!(matches_type_pattern("uavcan.protocol.debug.LogMessage", "uavcan.*"));
+        assert!(!matches_type_pattern("uavcan.protocol.NodeStatus", "uavcan.protocol.debug.*"));
+        assert!(!matches_type_pattern("uavcan.protocols.NodeStatus", "uavcan.protocol.*"));
+        assert!(!matches_type_pattern("uavcan.protocol.NodeStatus", "uavcan.protocol.Node"));
+    }
+}
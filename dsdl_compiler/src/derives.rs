@@ -0,0 +1,90 @@
+//! Choosing the traits in `CompileConfig::derives` that can be derived for a type
+//!
+//! Some traits can't be derived for every type: `Copy` can't be derived for types containing dynamic arrays (which implement `Drop`),
+//! and `Eq` and `Hash` can't be derived for types containing floats. A type contains the fields of the composite types it uses, so these are looked into as well.
+
+use dsdl_parser;
+use dsdl_parser::DSDL;
+
+/// What a definition contains, including the definitions of the composite types it uses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Contents {
+    floats: bool,
+    dynamic_arrays: bool,
+}
+
+fn contents(dsdl: &DSDL, namespace: &str, definition: &dsdl_parser::MessageDefinition, visited: &mut Vec<String>) -> Contents {
+    let mut contents = Contents::default();
+    for line in &definition.0 {
+        let field = match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref field), ..} => field,
+            _ => continue,
+        };
+
+        if let Some(dsdl_parser::ArrayInfo::DynamicLess(_)) | Some(dsdl_parser::ArrayInfo::DynamicLeq(_)) = field.array {
+            contents.dynamic_arrays = true;
+        }
+
+        match field.field_type {
+            dsdl_parser::Ty::Primitive(dsdl_parser::PrimitiveType::Float16) |
+            dsdl_parser::Ty::Primitive(dsdl_parser::PrimitiveType::Float32) |
+            dsdl_parser::Ty::Primitive(dsdl_parser::PrimitiveType::Float64) => contents.floats = true,
+            dsdl_parser::Ty::Composite(ref composite) => {
+                let composite_namespace = composite.namespace.as_ref().map_or(namespace, |namespace| namespace.as_ref());
+                let full_name = format!("{}.{}", composite_namespace, composite.name.as_ref());
+                // a type that's already being looked into would only repeat what's found there
+                if visited.contains(&full_name) {
+                    continue;
+                }
+                visited.push(full_name.clone());
+                if let Some(&dsdl_parser::File{definition: dsdl_parser::TypeDefinition::Message(ref nested), ..}) = dsdl.get_file(&full_name) {
+                    let nested_contents = self::contents(dsdl, composite_namespace, nested, visited);
+                    contents.floats |= nested_contents.floats;
+                    contents.dynamic_arrays |= nested_contents.dynamic_arrays;
+                }
+            },
+            _ => (),
+        }
+    }
+    contents
+}
+
+/// Returns the traits in `derives` that can be derived for a definition in `namespace`
+pub(crate) fn valid_derives(dsdl: &DSDL, namespace: &str, definition: &dsdl_parser::MessageDefinition, derives: &[String]) -> Vec<String> {
+    let contents = contents(dsdl, namespace, definition, &mut Vec::new());
+    derives.iter()
+        .filter(|derive| match derive.as_str() {
+            "Copy" => !contents.dynamic_arrays,
+            "Eq" | "Hash" => !contents.floats,
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_derives(dsdl: &DSDL, full_name: &str) -> Vec<String> {
+        let derives: Vec<String> = ["PartialEq", "Copy", "Eq", "Hash"].iter().map(|derive| String::from(*derive)).collect();
+        let file = dsdl.get_file(full_name).unwrap();
+        match file.definition {
+            dsdl_parser::TypeDefinition::Message(ref message) => valid_derives(dsdl, &file.name.namespace, message, &derives),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn derives_for_contents() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+
+        assert_eq!(message_derives(&dsdl, "uavcan.protocol.NodeStatus"), vec!["PartialEq", "Copy", "Eq", "Hash"]);
+        // `source` and `text` are dynamic arrays
+        assert_eq!(message_derives(&dsdl, "uavcan.protocol.debug.LogMessage"), vec!["PartialEq", "Eq", "Hash"]);
+        // `command_value` is a float
+        assert_eq!(message_derives(&dsdl, "uavcan.equipment.actuator.Command"), vec!["PartialEq", "Copy"]);
+        // the floats are in the nested `Command`
+        assert_eq!(message_derives(&dsdl, "uavcan.equipment.actuator.ArrayCommand"), vec!["PartialEq"]);
+    }
+}
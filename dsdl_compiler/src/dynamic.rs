@@ -0,0 +1,556 @@
+//! Decoding transfers into a tree of values, using the DSDL definitions at runtime instead of compiled types
+//!
+//! This is meant for tools like bus sniffers, where the types are only known from a DSDL directory when the program runs.
+//! The payload is read with the same `DeserializationBuffer` as the compiled types use, and the result is decoded bit for bit
+//! like a compiled type would be: void fields are skipped, unions are decoded from their tag, and tail array optimization
+//! is applied to the last field of the top level type (and the last field of a composite type that is itself the last field).
+//!
+//! ```
+//! use dsdl_compiler::DSDL;
+//! use dsdl_compiler::dynamic::{Decoder, DynamicValue};
+//!
+//! let dsdl = DSDL::read("tests/dsdl/").unwrap();
+//! let decoder = Decoder::new(&dsdl);
+//!
+//! // `uavcan.protocol.NodeStatus`
+//! let status = decoder.decode_message(341, &[1, 0, 0, 0, 0b10011100, 5, 0]).unwrap();
+//! assert_eq!(status.field("uptime_sec"), Some(&DynamicValue::Unsigned(1)));
+//! assert_eq!(status.field("mode"), Some(&DynamicValue::Unsigned(3)));
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use dsdl_parser;
+use dsdl_parser::DSDL;
+use half::f16;
+use uavcan::DeserializationBuffer;
+
+use config::TypeIdMap;
+use validate::{bits_needed, primitive_bit_length};
+use super::primitive_type_name;
+use Compile;
+use CompileConfig;
+
+/// A value decoded without a compiled type
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+
+    /// The value of an unsigned integer (`uintN`)
+    Unsigned(u64),
+
+    /// The value of a signed integer (`intN`)
+    Signed(i64),
+
+    /// The value of a float (`floatN`), converted to `f64`
+    Float(f64),
+
+    /// The active field of a union, when it's a void field
+    Void,
+
+    /// The elements of a static or dynamic array
+    Array(Vec<DynamicValue>),
+
+    /// The fields of a composite type by their DSDL name, in the order they're defined. Void fields are left out.
+    Struct(Vec<(String, DynamicValue)>),
+
+    /// The DSDL name of the active field of a union (empty for void fields) and its value
+    Union(String, Box<DynamicValue>),
+}
+
+impl DynamicValue {
+    /// Returns the field with the given DSDL name, if this is a composite value that has one
+    pub fn field(&self, name: &str) -> Option<&DynamicValue> {
+        match *self {
+            DynamicValue::Struct(ref fields) => fields.iter().find(|(field_name, _)| field_name == name).map(|(_, value)| value),
+            DynamicValue::Union(ref field_name, ref value) if field_name == name => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// An error that occurred when decoding a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// No message type has this data type ID
+    UnknownMessageId(u16),
+
+    /// No service type has this data type ID
+    UnknownServiceId(u8),
+
+    /// A type (full name) that doesn't exist in the DSDL tree, or that is a service where a message was expected
+    UnknownType(String),
+
+    /// The payload ended in the middle of a field
+    UnexpectedEnd,
+
+    /// A union tag (and the number of fields in the union) that doesn't select a field
+    InvalidUnionTag(u64, usize),
+
+    /// A dynamic array (field name) with a length prefix (the first value) that is larger than its maximum length (the second value)
+    InvalidArrayLength(String, u64, u64),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnknownMessageId(id) => write!(f, "no message has data type ID {}", id),
+            DecodeError::UnknownServiceId(id) => write!(f, "no service has data type ID {}", id),
+            DecodeError::UnknownType(ref name) => write!(f, "cannot find message type `{}`", name),
+            DecodeError::UnexpectedEnd => write!(f, "the payload ended in the middle of a field"),
+            DecodeError::InvalidUnionTag(tag, fields) => write!(f, "union tag {} is invalid, the union has {} fields", tag, fields),
+            DecodeError::InvalidArrayLength(ref name, length, max) => write!(f, "array `{}` has length {}, it can hold at most {} elements", name, length, max),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Decodes transfers with the definitions in a DSDL tree
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    dsdl: &'a DSDL,
+    type_ids: TypeIdMap,
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a decoder finding types by their default data type ID
+    pub fn new(dsdl: &'a DSDL) -> Decoder<'a> {
+        Decoder {
+            dsdl,
+            type_ids: TypeIdMap::new(),
+        }
+    }
+
+    /// Assigns or overrides data type IDs, like `CompileConfig::type_ids`
+    pub fn type_ids(mut self, type_ids: TypeIdMap) -> Decoder<'a> {
+        self.type_ids = type_ids;
+        self
+    }
+
+    /// Decodes the payload of a message transfer
+    pub fn decode_message(&self, type_id: u16, payload: &[u8]) -> Result<DynamicValue, DecodeError> {
+        match self.find_type(u64::from(type_id), false) {
+            Some(&dsdl_parser::File{ref name, definition: dsdl_parser::TypeDefinition::Message(ref message)}) => self.decode_definition(&name.namespace, message, payload),
+            _ => Err(DecodeError::UnknownMessageId(type_id)),
+        }
+    }
+
+    /// Decodes the payload of a service request transfer
+    pub fn decode_request(&self, type_id: u8, payload: &[u8]) -> Result<DynamicValue, DecodeError> {
+        match self.find_type(u64::from(type_id), true) {
+            Some(&dsdl_parser::File{ref name, definition: dsdl_parser::TypeDefinition::Service(ref service)}) => self.decode_definition(&name.namespace, &service.request, payload),
+            _ => Err(DecodeError::UnknownServiceId(type_id)),
+        }
+    }
+
+    /// Decodes the payload of a service response transfer
+    pub fn decode_response(&self, type_id: u8, payload: &[u8]) -> Result<DynamicValue, DecodeError> {
+        match self.find_type(u64::from(type_id), true) {
+            Some(&dsdl_parser::File{ref name, definition: dsdl_parser::TypeDefinition::Service(ref service)}) => self.decode_definition(&name.namespace, &service.response, payload),
+            _ => Err(DecodeError::UnknownServiceId(type_id)),
+        }
+    }
+
+    /// Decodes a payload as the message type with the given full name, like `uavcan.protocol.NodeStatus`
+    pub fn decode(&self, full_name: &str, payload: &[u8]) -> Result<DynamicValue, DecodeError> {
+        match self.dsdl.get_file(full_name) {
+            Some(&dsdl_parser::File{ref name, definition: dsdl_parser::TypeDefinition::Message(ref message)}) => self.decode_definition(&name.namespace, message, payload),
+            _ => Err(DecodeError::UnknownType(String::from(full_name))),
+        }
+    }
+
+    /// Decodes a payload as a definition in `namespace`, which composite types without a namespace are looked up in
+    pub fn decode_definition(&self, namespace: &str, definition: &dsdl_parser::MessageDefinition, payload: &[u8]) -> Result<DynamicValue, DecodeError> {
+        let mut data = payload.to_vec();
+        let mut buffer = DeserializationBuffer::with_full_buffer(&mut data);
+        // like the compiled types, the top level type is decoded as the last field
+        self.decode_composite(namespace, definition, true, &mut buffer)
+    }
+
+    fn find_type(&self, type_id: u64, service: bool) -> Option<&'a dsdl_parser::File> {
+        self.dsdl.files().into_iter().find(|file| {
+            let is_service = matches!(file.definition, dsdl_parser::TypeDefinition::Service(_));
+            let id = match self.type_ids.get(&format!("{}", file.name)) {
+                Some(id) => Some(id),
+                None => file.name.id.as_ref().and_then(|id| id.parse().ok()),
+            };
+            is_service == service && id == Some(type_id)
+        })
+    }
+
+    fn find_composite(&self, namespace: &str, composite: &dsdl_parser::CompositeType) -> Result<(&'a str, &'a dsdl_parser::MessageDefinition), DecodeError> {
+        let composite_namespace = composite.namespace.as_ref().map_or(namespace, |namespace| namespace.as_ref());
+        let full_name = format!("{}.{}", composite_namespace, composite.name.as_ref());
+        match self.dsdl.get_file(&full_name) {
+            Some(&dsdl_parser::File{ref name, definition: dsdl_parser::TypeDefinition::Message(ref message)}) => Ok((&name.namespace, message)),
+            _ => Err(DecodeError::UnknownType(full_name)),
+        }
+    }
+
+    /// Decodes a composite type, `tail` is true when it's the last field all the way up to the top level type
+    fn decode_composite(&self, namespace: &str, definition: &dsdl_parser::MessageDefinition, tail: bool, buffer: &mut DeserializationBuffer) -> Result<DynamicValue, DecodeError> {
+        let fields = fields(definition);
+
+        if is_union(definition) {
+            let tag = read_bits(buffer, bits_needed(fields.len().saturating_sub(1) as u64) as usize)?;
+            let field = fields.get(tag as usize).ok_or(DecodeError::InvalidUnionTag(tag, fields.len()))?;
+            let value = self.decode_field(namespace, field, tail, buffer)?;
+            return Ok(DynamicValue::Union(field_name(field), Box::new(value)));
+        }
+
+        let mut values = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let value = self.decode_field(namespace, field, tail && i == fields.len() - 1, buffer)?;
+            if !field.field_type.is_void() {
+                values.push((field_name(field), value));
+            }
+        }
+        Ok(DynamicValue::Struct(values))
+    }
+
+    fn decode_field(&self, namespace: &str, field: &dsdl_parser::FieldDefinition, tail: bool, buffer: &mut DeserializationBuffer) -> Result<DynamicValue, DecodeError> {
+        let max_length = match field.array {
+            None => return self.decode_type(namespace, &field.field_type, tail, buffer),
+            Some(dsdl_parser::ArrayInfo::Static(length)) => {
+                let elements = (0..length).map(|_| self.decode_type(namespace, &field.field_type, false, buffer)).collect::<Result<_, _>>()?;
+                return Ok(DynamicValue::Array(elements));
+            },
+            Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => size.saturating_sub(1),
+            Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => size,
+        };
+
+        let mut elements = Vec::new();
+        // the length of the last array is left out when its elements are at least a byte long, it then ends with the payload
+        if tail && self.min_bit_length(namespace, &field.field_type, &mut Vec::new()) >= 8 {
+            while (elements.len() as u64) < max_length && buffer.bit_length() > 0 {
+                match self.decode_type(namespace, &field.field_type, false, buffer) {
+                    Ok(element) => elements.push(element),
+                    // the bits padding the payload to a whole byte are not an element
+                    Err(DecodeError::UnexpectedEnd) => break,
+                    Err(error) => return Err(error),
+                }
+            }
+        } else {
+            let length = read_bits(buffer, bits_needed(max_length) as usize)?;
+            if length > max_length {
+                return Err(DecodeError::InvalidArrayLength(field_name(field), length, max_length));
+            }
+            for _ in 0..length {
+                elements.push(self.decode_type(namespace, &field.field_type, false, buffer)?);
+            }
+        }
+        Ok(DynamicValue::Array(elements))
+    }
+
+    fn decode_type(&self, namespace: &str, ty: &dsdl_parser::Ty, tail: bool, buffer: &mut DeserializationBuffer) -> Result<DynamicValue, DecodeError> {
+        match *ty {
+            dsdl_parser::Ty::Primitive(ref primitive_type) => decode_primitive(primitive_type, buffer),
+            dsdl_parser::Ty::Composite(ref composite) => {
+                let (composite_namespace, definition) = self.find_composite(namespace, composite)?;
+                self.decode_composite(composite_namespace, definition, tail, buffer)
+            },
+        }
+    }
+
+    /// Returns the smallest number of bits a type can be encoded into, used to decide if tail array optimization applies
+    ///
+    /// `visiting` guards against recursive definitions, like in `validate`.
+    fn min_bit_length(&self, namespace: &str, ty: &dsdl_parser::Ty, visiting: &mut Vec<String>) -> u64 {
+        let composite = match *ty {
+            dsdl_parser::Ty::Primitive(ref primitive_type) => return primitive_bit_length(primitive_type),
+            dsdl_parser::Ty::Composite(ref composite) => composite,
+        };
+        let (composite_namespace, definition) = match self.find_composite(namespace, composite) {
+            Ok(found) => found,
+            Err(_) => return 0,
+        };
+        let full_name = format!("{}.{}", composite_namespace, composite.name.as_ref());
+        if visiting.contains(&full_name) {
+            return 0;
+        }
+        visiting.push(full_name);
+
+        let fields = fields(definition);
+        let field_lengths: Vec<u64> = fields.iter()
+            .map(|field| {
+                let element_length = self.min_bit_length(composite_namespace, &field.field_type, visiting);
+                match field.array {
+                    None => element_length,
+                    Some(dsdl_parser::ArrayInfo::Static(length)) => element_length.saturating_mul(length),
+                    Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => bits_needed(size.saturating_sub(1)),
+                    Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => bits_needed(size),
+                }
+            })
+            .collect();
+        visiting.pop();
+
+        if is_union(definition) {
+            bits_needed(fields.len().saturating_sub(1) as u64) + field_lengths.into_iter().min().unwrap_or(0)
+        } else {
+            field_lengths.into_iter().fold(0, u64::saturating_add)
+        }
+    }
+}
+
+fn fields(definition: &dsdl_parser::MessageDefinition) -> Vec<&dsdl_parser::FieldDefinition> {
+    definition.0.iter()
+        .filter_map(|line| match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref field), ..} => Some(field),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_union(definition: &dsdl_parser::MessageDefinition) -> bool {
+    definition.0.iter().any(|line| matches!(*line, dsdl_parser::Line::Directive{directive: dsdl_parser::Directive::Union, ..}))
+}
+
+fn field_name(field: &dsdl_parser::FieldDefinition) -> String {
+    field.name.as_ref().map_or_else(String::new, |name| String::from(name.as_ref()))
+}
+
+fn read_bits(buffer: &mut DeserializationBuffer, bit_length: usize) -> Result<u64, DecodeError> {
+    if bit_length > buffer.bit_length() {
+        Err(DecodeError::UnexpectedEnd)
+    } else if bit_length == 0 {
+        Ok(0)
+    } else {
+        Ok(buffer.pop_bits(bit_length))
+    }
+}
+
+fn decode_primitive(primitive_type: &dsdl_parser::PrimitiveType, buffer: &mut DeserializationBuffer) -> Result<DynamicValue, DecodeError> {
+    let bit_length = primitive_bit_length(primitive_type) as usize;
+    let bits = read_bits(buffer, bit_length)?;
+    let ty = (*primitive_type).compile(&CompileConfig::default()).expect("Primitive types always compile");
+    let type_name = primitive_type_name(&ty);
+    Ok(match type_name.chars().next() {
+        _ if type_name == "bool" => DynamicValue::Bool(bits != 0),
+        _ if type_name.starts_with("void") => DynamicValue::Void,
+        Some('u') => DynamicValue::Unsigned(bits),
+        // the sign bit is the highest of the `bit_length` bits
+        Some('i') => DynamicValue::Signed(((bits << (64 - bit_length)) as i64) >> (64 - bit_length)),
+        _ => DynamicValue::Float(match bit_length {
+            16 => f16::from_bits(bits as u16).to_f64(),
+            32 => f64::from(f32::from_bits(bits as u32)),
+            _ => f64::from_bits(bits),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uavcan::types::*;
+    use uavcan::{Serializable, SerializationBuffer, SerializationResult, Struct};
+
+    use dsdl_parser::{Line, AttributeDefinition, Ty, PrimitiveType, ArrayInfo};
+
+    fn field(field_type: PrimitiveType, array: Option<ArrayInfo>, name: Option<&str>) -> Line {
+        Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(field_type),
+                array,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        }
+    }
+
+    /// Encodes a value with its compiled type, and decodes the payload both with the compiled type and dynamically
+    fn cross_check<T: Struct + Default>(value: &T, definition: &dsdl_parser::MessageDefinition) -> (Vec<u8>, T, DynamicValue) {
+        let mut data = [0u8; 64];
+        let bit_length = {
+            let mut buffer = SerializationBuffer::with_empty_buffer(&mut data);
+            assert_eq!(value.serialize(&mut 0, &mut 0, true, &mut buffer), SerializationResult::Finished);
+            buffer.bit_length()
+        };
+        let mut payload = data[..bit_length.div_ceil(8)].to_vec();
+
+        let dynamic = Decoder::new(&DSDL::read("tests/dsdl/").unwrap()).decode_definition("com.example", definition, &payload).unwrap();
+
+        let mut compiled = T::default();
+        {
+            let mut buffer = DeserializationBuffer::with_full_buffer(&mut payload);
+            compiled.deserialize(&mut 0, &mut 0, true, &mut buffer);
+        }
+        (data[..bit_length.div_ceil(8)].to_vec(), compiled, dynamic)
+    }
+
+    #[test]
+    fn decode_primitives() {
+        #[derive(Debug, Clone, Default, PartialEq, UavcanStruct)]
+        struct Sample {
+            uptime_sec: u32,
+            health: u2,
+            _v0: void3,
+            offset: i3,
+            small: i13,
+            enabled: bool,
+            gain: f16,
+            ratio: f32,
+            scale: f64,
+        }
+        let definition = dsdl_parser::MessageDefinition(vec![
+            field(PrimitiveType::Uint32, None, Some("uptime_sec")),
+            field(PrimitiveType::Uint2, None, Some("health")),
+            field(PrimitiveType::Void3, None, None),
+            field(PrimitiveType::Int3, None, Some("offset")),
+            field(PrimitiveType::Int13, None, Some("small")),
+            field(PrimitiveType::Bool, None, Some("enabled")),
+            field(PrimitiveType::Float16, None, Some("gain")),
+            field(PrimitiveType::Float32, None, Some("ratio")),
+            field(PrimitiveType::Float64, None, Some("scale")),
+        ]);
+        let value = Sample{
+            uptime_sec: 120, health: u2::new(2), _v0: void3{}, offset: i3::new(-4), small: i13::new(-1000),
+            enabled: true, gain: f16::from_f32(-0.5), ratio: 1.25, scale: 1e-3,
+        };
+
+        let (_, compiled, dynamic) = cross_check(&value, &definition);
+        assert_eq!(compiled, value);
+        assert_eq!(dynamic, DynamicValue::Struct(vec![
+            (String::from("uptime_sec"), DynamicValue::Unsigned(u64::from(compiled.uptime_sec))),
+            (String::from("health"), DynamicValue::Unsigned(u64::from(compiled.health))),
+            (String::from("offset"), DynamicValue::Signed(i64::from(compiled.offset))),
+            (String::from("small"), DynamicValue::Signed(i64::from(compiled.small))),
+            (String::from("enabled"), DynamicValue::Bool(compiled.enabled)),
+            (String::from("gain"), DynamicValue::Float(compiled.gain.to_f64())),
+            (String::from("ratio"), DynamicValue::Float(f64::from(compiled.ratio))),
+            (String::from("scale"), DynamicValue::Float(compiled.scale)),
+        ]));
+    }
+
+    #[test]
+    fn decode_arrays() {
+        #[derive(Debug, Clone, Default, PartialEq, UavcanStruct)]
+        struct Sample {
+            gains: [u3; 3],
+            flags: Dynamic<[bool; 3]>,
+            text: Dynamic<[u8; 8]>,
+        }
+        let definition = dsdl_parser::MessageDefinition(vec![
+            field(PrimitiveType::Uint3, Some(ArrayInfo::Static(3)), Some("gains")),
+            field(PrimitiveType::Bool, Some(ArrayInfo::DynamicLess(4)), Some("flags")),
+            field(PrimitiveType::Uint8, Some(ArrayInfo::DynamicLeq(8)), Some("text")),
+        ]);
+        let value = Sample{
+            gains: [u3::new(1), u3::new(5), u3::new(7)],
+            flags: Dynamic::<[bool; 3]>::with_data(&[true, false]),
+            text: Dynamic::<[u8; 8]>::with_data(b"uavcan"),
+        };
+
+        let (payload, compiled, dynamic) = cross_check(&value, &definition);
+        // 9 bits of gains, 2 bits of length and 2 flags, then `text` without its length
+        assert_eq!(payload.len(), 2 + 6);
+        assert_eq!(compiled, value);
+        let unsigned = |values: &[u64]| DynamicValue::Array(values.iter().map(|value| DynamicValue::Unsigned(*value)).collect());
+        assert_eq!(dynamic, DynamicValue::Struct(vec![
+            (String::from("gains"), unsigned(&compiled.gains.iter().map(|gain| u64::from(*gain)).collect::<Vec<_>>())),
+            (String::from("flags"), DynamicValue::Array(compiled.flags.iter().map(|flag| DynamicValue::Bool(*flag)).collect())),
+            (String::from("text"), unsigned(&compiled.text.iter().map(|c| u64::from(*c)).collect::<Vec<_>>())),
+        ]));
+
+        // arrays of elements shorter than a byte keep their length, even as the last field
+        #[derive(Debug, Clone, Default, PartialEq, UavcanStruct)]
+        struct Flags {
+            count: u8,
+            flags: Dynamic<[bool; 5]>,
+        }
+        let definition = dsdl_parser::MessageDefinition(vec![
+            field(PrimitiveType::Uint8, None, Some("count")),
+            field(PrimitiveType::Bool, Some(ArrayInfo::DynamicLeq(5)), Some("flags")),
+        ]);
+        let value = Flags{count: 3, flags: Dynamic::<[bool; 5]>::with_data(&[true, true, false])};
+        let (_, compiled, dynamic) = cross_check(&value, &definition);
+        assert_eq!(compiled, value);
+        assert_eq!(dynamic.field("flags"), Some(&DynamicValue::Array(vec![DynamicValue::Bool(true), DynamicValue::Bool(true), DynamicValue::Bool(false)])));
+    }
+
+    #[test]
+    fn decode_unions() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+        let decoder = Decoder::new(&dsdl);
+        let definition = dsdl_parser::MessageDefinition(vec![
+            Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+            field(PrimitiveType::Uint8, None, Some("integer")),
+            field(PrimitiveType::Void4, None, None),
+            field(PrimitiveType::Uint8, Some(ArrayInfo::DynamicLeq(4)), Some("text")),
+        ]);
+
+        // the tag is 2 bits, followed by the value of the active field
+        assert_eq!(
+            decoder.decode_definition("com.example", &definition, &[0b0000_1010, 0b0000_0000]),
+            Ok(DynamicValue::Union(String::from("integer"), Box::new(DynamicValue::Unsigned(10))))
+        );
+        assert_eq!(
+            decoder.decode_definition("com.example", &definition, &[0b0100_0000]),
+            Ok(DynamicValue::Union(String::new(), Box::new(DynamicValue::Void)))
+        );
+        // the array is the last field, so it ends with the payload
+        assert_eq!(
+            decoder.decode_definition("com.example", &definition, &[0b1000_0001, 0b0000_0000]),
+            Ok(DynamicValue::Union(String::from("text"), Box::new(DynamicValue::Array(vec![DynamicValue::Unsigned(1)]))))
+        );
+        assert_eq!(
+            decoder.decode_definition("com.example", &definition, &[0b1100_0000]),
+            Err(DecodeError::InvalidUnionTag(3, 3))
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+        let decoder = Decoder::new(&dsdl);
+        let definition = dsdl_parser::MessageDefinition(vec![
+            field(PrimitiveType::Bool, Some(ArrayInfo::DynamicLeq(2)), Some("flags")),
+            field(PrimitiveType::Uint16, None, Some("value")),
+        ]);
+
+        assert_eq!(decoder.decode_definition("com.example", &definition, &[0b1100_0000]), Err(DecodeError::InvalidArrayLength(String::from("flags"), 3, 2)));
+        assert_eq!(decoder.decode_definition("com.example", &definition, &[0b0000_0000]), Err(DecodeError::UnexpectedEnd));
+        assert_eq!(decoder.decode_message(65535, &[]), Err(DecodeError::UnknownMessageId(65535)));
+    }
+
+    #[test]
+    fn decode_with_dsdl() {
+        #[derive(Debug, Clone, Default, PartialEq, UavcanStruct)]
+        struct LogLevel {
+            value: u3,
+        }
+
+        #[derive(Debug, Clone, Default, PartialEq, UavcanStruct)]
+        struct LogMessage {
+            level: LogLevel,
+            source: Dynamic<[u8; 31]>,
+            text: Dynamic<[u8; 90]>,
+        }
+
+        let value = LogMessage{level: LogLevel{value: u3::new(2)}, source: Dynamic::<[u8; 31]>::with_data(b"node"), text: Dynamic::<[u8; 90]>::with_data(b"hello")};
+        let mut data = [0u8; 16];
+        let bit_length = {
+            let mut buffer = SerializationBuffer::with_empty_buffer(&mut data);
+            assert_eq!(value.serialize(&mut 0, &mut 0, true, &mut buffer), SerializationResult::Finished);
+            buffer.bit_length()
+        };
+        let mut payload = data[..bit_length.div_ceil(8)].to_vec();
+
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+        let dynamic = Decoder::new(&dsdl).decode_message(16383, &payload).unwrap();
+
+        let mut compiled = LogMessage::default();
+        compiled.deserialize(&mut 0, &mut 0, true, &mut DeserializationBuffer::with_full_buffer(&mut payload));
+        let bytes = |bytes: &[u8]| DynamicValue::Array(bytes.iter().map(|byte| DynamicValue::Unsigned(u64::from(*byte))).collect());
+        assert_eq!(dynamic, DynamicValue::Struct(vec![
+            (String::from("level"), DynamicValue::Struct(vec![(String::from("value"), DynamicValue::Unsigned(u64::from(compiled.level.value)))])),
+            (String::from("source"), bytes(compiled.source.as_ref())),
+            (String::from("text"), bytes(compiled.text.as_ref())),
+        ]));
+
+        assert_eq!(Decoder::new(&dsdl).decode("uavcan.protocol.debug.LogMessage", &payload), Ok(dynamic));
+    }
+}
@@ -0,0 +1,148 @@
+//! Errors that can occur when compiling DSDL
+
+use std::error::Error;
+use std::fmt;
+
+/// An error that occurred when compiling DSDL
+///
+/// Together with the reason, the error contains the DSDL file (relative to the DSDL root) and line that caused it, when they are known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    kind: CompileErrorKind,
+    file: Option<String>,
+    line: Option<usize>,
+}
+
+/// The reason compilation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileErrorKind {
+    /// A composite type (full name) that doesn't exist in the DSDL tree
+    UnknownCompositeType(String),
+
+    /// An array (field name and size) that can't hold any elements
+    InvalidArraySize(String, u64),
+
+    /// A name that's used for more than one field or constant in the same definition
+    NameClash(String),
+
+    /// Two fields or modules (original names) that get the same name (the third) when converted to Rust
+    ConvertedNameClash(String, String, String),
+
+    /// A name that can't be used as a Rust identifier
+    InvalidIdentifier(String),
+
+    /// A constant (name and reason) whose value can't be represented by its type
+    InvalidConstant(String, String),
+
+    /// A default data type ID (and the full name of the type that already uses it) that's used by more than one type of the same kind
+    DuplicateTypeId(u64, String),
+
+    /// A default data type ID that's not a number in the valid range (which ends at the second value)
+    InvalidTypeId(String, u64),
+
+    /// A type (full name) in the data type ID map that doesn't exist in the DSDL tree
+    UnknownMappedType(String),
+
+    /// A union (with the number of fields) that has fewer than 2 fields
+    UnionTooSmall(usize),
+
+    /// A definition where the maximum bit length (the first value) is larger than the protocol allows (the second value)
+    BitLengthTooLarge(u64, u64),
+
+    /// A type (full name) that contains itself, directly or through other composite types
+    RecursiveType(String),
+
+    /// An attribute in `CompileConfig::type_attributes` that can't be parsed
+    InvalidAttribute(String),
+
+    /// The compiler generated something that isn't valid Rust, this is a bug in the compiler
+    CodeGeneration(String),
+}
+
+impl CompileError {
+    /// Creates a new `CompileError` without any location
+    pub fn new(kind: CompileErrorKind) -> CompileError {
+        CompileError {
+            kind,
+            file: None,
+            line: None,
+        }
+    }
+
+    /// The reason compilation failed
+    pub fn kind(&self) -> &CompileErrorKind {
+        &self.kind
+    }
+
+    /// The DSDL file (relative to the DSDL root) that caused the error
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// The line (starting at 1) in the DSDL file that caused the error
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Sets the file, unless it's already known
+    pub(crate) fn in_file(mut self, file: &str) -> CompileError {
+        if self.file.is_none() {
+            self.file = Some(String::from(file));
+        }
+        self
+    }
+
+    /// Sets the line, unless it's already known
+    pub(crate) fn at_line(mut self, line: usize) -> CompileError {
+        if self.line.is_none() {
+            self.line = Some(line);
+        }
+        self
+    }
+
+    /// Moves the line down by `offset` lines, used when the definition doesn't start at the top of the file
+    pub(crate) fn offset_line(mut self, offset: usize) -> CompileError {
+        self.line = self.line.map(|line| line + offset);
+        self
+    }
+}
+
+impl fmt::Display for CompileErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompileErrorKind::UnknownCompositeType(ref name) => write!(f, "cannot find composite type `{}`", name),
+            CompileErrorKind::InvalidArraySize(ref name, size) => write!(f, "array `{}` can hold at most {} elements, arrays must hold at least 1 element", name, size),
+            CompileErrorKind::NameClash(ref name) => write!(f, "the name `{}` is defined multiple times", name),
+            CompileErrorKind::ConvertedNameClash(ref first, ref second, ref name) => write!(f, "`{}` and `{}` are both named `{}` in Rust", first, second, name),
+            CompileErrorKind::InvalidIdentifier(ref name) => write!(f, "`{}` is not a valid identifier", name),
+            CompileErrorKind::InvalidConstant(ref name, ref reason) => write!(f, "invalid value for constant `{}`: {}", name, reason),
+            CompileErrorKind::DuplicateTypeId(id, ref other) => write!(f, "data type ID {} is already used by `{}`", id, other),
+            CompileErrorKind::InvalidTypeId(ref id, max) => write!(f, "data type ID `{}` is invalid, IDs must be in the range 0..={}", id, max),
+            CompileErrorKind::UnknownMappedType(ref name) => write!(f, "the data type ID map contains `{}`, which is not a type in the DSDL tree", name),
+            CompileErrorKind::UnionTooSmall(fields) => write!(f, "unions must have at least 2 fields, found {}", fields),
+            CompileErrorKind::BitLengthTooLarge(bits, max) => write!(f, "the maximum bit length is {} bits, the protocol allows at most {} bits", bits, max),
+            CompileErrorKind::RecursiveType(ref name) => write!(f, "the type `{}` contains itself, which would give it an unbounded size", name),
+            CompileErrorKind::InvalidAttribute(ref attribute) => write!(f, "`{}` is not a valid attribute", attribute),
+            CompileErrorKind::CodeGeneration(ref reason) => write!(f, "failed to generate code: {}", reason),
+        }
+    }
+}
+
+/// Formats the error like rustc does, without the source line
+///
+/// ```text
+/// error: cannot find composite type `uavcan.protocol.Unknown`
+///   --> uavcan/protocol/341.NodeStatus.uavcan:12
+/// ```
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error: {}", self.kind)?;
+        match (self.file(), self.line) {
+            (Some(file), Some(line)) => write!(f, "\n  --> {}:{}", file, line),
+            (Some(file), None) => write!(f, "\n  --> {}", file),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl Error for CompileError {}
@@ -0,0 +1,375 @@
+//! Exporting Rust types to DSDL
+//!
+//! Types written by hand with `#[derive(UavcanStruct)]` can be shared with nodes using other UAVCAN implementations by exporting
+//! them as a DSDL directory. The types are registered with an `Export`, which writes every type to `namespace/[ID.]Name.uavcan`
+//! (like `com/example/20000.Temperature.uavcan`) with its normalized definition, void padding included.
+//!
+//! Since a program can't list the types of another crate, the export is run from a small binary (or a test) in the crate defining them.
+//! Every composite type that is used as a field must be registered as well, so the directory is complete.
+//!
+//! ```no_run
+//! #[macro_use]
+//! extern crate uavcan;
+//! extern crate dsdl_compiler;
+//!
+//! use uavcan::types::*;
+//! use uavcan::Message;
+//! use dsdl_compiler::export::Export;
+//!
+//! #[derive(UavcanStruct)]
+//! #[FullName = "com.example.Health"]
+//! struct Health {
+//!     value: u2,
+//!     _v0: void6,
+//! }
+//!
+//! #[derive(UavcanStruct)]
+//! #[FullName = "com.example.Temperature"]
+//! struct Temperature {
+//!     health: Health,
+//!     kelvin: f16,
+//! }
+//!
+//! impl Message for Temperature {
+//!     const TYPE_ID: Option<u16> = Some(20000);
+//! }
+//!
+//! fn main() {
+//!     let mut export = Export::new();
+//!     export.composite::<Health>().message::<Temperature>();
+//!
+//!     export.write("dsdl").unwrap_or_else(|error| panic!("{}", error));
+//!     export.verify("dsdl").unwrap_or_else(|error| panic!("{}", error));
+//! }
+//! ```
+//!
+//! `verify` reads the written directory back the way `dsdlc` does, and checks that the data type signatures of the DSDL match the Rust types.
+//!
+//! Generic types are registered once for every instance that should be exported, like `export.composite::<Pair<u8>>()`.
+//!
+//! # `dsdlc export`
+//! When the types are registered in an example calling `Export::run`, `dsdlc export --example <name> -o <dir>` runs the example
+//! with cargo, which writes the types to `dir` and verifies them.
+//!
+//! ```ignore
+//! // examples/export_dsdl.rs
+//! fn main() {
+//!     let mut export = Export::new();
+//!     export.composite::<Health>().message::<Temperature>();
+//!     export.run();
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+use dsdl_parser;
+use dsdl_parser::DSDL;
+use uavcan::{Message, Request, Struct};
+
+use error::CompileError;
+use validate::validate;
+use CompileConfig;
+use super::file_path;
+
+/// A set of Rust types to export as DSDL
+#[derive(Debug, Clone, Default)]
+pub struct Export {
+    types: Vec<ExportedType>,
+}
+
+/// A registered type, with everything needed to write its `.uavcan` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ExportedType {
+    full_name: &'static str,
+    type_id: Option<u16>,
+    definition: String,
+    data_type_signature: u64,
+}
+
+/// An error that occurred when exporting types
+#[derive(Debug)]
+pub enum ExportError {
+    /// Writing or reading the DSDL directory failed
+    Io(io::Error),
+
+    /// More than one registered type has this full name
+    DuplicateType(String),
+
+    /// A registered type (the first name) has a field of a composite type (the second name) that isn't registered
+    MissingType(String, String),
+
+    /// The written DSDL contains errors
+    Compile(Vec<CompileError>),
+
+    /// A type (full name) has a different data type signature in Rust (the first value) than in the written DSDL (the second value)
+    SignatureMismatch(String, u64, Option<u64>),
+}
+
+impl Export {
+    /// Creates an export without any types
+    pub fn new() -> Export {
+        Export::default()
+    }
+
+    /// Registers a message type, the file name includes the data type ID when it has one
+    pub fn message<T: Message>(&mut self) -> &mut Export {
+        self.register::<T>(T::TYPE_ID, definition::<T>())
+    }
+
+    /// Registers a service by its request type, the definition is the request and the response separated by `---`
+    pub fn service<T: Request>(&mut self) -> &mut Export {
+        let definition = format!("{}---\n{}", definition::<T>(), definition::<T::RESPONSE>());
+        self.register::<T>(T::TYPE_ID.map(u16::from), definition)
+    }
+
+    /// Registers a composite type that is only used as a field of other types, it's written without a data type ID
+    pub fn composite<T: Struct>(&mut self) -> &mut Export {
+        self.register::<T>(None, definition::<T>())
+    }
+
+    fn register<T: Struct>(&mut self, type_id: Option<u16>, definition: String) -> &mut Export {
+        self.types.push(ExportedType {
+            full_name: T::FULL_NAME,
+            type_id,
+            definition,
+            data_type_signature: T::DATA_TYPE_SIGNATURE,
+        });
+        self
+    }
+
+    /// Returns the path (relative to the DSDL root) and content of the file of every registered type
+    pub fn files(&self) -> Result<Vec<(PathBuf, String)>, ExportError> {
+        let mut names = HashSet::new();
+        for exported in &self.types {
+            if !names.insert(exported.full_name) {
+                return Err(ExportError::DuplicateType(String::from(exported.full_name)));
+            }
+        }
+
+        for exported in &self.types {
+            for name in composite_types(&exported.definition) {
+                if !names.contains(name) {
+                    return Err(ExportError::MissingType(String::from(exported.full_name), String::from(name)));
+                }
+            }
+        }
+
+        Ok(self.types.iter().map(|exported| (PathBuf::from(file_path(&exported.file_name())), exported.definition.clone())).collect())
+    }
+
+    /// Writes the file of every registered type to the DSDL root `dir`, returning the paths of the written files
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, ExportError> {
+        let mut paths = Vec::new();
+        for (path, definition) in self.files()? {
+            let path = dir.as_ref().join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(ExportError::Io)?;
+            }
+            fs::write(&path, definition).map_err(ExportError::Io)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Reads the DSDL root `dir` and checks that it's valid, and that every registered type has the same data type signature as in Rust
+    pub fn verify<P: AsRef<Path>>(&self, dir: P) -> Result<(), ExportError> {
+        let dsdl = DSDL::read(dir).map_err(ExportError::Io)?;
+        validate(&dsdl, &CompileConfig::default()).map_err(ExportError::Compile)?;
+
+        for exported in &self.types {
+            let signature = dsdl.data_type_signature(exported.full_name);
+            if signature != Some(exported.data_type_signature) {
+                return Err(ExportError::SignatureMismatch(String::from(exported.full_name), exported.data_type_signature, signature));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the registered types to the DSDL root given as the first command line argument and verifies them, exiting with an error when that fails
+    ///
+    /// This is the `main` of the examples run by `dsdlc export`.
+    pub fn run(&self) {
+        let dir = match env::args_os().nth(1) {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                eprintln!("error: the DSDL root to export to must be given as the first argument");
+                process::exit(1);
+            },
+        };
+
+        if let Err(error) = self.write(&dir).and_then(|_| self.verify(&dir)) {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+impl ExportedType {
+    fn file_name(&self) -> dsdl_parser::FileName {
+        let (namespace, name) = match self.full_name.rfind('.') {
+            Some(i) => (&self.full_name[..i], &self.full_name[i+1..]),
+            None => ("", self.full_name),
+        };
+        dsdl_parser::FileName {
+            id: self.type_id.map(|id| id.to_string()),
+            namespace: String::from(namespace),
+            name: String::from(name),
+            version: None,
+        }
+    }
+}
+
+fn definition<T: Struct>() -> String {
+    let mut definition = String::new();
+    T::write_dsdl_definition(&mut definition).expect("Writing to a string can't fail");
+    definition
+}
+
+/// Returns the full names of the composite types used as fields in a normalized definition
+fn composite_types(definition: &str) -> Vec<&str> {
+    definition.lines()
+        .filter(|line| !line.starts_with('@') && *line != "---")
+        .filter_map(|line| line.split_whitespace().find(|word| *word != "saturated" && *word != "truncated"))
+        .map(|field_type| field_type.split('[').next().expect("Splitting always returns at least one part"))
+        .filter(|field_type| field_type.contains('.'))
+        .collect()
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::Io(ref error) => write!(f, "{}", error),
+            ExportError::DuplicateType(ref name) => write!(f, "type `{}` is registered more than once", name),
+            ExportError::MissingType(ref name, ref field_type) => write!(f, "type `{}` has a field of type `{}`, which is not registered", name, field_type),
+            ExportError::Compile(ref errors) => {
+                write!(f, "the exported DSDL contains errors")?;
+                for error in errors {
+                    write!(f, "\n\n{}", error)?;
+                }
+                Ok(())
+            },
+            ExportError::SignatureMismatch(ref name, rust, Some(dsdl)) => write!(f, "type `{}` has data type signature 0x{:x} in Rust, but 0x{:x} in the exported DSDL", name, rust, dsdl),
+            ExportError::SignatureMismatch(ref name, _, None) => write!(f, "type `{}` is missing from the exported DSDL", name),
+        }
+    }
+}
+
+impl Error for ExportError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use uavcan::types::*;
+
+    #[derive(UavcanStruct)]
+    #[FullName = "com.example.Health"]
+    struct Health {
+        value: u2,
+        _v0: void6,
+    }
+
+    #[derive(UavcanStruct)]
+    #[FullName = "com.example.sensors.Temperature"]
+    struct Temperature {
+        health: Health,
+        #[CastMode = "truncated"]
+        kelvin: Dynamic<[f16; 2]>,
+    }
+
+    impl Message for Temperature {
+        const TYPE_ID: Option<u16> = Some(20000);
+    }
+
+    #[derive(UavcanStruct)]
+    #[FullName = "com.example.Pair"]
+    struct Pair<T> {
+        first: T,
+        second: T,
+    }
+
+    #[derive(UavcanStruct)]
+    #[DSDLSignature = "0x1234"]
+    #[DataTypeSignature = "0x1234"]
+    #[FullName = "com.example.GetHealth"]
+    struct GetHealthRequest {
+        node: u7,
+    }
+
+    #[derive(UavcanStruct)]
+    #[DSDLSignature = "0x1234"]
+    #[DataTypeSignature = "0x1234"]
+    #[FullName = "com.example.GetHealth"]
+    struct GetHealthResponse {
+        health: Health,
+    }
+
+    impl Request for GetHealthRequest {
+        type RESPONSE = GetHealthResponse;
+        const TYPE_ID: Option<u8> = Some(200);
+    }
+
+    impl ::uavcan::Response for GetHealthResponse {
+        type REQUEST = GetHealthRequest;
+        const TYPE_ID: Option<u8> = Some(200);
+    }
+
+    #[test]
+    fn export_files() {
+        let mut export = Export::new();
+        export.composite::<Health>().message::<Temperature>().service::<GetHealthRequest>();
+
+        assert_eq!(export.files().unwrap(), vec![
+            (PathBuf::from("com/example/Health.uavcan"), String::from("saturated uint2 value\nvoid6\n")),
+            (PathBuf::from("com/example/sensors/20000.Temperature.uavcan"), String::from("com.example.Health health\ntruncated float16[<=2] kelvin\n")),
+            (PathBuf::from("com/example/200.GetHealth.uavcan"), String::from("saturated uint7 node\n---\ncom.example.Health health\n")),
+        ]);
+
+        // generic types are exported by registering an instance
+        let mut generic = Export::new();
+        generic.composite::<Pair<u2>>();
+        assert_eq!(generic.files().unwrap(), vec![
+            (PathBuf::from("com/example/Pair.uavcan"), String::from("saturated uint2 first\nsaturated uint2 second\n")),
+        ]);
+    }
+
+    #[test]
+    fn export_errors() {
+        match Export::new().message::<Temperature>().files() {
+            Err(ExportError::MissingType(ref name, ref field_type)) => {
+                assert_eq!(name, "com.example.sensors.Temperature");
+                assert_eq!(field_type, "com.example.Health");
+            },
+            other => panic!("expected a missing type, got {:?}", other),
+        }
+
+        match Export::new().composite::<Health>().composite::<Health>().files() {
+            Err(ExportError::DuplicateType(ref name)) => assert_eq!(name, "com.example.Health"),
+            other => panic!("expected a duplicate type, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let dir = env::temp_dir().join("dsdl_compiler_export_round_trip");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut export = Export::new();
+        export.composite::<Health>().message::<Temperature>();
+
+        let paths = export.write(&dir).unwrap();
+        assert_eq!(paths[1], dir.join("com/example/sensors/20000.Temperature.uavcan"));
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), Temperature::DSDL_DEFINITION);
+        export.verify(&dir).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+//! Cargo features for DSDL namespaces
+//!
+//! With `CompileConfig::namespace_features`, the types of every namespace are compiled behind `#[cfg(feature = "...")]`,
+//! so a crate only compiles the namespaces it needs. The feature of a namespace enables the features of the namespaces it uses types from.
+//!
+//! Cargo features must be declared in `Cargo.toml`. `cargo_features` returns the `[features]` table for a DSDL tree (`dsdlc --cargo-features` prints it),
+//! and `check_manifest` can be used from a build script to make sure the declared features are still up to date.
+//!
+//! ```toml
+//! [features]
+//! default = ["all"]
+//! all = ["equipment-esc", "protocol", "uavcan"]
+//! equipment-esc = []
+//! protocol = []
+//! uavcan = []
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use toml;
+
+use dsdl_parser::DSDL;
+
+use super::composite_types;
+use CompileConfig;
+
+/// The feature enabling every namespace
+pub const ALL_FEATURE: &str = "all";
+
+/// The features of the namespaces in a DSDL tree, together with the features they enable
+pub type NamespaceFeatures = BTreeMap<String, BTreeSet<String>>;
+
+/// An error found when checking the features declared in `Cargo.toml`
+#[derive(Debug)]
+pub enum ManifestError {
+    /// The manifest is not valid TOML
+    Toml(toml::de::Error),
+
+    /// Namespace features that are missing, or that don't enable every feature they need
+    OutdatedFeatures(Vec<String>),
+}
+
+/// Returns the name of the feature that enables a namespace
+///
+/// Dots and underscores are replaced by dashes. The standard root namespace is left out, except for the types directly in it.
+///
+/// ```
+/// use dsdl_compiler::features::feature_name;
+///
+/// assert_eq!(feature_name("uavcan.equipment.esc"), "equipment-esc");
+/// assert_eq!(feature_name("uavcan.protocol.dynamic_node_id"), "protocol-dynamic-node-id");
+/// assert_eq!(feature_name("uavcan"), "uavcan");
+/// assert_eq!(feature_name("com.example"), "com-example");
+/// ```
+pub fn feature_name(namespace: &str) -> String {
+    let namespace = match namespace.split_once('.') {
+        Some(("uavcan", nested)) => nested,
+        _ => namespace,
+    };
+    namespace.replace(['.', '_'], "-")
+}
+
+/// Returns the features of the namespaces compiled from a (validated) DSDL tree, and the features each of them enables
+///
+/// Namespaces in `config.extern_paths` are compiled in another crate, and don't get features.
+pub fn namespace_features(dsdl: &DSDL, config: &CompileConfig) -> NamespaceFeatures {
+    let mut features = NamespaceFeatures::new();
+
+    for file in dsdl.files().into_iter().filter(|file| config.extern_path(&file.name.namespace).is_none()) {
+        let feature = feature_name(&file.name.namespace);
+        let dependencies: Vec<String> = composite_types(file).into_iter()
+            .map(|(_, full_name)| String::from(full_name.rsplit_once('.').map_or("", |(namespace, _)| namespace)))
+            .filter(|namespace| config.extern_path(namespace).is_none())
+            .map(|namespace| feature_name(&namespace))
+            .filter(|dependency| *dependency != feature)
+            .collect();
+        features.entry(feature).or_default().extend(dependencies);
+    }
+
+    features
+}
+
+/// Returns the `[features]` table declaring the namespace features, enabling every namespace by default
+pub fn cargo_features(features: &NamespaceFeatures) -> String {
+    let list = |features: &mut dyn Iterator<Item = &String>| features.map(|feature| format!("\"{}\"", feature)).collect::<Vec<_>>().join(", ");
+
+    let mut table = String::from("[features]\n");
+    table.push_str(&format!("default = [\"{}\"]\n", ALL_FEATURE));
+    table.push_str(&format!("{} = [{}]\n", ALL_FEATURE, list(&mut features.keys())));
+    for (feature, dependencies) in features {
+        table.push_str(&format!("{} = [{}]\n", feature, list(&mut dependencies.iter())));
+    }
+    table
+}
+
+/// Checks that a `Cargo.toml` declares every namespace feature, and that they enable the features they need
+pub fn check_manifest(manifest: &str, features: &NamespaceFeatures) -> Result<(), ManifestError> {
+    let manifest = manifest.parse::<toml::Value>().map_err(ManifestError::Toml)?;
+    let declared = manifest.get("features").and_then(|features| features.as_table());
+
+    let outdated: Vec<String> = features.iter()
+        .filter(|&(feature, dependencies)| {
+            let enabled: Option<Vec<&str>> = declared
+                .and_then(|declared| declared.get(feature))
+                .and_then(|enabled| enabled.as_array())
+                .map(|enabled| enabled.iter().filter_map(|value| value.as_str()).collect());
+            match enabled {
+                Some(enabled) => dependencies.iter().any(|dependency| !enabled.contains(&dependency.as_str())),
+                None => true,
+            }
+        })
+        .map(|(feature, _)| feature.clone())
+        .collect();
+
+    if outdated.is_empty() {
+        Ok(())
+    } else {
+        Err(ManifestError::OutdatedFeatures(outdated))
+    }
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ManifestError::Toml(ref error) => write!(f, "failed to parse the manifest: {}", error),
+            ManifestError::OutdatedFeatures(ref features) => write!(f, "the manifest doesn't declare the features `{}` as needed by the DSDL", features.join("`, `")),
+        }
+    }
+}
+
+impl ::std::error::Error for ManifestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features() -> NamespaceFeatures {
+        let mut features = NamespaceFeatures::new();
+        features.insert(String::from("uavcan"), BTreeSet::new());
+        features.insert(String::from("equipment-gnss"), vec![String::from("uavcan")].into_iter().collect());
+        features
+    }
+
+    #[test]
+    fn render_features() {
+        assert_eq!(cargo_features(&features()), "\
+[features]
+default = [\"all\"]
+all = [\"equipment-gnss\", \"uavcan\"]
+equipment-gnss = [\"uavcan\"]
+uavcan = []
+");
+    }
+
+    #[test]
+    fn check_features() {
+        assert!(check_manifest(&cargo_features(&features()), &features()).is_ok());
+
+        let outdated = "[features]\nequipment-gnss = []\nuavcan = []\n";
+        match check_manifest(outdated, &features()) {
+            Err(ManifestError::OutdatedFeatures(outdated)) => assert_eq!(outdated, vec![String::from("equipment-gnss")]),
+            _ => panic!("`equipment-gnss` needs to enable `uavcan`"),
+        }
+
+        match check_manifest("[package]\nname = \"dsdl\"\n", &features()) {
+            Err(ManifestError::OutdatedFeatures(outdated)) => assert_eq!(outdated.len(), 2),
+            _ => panic!("no features are declared"),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Formatting of generated code
+//!
+//! `quote` writes every token separated by a space, on a single line. The code is parsed again with a recent version of `syn`,
+//! and printed by `prettyplease`, which formats it much like rustfmt does and writes doc attributes as doc comments.
+
+use syn;
+use syn2;
+use prettyplease;
+
+use error::CompileError;
+
+use super::code_generation_error;
+
+/// Formats compiled items
+pub(crate) fn format_items(items: &[syn::Item]) -> Result<String, CompileError> {
+    let tokens = quote!{#(#items)*};
+    format(tokens.as_str())
+}
+
+/// Formats code produced by `quote`, failing if it isn't valid Rust
+pub(crate) fn format(code: &str) -> Result<String, CompileError> {
+    let file = syn2::parse_file(code).map_err(code_generation_error)?;
+    Ok(prettyplease::unparse(&file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_struct() {
+        let tokens = quote!{
+            #[doc = " Abstract node status"]
+            #[derive(Debug, Clone, UavcanStruct)]
+            pub struct NodeStatus {
+                pub uptime_sec: u32,
+                pub health: ::u2,
+                pub data: ::Dynamic<[u8; 3]>,
+            }
+            impl NodeStatus {
+                pub const HEALTH_OK: ::u2 = ::u2::new(0);
+            }
+            impl ::uavcan_rs::Message for NodeStatus {
+                const TYPE_ID: Option<u16> = Some(341);
+            }
+        };
+
+        assert_eq!(format(tokens.as_str()).unwrap(), "\
+/// Abstract node status
+#[derive(Debug, Clone, UavcanStruct)]
+pub struct NodeStatus {
+    pub uptime_sec: u32,
+    pub health: ::u2,
+    pub data: ::Dynamic<[u8; 3]>,
+}
+impl NodeStatus {
+    pub const HEALTH_OK: ::u2 = ::u2::new(0);
+}
+impl ::uavcan_rs::Message for NodeStatus {
+    const TYPE_ID: Option<u16> = Some(341);
+}
+");
+    }
+
+    #[test]
+    fn format_functions() {
+        let tokens = quote!{
+            pub mod node_status {
+                impl ::uavcan_rs::TryFrom<::u2> for Health {
+                    type Error = ::u2;
+                    fn try_from(value: ::u2) -> Result<Self, Self::Error> {
+                        if value == super::NodeStatus::HEALTH_OK { Ok(Health::Ok) } else { Err(value) }
+                    }
+                    fn is_ok(&self, other: i8) -> bool {
+                        match *self { Health::Ok => other < -1, }
+                    }
+                }
+            }
+        };
+
+        assert_eq!(format(tokens.as_str()).unwrap(), "\
+pub mod node_status {
+    impl ::uavcan_rs::TryFrom<::u2> for Health {
+        type Error = ::u2;
+        fn try_from(value: ::u2) -> Result<Self, Self::Error> {
+            if value == super::NodeStatus::HEALTH_OK {
+                Ok(Health::Ok)
+            } else {
+                Err(value)
+            }
+        }
+        fn is_ok(&self, other: i8) -> bool {
+            match *self {
+                Health::Ok => other < -1,
+            }
+        }
+    }
+}
+");
+    }
+
+    #[test]
+    fn format_invalid_code() {
+        assert!(format("pub struct {").is_err());
+    }
+}
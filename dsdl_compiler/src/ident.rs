@@ -0,0 +1,122 @@
+//! Conversion of DSDL names into Rust identifiers
+//!
+//! DSDL allows names that are reserved in Rust, like a field named `type`. These are escaped by adding a `_` suffix (`type_`), raw identifiers (`r#type`) can't be used as the generated structs are parsed by `uavcan-derive`.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use syn;
+
+use inflections::Inflect;
+
+use error::CompileError;
+use error::CompileErrorKind;
+
+/// Rust keywords (strict and reserved), these can't be used as identifiers
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box",
+    "do", "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+];
+
+/// Returns true if `name` can be used as a Rust identifier, once it's escaped
+pub(crate) fn is_valid(name: &str) -> bool {
+    let valid_start = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    let valid_rest = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid_start && valid_rest && name != "_"
+}
+
+/// Returns true if `name` is a Rust keyword
+pub(crate) fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Escapes a name that's a Rust keyword, other names are returned unchanged
+pub(crate) fn escape(name: &str) -> String {
+    if is_keyword(name) {
+        format!("{}_", name)
+    } else {
+        String::from(name)
+    }
+}
+
+/// Returns the name of the enum variant a union field is compiled into
+pub(crate) fn variant_name(name: &str) -> String {
+    escape(&name.to_pascal_case())
+}
+
+/// Returns the doc attributes that records the DSDL name of an item whose name had to be escaped
+pub(crate) fn original_name_docs(dsdl_name: &str) -> Vec<syn::Attribute> {
+    ["".to_string(), format!(" DSDL name: `{}`", dsdl_name)].iter()
+        .map(|doc| syn::Attribute{
+            style: syn::AttrStyle::Outer,
+            value: syn::MetaItem::NameValue(syn::Ident::from("doc"), syn::Lit::Str(doc.clone(), syn::StrStyle::Cooked)),
+            is_sugared_doc: true,
+        })
+        .collect()
+}
+
+/// The names used in a definition, used to detect names that clash in DSDL or after they're converted to Rust
+#[derive(Debug, Default)]
+pub(crate) struct Names {
+    dsdl: HashSet<String>,
+    rust: HashMap<String, String>,
+}
+
+impl Names {
+    /// Adds a name, failing if it's already used
+    pub(crate) fn insert(&mut self, dsdl_name: &str, rust_name: String) -> Result<(), CompileError> {
+        if !self.dsdl.insert(String::from(dsdl_name)) {
+            return Err(CompileError::new(CompileErrorKind::NameClash(String::from(dsdl_name))));
+        }
+        if let Some(other) = self.rust.get(&rust_name) {
+            return Err(CompileError::new(CompileErrorKind::ConvertedNameClash(other.clone(), String::from(dsdl_name), rust_name)));
+        }
+        self.rust.insert(rust_name, String::from(dsdl_name));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_keywords() {
+        assert_eq!(escape("type"), "type_");
+        assert_eq!(escape("self"), "self_");
+        assert_eq!(escape("Self"), "Self_");
+        assert_eq!(escape("node_id"), "node_id");
+        assert_eq!(variant_name("self"), "Self_");
+        assert_eq!(variant_name("node_id"), "NodeId");
+    }
+
+    #[test]
+    fn valid_names() {
+        assert!(is_valid("type"));
+        assert!(is_valid("_v0"));
+        assert!(!is_valid("_"));
+        assert!(!is_valid("0value"));
+        assert!(!is_valid("node-id"));
+        assert!(!is_valid(""));
+    }
+
+    #[test]
+    fn name_clashes() {
+        let mut names = Names::default();
+        assert!(names.insert("type", escape("type")).is_ok());
+        assert_eq!(names.insert("type", escape("type")), Err(CompileError::new(CompileErrorKind::NameClash(String::from("type")))));
+        assert_eq!(
+            names.insert("type_", escape("type_")),
+            Err(CompileError::new(CompileErrorKind::ConvertedNameClash(String::from("type"), String::from("type_"), String::from("type_")))),
+        );
+
+        let mut variants = Names::default();
+        assert!(variants.insert("foo_bar", variant_name("foo_bar")).is_ok());
+        assert_eq!(
+            variants.insert("fooBar", variant_name("fooBar")),
+            Err(CompileError::new(CompileErrorKind::ConvertedNameClash(String::from("foo_bar"), String::from("fooBar"), String::from("FooBar")))),
+        );
+    }
+}
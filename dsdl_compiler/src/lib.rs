@@ -16,7 +16,7 @@
 //! use dsdl_compiler::CompileConfig;
 //!
 //! let dsdl = DSDL::read("tests/dsdl/").unwrap();
-//! let items = dsdl.compile(&CompileConfig::default());
+//! let items = dsdl.compile(&CompileConfig::default()).unwrap();
 //!
 //! assert!(items.len() >= 1);
 //!
@@ -36,7 +36,7 @@
 //!
 //! # fn main() {
 //! let dsdl = DSDL::read("tests/dsdl/").unwrap();
-//! let items = dsdl.compile(&CompileConfig::default());
+//! let items = dsdl.compile(&CompileConfig::default()).unwrap();
 //!
 //! let tokens = quote!{#(#items)*};
 //!
@@ -44,8 +44,20 @@
 //! # }
 //! 
 //! ```
+//!
+//! ### Compile DSDL from a build script
+//! The [`Builder`](builder/struct.Builder.html) validates and compiles DSDL into `OUT_DIR`, and makes cargo rerun the build script when the DSDL changes.
+//!
+//! ```no_run
+//! use dsdl_compiler::Builder;
+//!
+//! Builder::new()
+//!     .include("dsdl")
+//!     .compile()
+//!     .unwrap_or_else(|error| panic!("{}", error));
+//! ```
 
-#![recursion_limit="128"]
+#![recursion_limit="512"]
 
 #[allow(unused_imports)]
 #[macro_use]
@@ -53,72 +65,272 @@ extern crate quote;
 extern crate dsdl_parser;
 extern crate syn;
 extern crate inflections;
+extern crate half;
+extern crate toml;
+extern crate syn2;
+extern crate prettyplease;
+#[cfg_attr(test, macro_use)]
+extern crate uavcan;
 
 use inflections::Inflect;
 
 pub mod bin;
 pub mod config;
+pub mod error;
+pub mod validate;
+pub mod builder;
+pub mod roots;
+pub mod modules;
+pub mod features;
+pub mod dynamic;
+pub mod export;
+mod derives;
+mod ident;
+mod format;
 
 pub use dsdl_parser::DSDL;
 pub use config::CompileConfig;
+pub use error::CompileError;
+pub use builder::Builder;
 
 use config::*;
+use error::CompileErrorKind;
 
 /// The trait that must be implemented to compile from DSDL to code
 pub trait Compile<T> {
     /// The function used to compile from DSDL to code
-    fn compile(self, config: &CompileConfig) -> T;
+    fn compile(self, config: &CompileConfig) -> Result<T, CompileError>;
 }
 
 impl Compile<Vec<syn::Item>> for DSDL {
-    fn compile(self, config: &CompileConfig) -> Vec<syn::Item> {
-        let mut items = Vec::new();
-        for file in self.files() {
-            let data_type_signature = self.data_type_signature(format!("{}", file.name)).unwrap();
-            let new_items = file.clone().compile(config);
-            for mut new_item in new_items {
-                if config.data_type_signature {
-                    add_data_type_signature(&mut new_item, data_type_signature);
+    fn compile(self, config: &CompileConfig) -> Result<Vec<syn::Item>, CompileError> {
+        compile_all(&self, config).map_err(|errors| errors.into_iter().next().expect("Compilation fails with at least one error"))
+    }
+}
+
+/// Validates and compiles a DSDL tree like `Compile`, but returns every error that was found instead of only the first
+///
+/// ```
+/// use dsdl_compiler::DSDL;
+/// use dsdl_compiler::CompileConfig;
+/// use dsdl_compiler::compile_all;
+///
+/// let dsdl = DSDL::read("tests/dsdl/").unwrap();
+/// let items = compile_all(&dsdl, &CompileConfig::default()).unwrap();
+///
+/// assert!(items.len() >= 1);
+/// ```
+pub fn compile_all(dsdl: &DSDL, config: &CompileConfig) -> Result<Vec<syn::Item>, Vec<CompileError>> {
+    validate::validate(dsdl, config)?;
+
+    let mut items = compile_files(dsdl, config, |_| true)?;
+    if config.arbitrary {
+        items.push(arbitrary_types_visitor(dsdl, config, |_| true).map_err(|error| vec![error])?);
+    }
+    add_preamble(&mut items);
+    Ok(items)
+}
+
+/// Compiles the files of a (validated) DSDL tree that are accepted by `filter` into namespace modules, returning the errors of every file that fails to compile
+fn compile_files<F: Fn(&dsdl_parser::File) -> bool>(dsdl: &DSDL, config: &CompileConfig, filter: F) -> Result<Vec<syn::Item>, Vec<CompileError>> {
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    // types in extern namespaces are compiled in another crate
+    for file in dsdl.files().into_iter().filter(|file| filter(file) && config.extern_path(&file.name.namespace).is_none()) {
+        match file_items(dsdl, file, config, Layout::Namespace) {
+            Ok(new_items) => {
+                for new_item in in_namespace_modules(&file.name, new_items) {
+                    add_item(new_item, &mut items);
+                }
+            },
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(items)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Compiles a file of a (validated) DSDL tree, adding the data type signature when it's enabled in `config`
+fn file_items(dsdl: &DSDL, file: &dsdl_parser::File, config: &CompileConfig, layout: Layout) -> Result<Vec<syn::Item>, CompileError> {
+    let data_type_signature = dsdl.data_type_signature(format!("{}", file.name))
+        .ok_or_else(|| CompileError::new(CompileErrorKind::UnknownCompositeType(format!("{}", file.name))).in_file(&file_path(&file.name)))?;
+    let mut items = compile_file(file.clone(), config, layout)?;
+    if config.data_type_signature {
+        for item in &mut items {
+            add_data_type_signature(item, data_type_signature);
+        }
+    }
+    add_derives_and_attributes(dsdl, file, config, &mut items)?;
+    if config.namespace_features && layout == Layout::Namespace {
+        // in the module tree, the modules of the types are gated instead
+        for item in &mut items {
+            item.attrs.push(feature_gate(&file.name.namespace));
+        }
+    }
+    Ok(items)
+}
+
+/// Adds the traits in `config.derives` that are valid for the compiled types, and the attributes in `config.type_attributes` matching them
+fn add_derives_and_attributes(dsdl: &DSDL, file: &dsdl_parser::File, config: &CompileConfig, items: &mut [syn::Item]) -> Result<(), CompileError> {
+    let full_name = format!("{}", file.name);
+    let mut attributes = Vec::new();
+    for (_, attribute) in config.type_attributes.iter().filter(|(pattern, _)| config::matches_type_pattern(&full_name, pattern)) {
+        attributes.push(syn::parse_outer_attr(attribute)
+            .map_err(|_| CompileError::new(CompileErrorKind::InvalidAttribute(attribute.clone())).in_file(&file_path(&file.name)))?);
+    }
+
+    let type_name = ident::escape(&file.name.name);
+    let definitions = match file.definition {
+        dsdl_parser::TypeDefinition::Message(ref message) => vec![(type_name, message)],
+        dsdl_parser::TypeDefinition::Service(ref service) => vec![(type_name.clone() + "Request", &service.request), (type_name + "Response", &service.response)],
+    };
+
+    for (name, definition) in definitions {
+        let derives = derives::valid_derives(dsdl, &file.name.namespace, definition, &config.derives);
+        // the constant enums and builders of a type are not extended, only the type itself
+        for item in items.iter_mut().filter(|item| item.ident == name) {
+            if let syn::ItemKind::Enum(_, _) | syn::ItemKind::Struct(_, _) = item.node {
+                for attr in &mut item.attrs {
+                    if let syn::MetaItem::List(ref ident, ref mut traits) = attr.value {
+                        if ident == "derive" {
+                            traits.extend(derives.iter().map(|derive| syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from(derive.as_str())))));
+                        }
+                    }
                 }
-                add_item(new_item, &mut items);
+                item.attrs.extend(attributes.iter().cloned());
             }
         }
-        
-        // insert `pub(crate) use uavcan`
-        items.insert(0,
-                     syn::Item{
-                         ident: syn::Ident::from(""),
-                         vis: syn::Visibility::Crate,
-                         attrs: vec![
-                             syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::List(syn::Ident::from("allow"), vec![
-                                 syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("unused_imports")))
-                             ])},
-                         ],
-                         node: syn::ItemKind::Use(Box::new(syn::ViewPath::Glob(syn::Path{global: false, segments: vec![
-                             syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
-                             syn::PathSegment{ident: syn::Ident::from("types"), parameters: syn::PathParameters::none()},
-                         ]}))),
-                     }
-        );
-                         
-        // insert `extern crate uavcan as uavcan_rs`
-        items.insert(0,
-                     syn::Item{
-                         ident: syn::Ident::from("uavcan_rs"),
-                         vis: syn::Visibility::Inherited,
-                         attrs: vec![
-                             syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::List(syn::Ident::from("allow"), vec![
-                                 syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("unused_imports")))
-                             ])},
-                             syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::Word(syn::Ident::from("macro_use"))}
-                         ],
-                         node: syn::ItemKind::ExternCrate(Some(syn::Ident::from("uavcan"))),
-                     }
-        );
-        items
+    }
+    Ok(())
+}
+
+/// Returns the `#[cfg(feature = "...")]` attribute gating the types of a namespace
+fn feature_gate(namespace: &str) -> syn::Attribute {
+    syn::Attribute{
+        style: syn::AttrStyle::Outer,
+        value: syn::MetaItem::List(syn::Ident::from("cfg"), vec![
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+                syn::Ident::from("feature"),
+                syn::Lit::Str(features::feature_name(namespace), syn::StrStyle::Cooked),
+            )),
+        ]),
+        is_sugared_doc: false,
+    }
+}
+
+/// Puts items into the modules of the namespace of a file
+fn in_namespace_modules(name: &dsdl_parser::FileName, mut items: Vec<syn::Item>) -> Vec<syn::Item> {
+    for mod_name in name.rsplit_namespace() {
+        items = vec![syn::Item{
+            ident: syn::Ident::from(ident::escape(&mod_name)),
+            vis: syn::Visibility::Public,
+            attrs: Vec::new(),
+            node: syn::ItemKind::Mod(Some(items)),
+        }];
+    }
+    items
+}
+
+/// Inserts the items that must precede the compiled DSDL, like `extern crate uavcan as uavcan_rs`
+fn add_preamble(items: &mut Vec<syn::Item>) {
+    // insert `pub(crate) use uavcan`
+    items.insert(0,
+                 syn::Item{
+                     ident: syn::Ident::from(""),
+                     vis: syn::Visibility::Crate,
+                     attrs: vec![
+                         syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::List(syn::Ident::from("allow"), vec![
+                             syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("unused_imports")))
+                         ])},
+                     ],
+                     node: syn::ItemKind::Use(Box::new(syn::ViewPath::Glob(syn::Path{global: false, segments: vec![
+                         syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
+                         syn::PathSegment{ident: syn::Ident::from("types"), parameters: syn::PathParameters::none()},
+                     ]}))),
+                 }
+    );
+                     
+    // insert `extern crate uavcan as uavcan_rs`
+    items.insert(0,
+                 syn::Item{
+                     ident: syn::Ident::from("uavcan_rs"),
+                     vis: syn::Visibility::Inherited,
+                     attrs: vec![
+                         syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::List(syn::Ident::from("allow"), vec![
+                             syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("unused_imports")))
+                         ])},
+                         syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::Word(syn::Ident::from("macro_use"))}
+                     ],
+                     node: syn::ItemKind::ExternCrate(Some(syn::Ident::from("uavcan"))),
+                 }
+    );
+}
+
+/// Returns the path of a DSDL file relative to the DSDL root, like `uavcan/protocol/341.NodeStatus.uavcan`
+fn file_path(name: &dsdl_parser::FileName) -> String {
+    let mut path: String = name.namespace.split('.').map(|segment| format!("{}/", segment)).collect();
+    if let Some(ref id) = name.id {
+        path.push_str(&format!("{}.", id));
+    }
+    path.push_str(&format!("{}.uavcan", name.name));
+    path
+}
+
+/// Returns the data type ID of a type, the ID given in `config.type_ids` takes precedence over the default ID in the file name
+fn type_id(name: &dsdl_parser::FileName, config: &CompileConfig) -> Option<String> {
+    match config.type_ids.get(&format!("{}", name)) {
+        Some(id) => Some(id.to_string()),
+        None => name.id.clone(),
+    }
+}
+
+/// Returns the lines of a definition together with their line number in the DSDL file
+///
+/// The request and response of a service are separated by a line (`---`) that is not part of either definition.
+fn numbered_lines(definition: &dsdl_parser::TypeDefinition) -> Vec<(usize, &dsdl_parser::Line)> {
+    match *definition {
+        dsdl_parser::TypeDefinition::Message(ref message) => message.0.iter().enumerate().map(|(i, line)| (i + 1, line)).collect(),
+        dsdl_parser::TypeDefinition::Service(ref service) => {
+            let response_offset = service.request.0.len() + 1;
+            service.request.0.iter().enumerate().map(|(i, line)| (i + 1, line))
+                .chain(service.response.0.iter().enumerate().map(|(i, line)| (i + 1 + response_offset, line)))
+                .collect()
+        },
     }
 }
 
+/// Returns the full names of the composite types used in a file, together with the line they're used on
+fn composite_types(file: &dsdl_parser::File) -> Vec<(usize, String)> {
+    numbered_lines(&file.definition).into_iter()
+        .filter_map(|(line_number, line)| match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(dsdl_parser::FieldDefinition{field_type: dsdl_parser::Ty::Composite(ref composite), ..}), ..} => {
+                let full_name = match composite.namespace {
+                    Some(ref namespace) => format!("{}.{}", namespace.as_ref(), composite.name.as_ref()),
+                    None => format!("{}.{}", file.name.namespace, composite.name.as_ref()),
+                };
+                Some((line_number, full_name))
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks that every composite type used in a file is defined in the DSDL tree
+fn check_composite_types(dsdl: &DSDL, file: &dsdl_parser::File) -> Result<(), CompileError> {
+    for (line_number, full_name) in composite_types(file) {
+        if dsdl.get_file(&full_name).is_none() {
+            return Err(CompileError::new(CompileErrorKind::UnknownCompositeType(full_name))
+                       .in_file(&file_path(&file.name))
+                       .at_line(line_number));
+        }
+    }
+    Ok(())
+}
+
 
 fn add_data_type_signature(current_item: &mut syn::Item, signature: u64) {
     match current_item.node {
@@ -139,6 +351,673 @@ fn add_data_type_signature(current_item: &mut syn::Item, signature: u64) {
 }
 
 
+fn constants_impl(type_name: String, constants: Vec<syn::ImplItem>) -> syn::Item {
+    syn::Item {
+        ident: syn::Ident::from(type_name.clone()),
+        vis: syn::Visibility::Inherited,
+        attrs: Vec::new(),
+        node: syn::ItemKind::Impl(
+            syn::Unsafety::Normal,
+            syn::ImplPolarity::Positive,
+            syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
+            None,
+            Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(type_name), parameters: syn::PathParameters::none()}]})),
+            constants,
+        ),
+    }
+}
+
+/// A field together with the constants prefixed by its (upper case) name, e.g. `uint2 health` and `HEALTH_OK`
+struct ConstantGroup {
+    field_name: String,
+    field_type: syn::Ty,
+    field_comments: Vec<syn::Attribute>,
+    variants: Vec<(String, String, Vec<syn::Attribute>)>,
+}
+
+fn constant_groups(message: &dsdl_parser::MessageDefinition, config: &CompileConfig) -> Result<Vec<ConstantGroup>, CompileError> {
+    let mut fields = Vec::new();
+    let mut constants = Vec::new();
+    let mut current_comments = Vec::new();
+
+    for line in message.0.iter().cloned() {
+        match line {
+            dsdl_parser::Line::Empty => current_comments = Vec::new(),
+            dsdl_parser::Line::Comment(comment) => current_comments.push(comment.compile(config)?),
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(def), comment: opt_comment} => {
+                if let (dsdl_parser::Ty::Primitive(primitive_type), None, Some(name)) = (def.field_type, def.array, def.name) {
+                    let mut comments = current_comments.clone();
+                    if let Some(comment) = opt_comment {
+                        comments.push(comment.compile(config)?);
+                    }
+                    fields.push((String::from(name.as_ref()), primitive_type, comments));
+                }
+                current_comments = Vec::new();
+            },
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(def), comment: opt_comment} => {
+                let comment = match opt_comment {
+                    Some(comment) => Some(comment.compile(config)?),
+                    None => None,
+                };
+                constants.push((String::from(def.name.as_ref()), def.field_type, comment));
+            },
+            dsdl_parser::Line::Directive{..} => (),
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (field_name, field_type, field_comments) in fields {
+        let field_type_name = primitive_type_name(&field_type.clone().compile(config)?);
+        if !field_type_name.starts_with('u') && !field_type_name.starts_with('i') {
+            continue;
+        }
+
+        let prefix = field_name.to_uppercase() + "_";
+        let variants: Vec<(String, String, Vec<syn::Attribute>)> = constants.iter()
+            .filter(|(name, ty, _)| name.starts_with(&prefix) && *ty == field_type)
+            .map(|(name, _, comment)| (ident::variant_name(&name[prefix.len()..]), ident::escape(name), comment.iter().cloned().collect()))
+            .collect();
+
+        // only group the constants when every one of them becomes a distinct and valid variant name
+        let valid_names = variants.iter().all(|(variant, _, _)| variant.chars().next().is_some_and(|c| c.is_alphabetic()));
+        let distinct_names = variants.iter().enumerate().all(|(i, (variant, _, _))| variants[..i].iter().all(|(other, _, _)| other != variant));
+        if variants.is_empty() || !valid_names || !distinct_names {
+            continue;
+        }
+
+        groups.push(ConstantGroup{
+            field_name,
+            field_type: field_type.compile(config)?,
+            field_comments,
+            variants,
+        });
+    }
+
+    Ok(groups)
+}
+
+/// Returns true if the constant groups of a message definition are compiled into enums
+fn has_constant_enums(message: &dsdl_parser::MessageDefinition, config: &CompileConfig) -> bool {
+    // unions don't have fields to make accessors for
+    config.constant_enums
+        && !message.0.iter().any(|line| line.is_directive())
+        && constant_groups(message, config).map(|groups| !groups.is_empty()).unwrap_or(false)
+}
+
+/// Returns the name of the module holding the constant enums of a type, when they're placed next to the type
+fn constant_enums_module(type_name: &str) -> String {
+    ident::escape(&type_name.to_snake_case())
+}
+
+/// Compiles the constant groups of a struct into enums (in a module named after the struct) and typed accessors
+fn constant_enums(type_name: &str, message: &dsdl_parser::MessageDefinition, config: &CompileConfig, layout: Layout) -> Result<Vec<syn::Item>, CompileError> {
+    if message.0.iter().any(|line| line.is_directive()) {
+        // unions don't have fields to make accessors for
+        return Ok(Vec::new());
+    }
+
+    let groups = constant_groups(message, config)?;
+    if groups.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let module_name = constant_enums_module(type_name);
+    // the paths from the enums to the type, and from the type to the enums
+    let (type_path, enum_path) = match layout {
+        Layout::Namespace => (format!("super::{}", type_name), format!("{}::", module_name)),
+        Layout::TypeModule => (String::from(type_name), String::new()),
+    };
+    let mut module_items = Vec::new();
+    let mut accessors = Vec::new();
+
+    for group in groups {
+        let enum_name = ident::variant_name(&group.field_name);
+        let field_type = {
+            let ty = &group.field_type;
+            quote!(#ty).to_string()
+        };
+
+        let variants: Vec<String> = group.variants.iter().map(|(variant, _, _)| variant.clone()).collect();
+        let mut enum_item = syn::parse_item(&format!(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum {} {{ {} }}",
+            enum_name,
+            variants.join(", "),
+        )).map_err(code_generation_error)?;
+        enum_item.attrs = group.field_comments.iter().cloned().chain(enum_item.attrs).collect();
+        if let syn::ItemKind::Enum(ref mut enum_variants, _) = enum_item.node {
+            for (enum_variant, (_, _, comments)) in enum_variants.iter_mut().zip(group.variants.iter()) {
+                enum_variant.attrs = comments.clone();
+            }
+        }
+        module_items.push(enum_item);
+
+        let try_from_arms: Vec<String> = group.variants.iter()
+            .map(|(variant, constant, _)| format!("if value == {}::{} {{ Ok({}::{}) }}", type_path, constant, enum_name, variant))
+            .collect();
+        module_items.push(syn::parse_item(&format!(
+            "impl ::uavcan_rs::TryFrom<{ty}> for {name} {{ type Error = {ty}; fn try_from(value: {ty}) -> Result<Self, Self::Error> {{ {arms} else {{ Err(value) }} }} }}",
+            ty = field_type,
+            name = enum_name,
+            arms = try_from_arms.join(" else "),
+        )).map_err(code_generation_error)?);
+
+        let from_arms: Vec<String> = group.variants.iter()
+            .map(|(variant, constant, _)| format!("{}::{} => {}::{},", enum_name, variant, type_path, constant))
+            .collect();
+        module_items.push(syn::parse_item(&format!(
+            "impl From<{name}> for {ty} {{ fn from(value: {name}) -> Self {{ match value {{ {arms} }} }} }}",
+            ty = field_type,
+            name = enum_name,
+            arms = from_arms.join(" "),
+        )).map_err(code_generation_error)?);
+
+        accessors.push(format!(
+            "pub fn {field}(&self) -> Result<{path}{name}, {ty}> {{ ::uavcan_rs::TryFrom::try_from(self.{field}) }} \
+             pub fn set_{setter}(&mut self, value: {path}{name}) {{ self.{field} = value.into(); }}",
+            field = ident::escape(&group.field_name),
+            setter = group.field_name,
+            path = enum_path,
+            name = enum_name,
+            ty = field_type,
+        ));
+    }
+
+    let accessors = syn::parse_item(&format!("impl {} {{ {} }}", type_name, accessors.join(" "))).map_err(code_generation_error)?;
+    match layout {
+        Layout::Namespace => Ok(vec![
+            syn::Item {
+                ident: syn::Ident::from(module_name),
+                vis: syn::Visibility::Public,
+                attrs: Vec::new(),
+                node: syn::ItemKind::Mod(Some(module_items)),
+            },
+            accessors,
+        ]),
+        Layout::TypeModule => {
+            module_items.push(accessors);
+            Ok(module_items)
+        },
+    }
+}
+
+/// Returns the native Rust type that is accepted for a compiled type (like `u8` for `::u2`), or `None` if the type is accepted as it is
+fn native_type(ty: &syn::Ty) -> Option<String> {
+    match *ty {
+        syn::Ty::Path(None, ref path) if path.segments.len() == 1 => {
+            let name = path.segments[0].ident.as_ref();
+            let bits: Option<u32> = name.get(1..).and_then(|bits| bits.parse().ok());
+            match (name.chars().next(), bits) {
+                (Some('f'), Some(16)) => Some(String::from("f32")),
+                (Some('u'), Some(bits)) | (Some('i'), Some(bits)) if bits % 8 != 0 => {
+                    Some(format!("{}{}", &name[..1], [8, 16, 32, 64].iter().find(|&&base| base > bits).expect("Primitive types are at most 64 bits")))
+                },
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Returns true if the compiled type is a primitive type, like `bool` or `::u2`
+fn is_primitive_type(ty: &syn::Ty) -> bool {
+    match *ty {
+        syn::Ty::Path(None, ref path) if path.segments.len() == 1 => {
+            let name = path.segments[0].ident.as_ref();
+            let bits = |prefix: &str| name.strip_prefix(prefix).is_some_and(|bits| bits.parse::<u32>().is_ok());
+            name == "bool" || bits("u") || bits("i") || bits("f") || bits("void")
+        },
+        _ => false,
+    }
+}
+
+/// Returns the type of the elements of a compiled (static, dynamic or bit) array or `Option`, or the type itself if it's not an array
+fn element_type(ty: &syn::Ty) -> &syn::Ty {
+    match *ty {
+        syn::Ty::Array(ref element, _) => element,
+        syn::Ty::Path(None, ref path) if is_array_wrapper(ty) => {
+            match path.segments[0].parameters {
+                syn::PathParameters::AngleBracketed(ref data) => match data.types.first() {
+                    Some(syn::Ty::Array(element, _)) => element,
+                    _ => unreachable!("Dynamic and bit arrays are always compiled with an array type"),
+                },
+                _ => unreachable!("Dynamic and bit arrays are always compiled with an array type"),
+            }
+        },
+        syn::Ty::Path(None, ref path) if is_option(ty) => {
+            match path.segments[0].parameters {
+                syn::PathParameters::AngleBracketed(ref data) => &data.types[0],
+                _ => unreachable!("Optional fields are always compiled with a type parameter"),
+            }
+        },
+        _ => ty,
+    }
+}
+
+/// Returns true if the compiled type takes the array it holds as type parameter, like `Dynamic<[T; N]>` and `BitArray<[bool; N]>`
+fn is_array_wrapper(ty: &syn::Ty) -> bool {
+    match *ty {
+        syn::Ty::Path(None, ref path) => path.segments.len() == 1 && ["Dynamic", "BitArray", "DynamicBitArray"].contains(&path.segments[0].ident.as_ref()),
+        _ => false,
+    }
+}
+
+/// Returns true if the compiled type is an `Option`, which `T[<=1]` is compiled into with `CompileConfig::optional_fields`
+fn is_option(ty: &syn::Ty) -> bool {
+    match *ty {
+        syn::Ty::Path(None, ref path) => !path.global && path.segments.len() == 1 && path.segments[0].ident.as_ref() == "Option",
+        _ => false,
+    }
+}
+
+/// Returns true if the compiled type is a static array longer than 32 elements, which `Default` is not implemented for
+fn is_long_array(ty: &syn::Ty) -> bool {
+    match *ty {
+        syn::Ty::Array(_, syn::ConstExpr::Lit(syn::Lit::Int(length, _))) => length > 32,
+        _ => false,
+    }
+}
+
+/// Returns the type of the parameter a field is set with, and the expression converting the parameter (named `parameter`) into the field
+fn native_parameter(ty: &syn::Ty, parameter: &str) -> (String, String) {
+    let convert = format!("::uavcan_rs::types::FromNative::from_native({})", parameter);
+    let native_element_type = |element: &syn::Ty| native_type(element).unwrap_or_else(|| quote!(#element).to_string());
+    match *ty {
+        syn::Ty::Array(ref element, _) if is_primitive_type(element) => (format!("&[{}]", native_element_type(element)), convert),
+        syn::Ty::Path(None, _) if is_array_wrapper(ty) => {
+            let element = element_type(ty);
+            if quote!(#element).to_string() == "u8" {
+                // byte arrays are usually strings
+                (String::from("impl AsRef<[u8]>"), format!("::uavcan_rs::types::FromNative::from_native({}.as_ref())", parameter))
+            } else {
+                (format!("&[{}]", native_element_type(element)), convert)
+            }
+        },
+        syn::Ty::Path(None, _) if is_option(ty) => {
+            (format!("Option<{}>", native_element_type(element_type(ty))), format!("{}.map(::uavcan_rs::types::FromNative::from_native).transpose()", parameter))
+        },
+        _ => (native_element_type(ty), convert),
+    }
+}
+
+/// Compiles a constructor and a builder for a struct, both taking native Rust types and checking that the values fit their fields
+///
+/// `new` takes every field (except void fields) as a parameter, while the builder has a setter named after each field.
+/// Both return a `FieldError` naming the first field whose value doesn't fit.
+fn constructors(type_name: &str, item_kinds: &[syn::ItemKind]) -> Result<Vec<syn::Item>, CompileError> {
+    let fields = match item_kinds.first() {
+        Some(&syn::ItemKind::Struct(syn::VariantData::Struct(ref fields), _)) => fields,
+        // unions are created from one of their variants
+        _ => return Ok(Vec::new()),
+    };
+
+    let is_void = |field: &syn::Field| field.ident.as_ref().is_some_and(|ident| ident.as_ref().starts_with("_v"));
+    if fields.iter().all(is_void) {
+        return Ok(Vec::new());
+    }
+
+    let builder_name = format!("{}Builder", type_name);
+    let mut parameters = Vec::new();
+    let mut new_fields = Vec::new();
+    let mut builder_fields = Vec::new();
+    let mut setters = Vec::new();
+    let mut build_fields = Vec::new();
+
+    for field in fields {
+        let name = field.ident.as_ref().expect("Struct fields are named").to_string();
+        if is_void(field) {
+            new_fields.push(format!("{}: Default::default(),", name));
+            build_fields.push(format!("{}: Default::default(),", name));
+            continue;
+        }
+
+        let ty = &field.ty;
+        let (parameter_type, convert) = native_parameter(ty, &name);
+        parameters.push(format!("{}: {}", name, parameter_type));
+        new_fields.push(format!(
+            "{name}: {convert}.map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, \"{name}\"))?,",
+            name = name,
+            convert = convert,
+        ));
+        let (_, convert) = native_parameter(ty, "value");
+        builder_fields.push(format!("{}: Option<{}>,", name, quote!(#ty)));
+        setters.push(format!(
+            "pub fn {name}(mut self, value: {ty}) -> Self {{ \
+                 match {convert} {{ \
+                     Ok(value) => self.{name} = Some(value), \
+                     Err(error) => if self._error.is_none() {{ self._error = Some(::uavcan_rs::types::NativeError::in_field(error, \"{name}\")) }}, \
+                 }} \
+                 self \
+             }}",
+            name = name,
+            ty = parameter_type,
+            convert = convert,
+        ));
+        build_fields.push(format!("{name}: self.{name}.ok_or(::uavcan_rs::types::FieldError::Missing(\"{name}\"))?,", name = name));
+    }
+
+    // a field named `build` would clash with the method building the struct, these types only get a constructor
+    let has_builder = !fields.iter().any(|field| field.ident.as_ref().is_some_and(|ident| ident.as_ref() == "build"));
+    let builder_method = if has_builder { format!("pub fn builder() -> {builder} {{ {builder}::default() }}", builder = builder_name) } else { String::new() };
+
+    let mut constructor = syn::parse_item(&format!(
+        "impl {name} {{ \
+             pub fn new({parameters}) -> Result<Self, ::uavcan_rs::types::FieldError> {{ Ok({name} {{ {new_fields} }}) }} \
+             {builder_method} \
+         }}",
+        name = type_name,
+        builder_method = builder_method,
+        parameters = parameters.join(", "),
+        new_fields = new_fields.join(" "),
+    )).map_err(code_generation_error)?;
+    if let syn::ItemKind::Impl(_, _, _, _, _, ref mut impl_items) = constructor.node {
+        // the parser doesn't know about tool lints, so the attribute is added afterwards
+        impl_items[0].attrs.push(syn::Attribute{
+            style: syn::AttrStyle::Outer,
+            is_sugared_doc: false,
+            value: syn::MetaItem::List(syn::Ident::from("allow"), vec![syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("clippy::too_many_arguments")))]),
+        });
+    }
+    let mut items = vec![constructor];
+
+    if !has_builder {
+        return Ok(items);
+    }
+
+    items.push(syn::parse_item(&format!(
+        "#[derive(Debug, Clone, Default)] pub struct {builder} {{ {fields} _error: Option<::uavcan_rs::types::FieldError>, }}",
+        builder = builder_name,
+        fields = builder_fields.join(" "),
+    )).map_err(code_generation_error)?);
+
+    items.push(syn::parse_item(&format!(
+        "impl {builder} {{ \
+             {setters} \
+             pub fn build(self) -> Result<{name}, ::uavcan_rs::types::FieldError> {{ \
+                 if let Some(error) = self._error {{ return Err(error); }} \
+                 Ok({name} {{ {build_fields} }}) \
+             }} \
+         }}",
+        name = type_name,
+        builder = builder_name,
+        setters = setters.join(" "),
+        build_fields = build_fields.join(" "),
+    )).map_err(code_generation_error)?);
+
+    Ok(items)
+}
+
+/// Implements `Default` for the types it can't be derived for, unions and structs with arrays longer than 32 elements
+///
+/// Unions default to their first variant, and long arrays are filled with default elements.
+fn default_impl(type_name: &str, item_kinds: &[syn::ItemKind], config: &CompileConfig) -> Result<Vec<syn::Item>, CompileError> {
+    let default = match item_kinds.first() {
+        Some(syn::ItemKind::Enum(variants, _)) if config.derive_default == DeriveDefault::All => {
+            format!("{}::{}(Default::default())", type_name, variants[0].ident)
+        },
+        Some(&syn::ItemKind::Struct(syn::VariantData::Struct(ref fields), _)) if fields.iter().any(|field| is_long_array(&field.ty)) => {
+            let implement_default = match config.derive_default {
+                DeriveDefault::PrimitiveTypes => fields.iter().all(|field| is_primitive_type(element_type(&field.ty))),
+                DeriveDefault::All => true,
+                DeriveDefault::None => false,
+            };
+            if !implement_default {
+                return Ok(Vec::new());
+            }
+            let field_defaults: Vec<String> = fields.iter()
+                .map(|field| {
+                    let name = field.ident.as_ref().expect("Struct fields are named");
+                    if is_long_array(&field.ty) {
+                        format!("{}: ::uavcan_rs::types::__array_from_fn(|_| Default::default()),", name)
+                    } else {
+                        format!("{}: Default::default(),", name)
+                    }
+                })
+                .collect();
+            format!("{} {{ {} }}", type_name, field_defaults.join(" "))
+        },
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(vec![syn::parse_item(&format!(
+        "impl Default for {name} {{ fn default() -> Self {{ {default} }} }}",
+        name = type_name,
+        default = default,
+    )).map_err(code_generation_error)?])
+}
+
+/// Returns the DSDL name of a compiled primitive type, e.g. `uint2` for `::u2`
+fn dsdl_primitive_name(ty: &syn::Ty) -> String {
+    let name = primitive_type_name(ty);
+    if name == "bool" || name.starts_with("void") {
+        return name;
+    }
+    let (kind, bits) = name.split_at(1);
+    match kind {
+        "u" => format!("uint{}", bits),
+        "i" => format!("int{}", bits),
+        _ => format!("float{}", bits),
+    }
+}
+
+/// Returns the `FieldInfo` describing a field, given as a Rust expression
+fn field_info(def: &dsdl_parser::FieldDefinition, namespace: &str, config: &CompileConfig) -> Result<String, CompileError> {
+    let (ty, cast_mode) = match def.field_type {
+        dsdl_parser::Ty::Primitive(_) => {
+            let ty = dsdl_primitive_name(&def.field_type.clone().compile(config)?);
+            let cast_mode = match def.cast_mode {
+                _ if def.field_type.is_void() => String::from("None"),
+                Some(dsdl_parser::CastMode::Truncated) => String::from("Some(::uavcan_rs::reflect::CastMode::Truncated)"),
+                Some(dsdl_parser::CastMode::Saturated) | None => String::from("Some(::uavcan_rs::reflect::CastMode::Saturated)"),
+            };
+            (ty, cast_mode)
+        },
+        dsdl_parser::Ty::Composite(ref composite) => {
+            let composite_namespace = composite.namespace.as_ref().map_or(namespace, |namespace| namespace.as_ref());
+            (format!("{}.{}", composite_namespace, composite.name.as_ref()), String::from("None"))
+        },
+    };
+    let array = match def.array {
+        None => String::from("Scalar"),
+        Some(dsdl_parser::ArrayInfo::Static(size)) => format!("Static({})", size),
+        Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => format!("Dynamic({})", size.saturating_sub(1)),
+        Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => format!("Dynamic({})", size),
+    };
+    Ok(format!(
+        "::uavcan_rs::reflect::FieldInfo{{name: {name:?}, ty: {ty:?}, array: ::uavcan_rs::reflect::ArrayKind::{array}, cast_mode: {cast_mode}}},",
+        name = def.name.as_ref().map_or("", |name| name.as_ref()),
+        ty = ty,
+        array = array,
+        cast_mode = cast_mode,
+    ))
+}
+
+/// Returns the `ConstantInfo` describing a constant, given as a Rust expression
+fn constant_info(def: dsdl_parser::ConstDefinition, config: &CompileConfig) -> Result<String, CompileError> {
+    let ty = def.field_type.compile(config)?;
+    let type_name = dsdl_primitive_name(&ty);
+    let value = match type_name.chars().next() {
+        Some('b') => match def.constant {
+            dsdl_parser::Const::Bool(value) => Ok(format!("Bool({})", value)),
+            ref constant => integer_value(constant.clone()).map(|value| format!("Bool({})", value != 0)),
+        },
+        Some('f') => float_value(def.constant.clone()).map(|value| format!("Float({:?})", value)),
+        Some('i') => integer_value(def.constant.clone()).map(|value| format!("Signed({})", value)),
+        _ => integer_value(def.constant.clone()).map(|value| format!("Unsigned({})", value)),
+    }.map_err(|reason| CompileError::new(CompileErrorKind::InvalidConstant(String::from(def.name.as_ref()), reason)))?;
+    Ok(format!(
+        "::uavcan_rs::reflect::ConstantInfo{{name: {name:?}, ty: {ty:?}, value: ::uavcan_rs::reflect::Value::{value}}},",
+        name = def.name.as_ref(),
+        ty = type_name,
+        value = value,
+    ))
+}
+
+/// Implements `Reflect` for a compiled type, describing its fields and constants as they're defined in DSDL
+///
+/// `namespace` is the namespace of the DSDL file, which composite types without a namespace are in.
+fn reflect_impl(type_name: &str, namespace: &str, definition: &dsdl_parser::MessageDefinition, item_kinds: &[syn::ItemKind], config: &CompileConfig) -> Result<Vec<syn::Item>, CompileError> {
+    let mut fields = Vec::new();
+    let mut constants = Vec::new();
+    for line in &definition.0 {
+        match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref def), ..} => fields.push(field_info(def, namespace, config)?),
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(ref def), ..} => constants.push(constant_info(def.clone(), config)?),
+            _ => (),
+        }
+    }
+
+    let visit_field = |value: String, index: usize| format!("::uavcan_rs::reflect::VisitField::visit_field({}, &Self::FIELDS[{}], visitor)", value, index);
+    let (union, visit) = match item_kinds.first() {
+        Some(syn::ItemKind::Enum(variants, _)) => {
+            let arms: Vec<String> = variants.iter().enumerate()
+                .map(|(i, variant)| format!("{}::{}(ref value) => {},", type_name, variant.ident, visit_field(String::from("value"), i)))
+                .collect();
+            (true, format!("match *self {{ {} }}", arms.join(" ")))
+        },
+        Some(syn::ItemKind::Struct(syn::VariantData::Struct(struct_fields), _)) => {
+            let statements: Vec<String> = struct_fields.iter().enumerate()
+                .map(|(i, field)| format!("{};", visit_field(format!("&self.{}", field.ident.as_ref().expect("Struct fields are named")), i)))
+                .collect();
+            (false, statements.join(" "))
+        },
+        _ => unreachable!("Messages are always compiled to a struct or an enum"),
+    };
+
+    Ok(vec![syn::parse_item(&format!(
+        "impl ::uavcan_rs::reflect::Reflect for {name} {{ \
+             const FIELDS: &'static [::uavcan_rs::reflect::FieldInfo] = &[{fields}]; \
+             const CONSTANTS: &'static [::uavcan_rs::reflect::ConstantInfo] = &[{constants}]; \
+             const UNION: bool = {union}; \
+             fn visit<V: ::uavcan_rs::reflect::Visitor>(&self, {visitor}: &mut V) {{ {visit} }} \
+         }}",
+        name = type_name,
+        fields = fields.join(" "),
+        constants = constants.join(" "),
+        union = union,
+        visitor = if fields.is_empty() { "_visitor" } else { "visitor" },
+        visit = visit,
+    )).map_err(code_generation_error)?])
+}
+
+/// Implements `uavcan::text::Parse`, `Display` and `FromStr` with the text format of `uavcan::text`, on top of `Reflect`
+fn text_impl(type_name: &str, definition: &dsdl_parser::MessageDefinition, item_kinds: &[syn::ItemKind]) -> Result<Vec<syn::Item>, CompileError> {
+    // void fields are skipped in the text, they're always their default
+    let voids: Vec<bool> = definition.0.iter()
+        .filter_map(|line| match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref def), ..} => Some(def.name.is_none()),
+            _ => None,
+        })
+        .collect();
+    let parse_field = |index: usize| if voids[index] {
+        String::from("Default::default()")
+    } else {
+        format!("parser.field(&<Self as ::uavcan_rs::reflect::Reflect>::FIELDS[{}])?", index)
+    };
+
+    let (parse, uses_parser) = match item_kinds.first() {
+        Some(syn::ItemKind::Enum(variants, _)) => {
+            let arms: Vec<String> = variants.iter().enumerate()
+                .map(|(i, variant)| format!("{} => Ok({}::{}({})),", i, type_name, variant.ident, parse_field(i)))
+                .collect();
+            (format!("match parser.variant(<Self as ::uavcan_rs::reflect::Reflect>::FIELDS)? {{ {} _ => unreachable!(\"The variant is one of the fields\"), }}", arms.join(" ")), true)
+        },
+        Some(syn::ItemKind::Struct(syn::VariantData::Struct(struct_fields), _)) => {
+            let fields: Vec<String> = struct_fields.iter().enumerate()
+                .map(|(i, field)| format!("{}: {},", field.ident.as_ref().expect("Struct fields are named"), parse_field(i)))
+                .collect();
+            (format!("Ok({} {{ {} }})", type_name, fields.join(" ")), voids.contains(&false))
+        },
+        _ => unreachable!("Messages are always compiled to a struct or an enum"),
+    };
+
+    let items = [
+        format!(
+            "impl ::uavcan_rs::text::Parse for {name} {{ \
+                 fn parse_fields({parser}: &mut ::uavcan_rs::text::Parser) -> Result<Self, ::uavcan_rs::text::ParseError> {{ {parse} }} \
+             }}",
+            name = type_name,
+            parser = if uses_parser { "parser" } else { "_parser" },
+            parse = parse,
+        ),
+        format!(
+            "impl ::uavcan_rs::text::fmt::Display for {} {{ \
+                 fn fmt(&self, f: &mut ::uavcan_rs::text::fmt::Formatter) -> ::uavcan_rs::text::fmt::Result {{ ::uavcan_rs::text::write(self, f) }} \
+             }}",
+            type_name,
+        ),
+        format!(
+            "impl ::uavcan_rs::text::FromStr for {} {{ \
+                 type Err = ::uavcan_rs::text::ParseError; \
+                 fn from_str(text: &str) -> Result<Self, Self::Err> {{ ::uavcan_rs::text::parse(text) }} \
+             }}",
+            type_name,
+        ),
+    ];
+    items.iter().map(|item| syn::parse_item(item).map_err(code_generation_error)).collect()
+}
+
+/// Implements `Arbitrary` for a compiled type, creating every field with `uavcan::arbitrary_field` and choosing the variant of unions
+fn arbitrary_impl(type_name: &str, item_kinds: &[syn::ItemKind]) -> Result<Vec<syn::Item>, CompileError> {
+    let (body, uses_input) = match item_kinds.first() {
+        Some(syn::ItemKind::Enum(variants, _)) => {
+            let arms: Vec<String> = variants.iter().enumerate()
+                .map(|(i, variant)| format!("{} => Ok({}::{}(::uavcan_rs::arbitrary_field::arbitrary(u)?)),", i, type_name, variant.ident))
+                .collect();
+            (format!("match u.int_in_range(0..={})? {{ {} _ => unreachable!(\"The variant is one of the fields\"), }}", variants.len().saturating_sub(1), arms.join(" ")), true)
+        },
+        Some(syn::ItemKind::Struct(syn::VariantData::Struct(struct_fields), _)) => {
+            let fields: Vec<String> = struct_fields.iter()
+                .map(|field| format!("{}: ::uavcan_rs::arbitrary_field::arbitrary(u)?,", field.ident.as_ref().expect("Struct fields are named")))
+                .collect();
+            (format!("Ok({} {{ {} }})", type_name, fields.join(" ")), !fields.is_empty())
+        },
+        _ => unreachable!("Messages are always compiled to a struct or an enum"),
+    };
+
+    let item = format!(
+        "impl<'a> ::uavcan_rs::arbitrary_field::Arbitrary<'a> for {name} {{ \
+             fn arbitrary({u}: &mut ::uavcan_rs::arbitrary_field::Unstructured<'a>) -> ::uavcan_rs::arbitrary_field::Result<Self> {{ {body} }} \
+         }}",
+        name = type_name,
+        u = if uses_input { "u" } else { "_u" },
+        body = body,
+    );
+    Ok(vec![syn::parse_item(&item).map_err(code_generation_error)?])
+}
+
+/// Returns the `visit_arbitrary_types` function, visiting every compiled type accepted by `filter` with a `uavcan::arbitrary_field::TypeVisitor`
+fn arbitrary_types_visitor<F: Fn(&dsdl_parser::File) -> bool>(dsdl: &DSDL, config: &CompileConfig, filter: F) -> Result<syn::Item, CompileError> {
+    let mut visits = Vec::new();
+    for file in dsdl.files().into_iter().filter(|file| filter(file) && config.extern_path(&file.name.namespace).is_none()) {
+        let gate = if config.namespace_features {
+            format!("#[cfg(feature = \"{}\")] ", features::feature_name(&file.name.namespace))
+        } else {
+            String::new()
+        };
+        let path: String = file.name.namespace.split('.').map(|segment| format!("::{}", ident::escape(segment))).collect();
+        let type_name = ident::escape(&file.name.name);
+        let type_names = match file.definition {
+            dsdl_parser::TypeDefinition::Message(_) => vec![type_name],
+            dsdl_parser::TypeDefinition::Service(_) => vec![type_name.clone() + "Request", type_name + "Response"],
+        };
+        for type_name in type_names {
+            visits.push(format!("{}visitor.visit::<{}::{}>();", gate, path, type_name));
+        }
+    }
+
+    let item = format!(
+        "#[doc = \" Visits every compiled type, like for checking them with `uavcan::arbitrary_field::check_round_trip`\"] \
+         #[allow(unused_variables)] \
+         pub fn visit_arbitrary_types<V: ::uavcan_rs::arbitrary_field::TypeVisitor>(visitor: &mut V) {{ {} }}",
+        visits.join(" "),
+    );
+    syn::parse_item(&item).map_err(code_generation_error)
+}
+
+fn code_generation_error<T: ToString>(reason: T) -> CompileError {
+    CompileError::new(CompileErrorKind::CodeGeneration(reason.to_string()))
+}
+
 fn add_item(new_item: syn::Item, items: &mut Vec<syn::Item>) {
     if let (module_name, syn::ItemKind::Mod(Some(new_sub_items))) = (&new_item.ident.clone(), new_item.node.clone()) {
         match items.iter_mut().find(|x| {
@@ -164,255 +1043,362 @@ fn add_item(new_item: syn::Item, items: &mut Vec<syn::Item>) {
             
 
 impl Compile<Vec<syn::Item>> for dsdl_parser::File {
-    fn compile(self, config: &CompileConfig) -> Vec<syn::Item> {
-        let mut items = Vec::new();
-        let dsdl_signature = self.clone().normalize().dsdl_signature();
-        match self.definition {
-            dsdl_parser::TypeDefinition::Message(message) => {
-                let (item_kinds, struct_attributes) = message.compile(config);
-                for item_kind in item_kinds {
-                    
-                    let attrs = match item_kind {
-                        syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
-                            let mut attrs = struct_attributes.clone();
-                            attrs.push(syn::Attribute{
-                                style: syn::AttrStyle::Outer,
-                                value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
-                                is_sugared_doc: true,
-                            });
-                            attrs
-                        },
-                        _ => Vec::new(),
-                    };
-                    
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone()),
-                        vis: syn::Visibility::Public,
-                        attrs: attrs,
-                        node: item_kind,
-                    });
-                }
+    fn compile(self, config: &CompileConfig) -> Result<Vec<syn::Item>, CompileError> {
+        let name = self.name.clone();
+        let items = compile_file(self, config, Layout::Namespace)?;
+        Ok(in_namespace_modules(&name, items))
+    }
+}
 
-                if let Some(ref id) = self.name.id {
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone()),
-                        vis: syn::Visibility::Inherited,
-                        attrs: Vec::new(),
-                        node: syn::ItemKind::Impl(
-                            syn::Unsafety::Normal,
-                            syn::ImplPolarity::Positive,
-                            syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
-                            Some(syn::Path{global: true, segments: vec![
-                                syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
-                                syn::PathSegment{ident: syn::Ident::from("Message"), parameters: syn::PathParameters::none()}
-                            ]}),
-                            Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(self.name.name.clone()), parameters: syn::PathParameters::none()}]})),
-                            vec![
-                                syn::ImplItem{
-                                    ident: syn::Ident::from("TYPE_ID"),
-                                    vis: syn::Visibility::Inherited,
-                                    defaultness: syn::Defaultness::Final,
-                                    attrs: Vec::new(),
-                                    node: syn::ImplItemKind::Const(
-                                        syn::parse::ty("Option<u16>").expect(""),
-                                        syn::parse::expr(&format!("Some({})", id)).expect(""),
-                                    ),
-                                }
-                            ],
-                        ),
-                    });
-                }                           
-            },
-            dsdl_parser::TypeDefinition::Service(service) => {
-                let (item_kinds_req, struct_attributes_req) = service.request.compile(config);
-                let (item_kinds_res, struct_attributes_res) = service.response.compile(config);
+/// Where the items compiled from a DSDL file are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// Directly in the namespace module, with the constant enums of a type in a module named after the type
+    Namespace,
+
+    /// In a module named after the type, together with its constant enums
+    TypeModule,
+}
+
+/// Compiles a DSDL file into the items defining its type(s), without the enclosing namespace modules
+fn compile_file(file: dsdl_parser::File, config: &CompileConfig, layout: Layout) -> Result<Vec<syn::Item>, CompileError> {
+    let path = file_path(&file.name);
+    let mut items = Vec::new();
+    let dsdl_signature = file.clone().normalize().dsdl_signature();
+    let full_name = format!("{}", file.name);
+    let type_name = ident::escape(&file.name.name);
+    match file.definition {
+        dsdl_parser::TypeDefinition::Message(message) => {
+            let enum_items = if config.constant_enums { constant_enums(&type_name, &message, config, layout).map_err(|error| error.in_file(&path))? } else { Vec::new() };
+            let (item_kinds, struct_attributes, constants) = message.clone().compile(config).map_err(|error| error.in_file(&path))?;
+            let constructor_items = if config.constructors { constructors(&type_name, &item_kinds)? } else { Vec::new() };
+            let default_items = default_impl(&type_name, &item_kinds, config)?;
+            let mut reflect_items = if config.reflect || config.text {
+                reflect_impl(&type_name, &file.name.namespace, &message, &item_kinds, config).map_err(|error| error.in_file(&path))?
+            } else {
+                Vec::new()
+            };
+            if config.text {
+                reflect_items.extend(text_impl(&type_name, &message, &item_kinds)?);
+            }
+            if config.arbitrary {
+                reflect_items.extend(arbitrary_impl(&type_name, &item_kinds)?);
+            }
+            for item_kind in item_kinds {
                 
-                for item_kind in item_kinds_req {
-
-                    let attrs = match item_kind {
-                        syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
-                            let mut attrs = struct_attributes_req.clone();
-                            attrs.push(syn::Attribute{
-                                style: syn::AttrStyle::Outer,
-                                value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
-                                is_sugared_doc: true,
-                            });
-                            attrs
-                        },
-                        _ => Vec::new(),
-                    };
-                    
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone() + "Request"),
-                        vis: syn::Visibility::Public,
-                        attrs: attrs,
-                        node: item_kind,
-                    });
-                    
-                }
+                let attrs = match item_kind {
+                    syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
+                        let mut attrs = struct_attributes.clone();
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("FullName"), syn::Lit::Str(full_name.clone(), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs
+                    },
+                    _ => Vec::new(),
+                };
                 
-                for item_kind in item_kinds_res {
-                    
-                    let attrs = match item_kind {
-                        syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
-                            let mut attrs = struct_attributes_res.clone();
-                            attrs.push(syn::Attribute{
-                                style: syn::AttrStyle::Outer,
-                                value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
-                                is_sugared_doc: true,
-                            });
-                            attrs
-                        },
-                        _ => Vec::new(),
-                    };
-                    
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone() + "Response"),
-                        vis: syn::Visibility::Public,
-                        attrs: attrs,
-                        node: item_kind,
-                    });
-                    
-                }
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone()),
+                    vis: syn::Visibility::Public,
+                    attrs: attrs,
+                    node: item_kind,
+                });
+            }
 
-                if let Some(ref id) = self.name.id {
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone() + "Request"),
-                        vis: syn::Visibility::Inherited,
-                        attrs: Vec::new(),
-                        node: syn::ItemKind::Impl(
-                            syn::Unsafety::Normal,
-                            syn::ImplPolarity::Positive,
-                            syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
-                            Some(syn::Path{global: true, segments: vec![
-                                syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
-                                syn::PathSegment{ident: syn::Ident::from("Request"), parameters: syn::PathParameters::none()}
-                            ]}),
-                            Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(self.name.name.clone() + "Request"), parameters: syn::PathParameters::none()}]})),
-                            vec![
-                                syn::ImplItem{
-                                    ident: syn::Ident::from("RESPONSE"),
-                                    vis: syn::Visibility::Inherited,
-                                    defaultness: syn::Defaultness::Final,
-                                    attrs: Vec::new(),
-                                    node: syn::ImplItemKind::Type(
-                                        syn::parse::ty(&format!("{}Response", self.name.name.clone())).expect(""),
-                                    ),
-                                },
-                                syn::ImplItem{
-                                    ident: syn::Ident::from("TYPE_ID"),
-                                    vis: syn::Visibility::Inherited,
-                                    defaultness: syn::Defaultness::Final,
-                                    attrs: Vec::new(),
-                                    node: syn::ImplItemKind::Const(
-                                        syn::parse::ty("Option<u8>").expect(""),
-                                        syn::parse::expr(&format!("Some({})", id)).expect(""),
-                                    ),
-                                }
-                            ],
-                        ),
-                    });
-
-                    items.push(syn::Item {
-                        ident: syn::Ident::from(self.name.name.clone() + "Response"),
-                        vis: syn::Visibility::Inherited,
-                        attrs: Vec::new(),
-                        node: syn::ItemKind::Impl(
-                            syn::Unsafety::Normal,
-                            syn::ImplPolarity::Positive,
-                            syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
-                            Some(syn::Path{global: true, segments: vec![
-                                syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
-                                syn::PathSegment{ident: syn::Ident::from("Response"), parameters: syn::PathParameters::none()}
-                            ]}),
-                            Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(self.name.name.clone() + "Response"), parameters: syn::PathParameters::none()}]})),
-                            vec![
-                                syn::ImplItem{
-                                    ident: syn::Ident::from("REQUEST"),
-                                    vis: syn::Visibility::Inherited,
-                                    defaultness: syn::Defaultness::Final,
-                                    attrs: Vec::new(),
-                                    node: syn::ImplItemKind::Type(
-                                        syn::parse::ty(&format!("{}Request", self.name.name.clone())).expect(""),
-                                    ),
-                                },
-                                syn::ImplItem{
-                                    ident: syn::Ident::from("TYPE_ID"),
-                                    vis: syn::Visibility::Inherited,
-                                    defaultness: syn::Defaultness::Final,
-                                    attrs: Vec::new(),
-                                    node: syn::ImplItemKind::Const(
-                                        syn::parse::ty("Option<u8>").expect(""),
-                                        syn::parse::expr(&format!("Some({})", id)).expect(""),
-                                    ),
-                                }
-                            ],
-                        ),
-                    });
+            if !constants.is_empty() {
+                items.push(constants_impl(type_name.clone(), constants));
+            }
 
-                }
-                    
-            },
-        }
+            items.extend(enum_items);
+            items.extend(default_items);
+            items.extend(constructor_items);
+            items.extend(reflect_items);
 
-        // put all the items into the correct namespace
-        for mod_name in self.name.rsplit_namespace() {
-            items = vec![syn::Item{
-                ident: syn::Ident::from(mod_name),
-                vis: syn::Visibility::Public,
-                attrs: Vec::new(),
-                node: syn::ItemKind::Mod(Some(items)),
-            }];
-        }
+            if let Some(id) = type_id(&file.name, config) {
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone()),
+                    vis: syn::Visibility::Inherited,
+                    attrs: Vec::new(),
+                    node: syn::ItemKind::Impl(
+                        syn::Unsafety::Normal,
+                        syn::ImplPolarity::Positive,
+                        syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
+                        Some(syn::Path{global: true, segments: vec![
+                            syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
+                            syn::PathSegment{ident: syn::Ident::from("Message"), parameters: syn::PathParameters::none()}
+                        ]}),
+                        Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(type_name.clone()), parameters: syn::PathParameters::none()}]})),
+                        vec![
+                            syn::ImplItem{
+                                ident: syn::Ident::from("TYPE_ID"),
+                                vis: syn::Visibility::Inherited,
+                                defaultness: syn::Defaultness::Final,
+                                attrs: Vec::new(),
+                                node: syn::ImplItemKind::Const(
+                                    syn::parse_type("Option<u16>").map_err(code_generation_error)?,
+                                    syn::parse_expr(&format!("Some({})", id)).map_err(code_generation_error)?,
+                                ),
+                            }
+                        ],
+                    ),
+                });
+            }                           
+        },
+        dsdl_parser::TypeDefinition::Service(service) => {
+            let (enum_items_req, enum_items_res) = if config.constant_enums {
+                // the request and response can have enums with the same name, so they're always kept in separate modules
+                (constant_enums(&(type_name.clone() + "Request"), &service.request, config, Layout::Namespace).map_err(|error| error.in_file(&path))?,
+                 constant_enums(&(type_name.clone() + "Response"), &service.response, config, Layout::Namespace).map_err(|error| error.in_file(&path))?)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            let response_offset = service.request.0.len() + 1;
+            let (item_kinds_req, struct_attributes_req, constants_req) = service.request.clone().compile(config).map_err(|error| error.in_file(&path))?;
+            let (item_kinds_res, struct_attributes_res, constants_res) = service.response.clone().compile(config).map_err(|error| error.offset_line(response_offset).in_file(&path))?;
+            let (constructor_items_req, constructor_items_res) = if config.constructors {
+                (constructors(&(type_name.clone() + "Request"), &item_kinds_req)?, constructors(&(type_name.clone() + "Response"), &item_kinds_res)?)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            let default_items_req = default_impl(&(type_name.clone() + "Request"), &item_kinds_req, config)?;
+            let default_items_res = default_impl(&(type_name.clone() + "Response"), &item_kinds_res, config)?;
+            let (mut reflect_items_req, mut reflect_items_res) = if config.reflect || config.text {
+                (reflect_impl(&(type_name.clone() + "Request"), &file.name.namespace, &service.request, &item_kinds_req, config).map_err(|error| error.in_file(&path))?,
+                 reflect_impl(&(type_name.clone() + "Response"), &file.name.namespace, &service.response, &item_kinds_res, config).map_err(|error| error.in_file(&path))?)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            if config.text {
+                reflect_items_req.extend(text_impl(&(type_name.clone() + "Request"), &service.request, &item_kinds_req)?);
+                reflect_items_res.extend(text_impl(&(type_name.clone() + "Response"), &service.response, &item_kinds_res)?);
+            }
+            if config.arbitrary {
+                reflect_items_req.extend(arbitrary_impl(&(type_name.clone() + "Request"), &item_kinds_req)?);
+                reflect_items_res.extend(arbitrary_impl(&(type_name.clone() + "Response"), &item_kinds_res)?);
+            }
+            
+            for item_kind in item_kinds_req {
+
+                let attrs = match item_kind {
+                    syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
+                        let mut attrs = struct_attributes_req.clone();
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("FullName"), syn::Lit::Str(full_name.clone(), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs
+                    },
+                    _ => Vec::new(),
+                };
+                
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone() + "Request"),
+                    vis: syn::Visibility::Public,
+                    attrs: attrs,
+                    node: item_kind,
+                });
+                
+            }
+            
+            for item_kind in item_kinds_res {
+                
+                let attrs = match item_kind {
+                    syn::ItemKind::Enum(_,_) | syn::ItemKind::Struct(_,_) => {
+                        let mut attrs = struct_attributes_res.clone();
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("DSDLSignature"), syn::Lit::Str(format!("0x{:x}", dsdl_signature), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs.push(syn::Attribute{
+                            style: syn::AttrStyle::Outer,
+                            value: syn::MetaItem::NameValue(syn::Ident::from("FullName"), syn::Lit::Str(full_name.clone(), syn::StrStyle::Cooked)),
+                            is_sugared_doc: true,
+                        });
+                        attrs
+                    },
+                    _ => Vec::new(),
+                };
+                
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone() + "Response"),
+                    vis: syn::Visibility::Public,
+                    attrs: attrs,
+                    node: item_kind,
+                });
+                
+            }
 
-        items        
+            if !constants_req.is_empty() {
+                items.push(constants_impl(type_name.clone() + "Request", constants_req));
+            }
+
+            if !constants_res.is_empty() {
+                items.push(constants_impl(type_name.clone() + "Response", constants_res));
+            }
+
+            items.extend(enum_items_req);
+            items.extend(enum_items_res);
+            items.extend(default_items_req);
+            items.extend(default_items_res);
+            items.extend(constructor_items_req);
+            items.extend(constructor_items_res);
+            items.extend(reflect_items_req);
+            items.extend(reflect_items_res);
+
+            if let Some(id) = type_id(&file.name, config) {
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone() + "Request"),
+                    vis: syn::Visibility::Inherited,
+                    attrs: Vec::new(),
+                    node: syn::ItemKind::Impl(
+                        syn::Unsafety::Normal,
+                        syn::ImplPolarity::Positive,
+                        syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
+                        Some(syn::Path{global: true, segments: vec![
+                            syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
+                            syn::PathSegment{ident: syn::Ident::from("Request"), parameters: syn::PathParameters::none()}
+                        ]}),
+                        Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(type_name.clone() + "Request"), parameters: syn::PathParameters::none()}]})),
+                        vec![
+                            syn::ImplItem{
+                                ident: syn::Ident::from("RESPONSE"),
+                                vis: syn::Visibility::Inherited,
+                                defaultness: syn::Defaultness::Final,
+                                attrs: Vec::new(),
+                                node: syn::ImplItemKind::Type(
+                                    syn::parse_type(&format!("{}Response", type_name.clone())).map_err(code_generation_error)?,
+                                ),
+                            },
+                            syn::ImplItem{
+                                ident: syn::Ident::from("TYPE_ID"),
+                                vis: syn::Visibility::Inherited,
+                                defaultness: syn::Defaultness::Final,
+                                attrs: Vec::new(),
+                                node: syn::ImplItemKind::Const(
+                                    syn::parse_type("Option<u8>").map_err(code_generation_error)?,
+                                    syn::parse_expr(&format!("Some({})", id)).map_err(code_generation_error)?,
+                                ),
+                            }
+                        ],
+                    ),
+                });
+
+                items.push(syn::Item {
+                    ident: syn::Ident::from(type_name.clone() + "Response"),
+                    vis: syn::Visibility::Inherited,
+                    attrs: Vec::new(),
+                    node: syn::ItemKind::Impl(
+                        syn::Unsafety::Normal,
+                        syn::ImplPolarity::Positive,
+                        syn::Generics{lifetimes: Vec::new(), ty_params: Vec::new(), where_clause: syn::WhereClause::none()},
+                        Some(syn::Path{global: true, segments: vec![
+                            syn::PathSegment{ident: syn::Ident::from("uavcan_rs"), parameters: syn::PathParameters::none()},
+                            syn::PathSegment{ident: syn::Ident::from("Response"), parameters: syn::PathParameters::none()}
+                        ]}),
+                        Box::new(syn::Ty::Path(None, syn::Path{global: false, segments: vec![syn::PathSegment{ident: syn::Ident::from(type_name.clone() + "Response"), parameters: syn::PathParameters::none()}]})),
+                        vec![
+                            syn::ImplItem{
+                                ident: syn::Ident::from("REQUEST"),
+                                vis: syn::Visibility::Inherited,
+                                defaultness: syn::Defaultness::Final,
+                                attrs: Vec::new(),
+                                node: syn::ImplItemKind::Type(
+                                    syn::parse_type(&format!("{}Request", type_name.clone())).map_err(code_generation_error)?,
+                                ),
+                            },
+                            syn::ImplItem{
+                                ident: syn::Ident::from("TYPE_ID"),
+                                vis: syn::Visibility::Inherited,
+                                defaultness: syn::Defaultness::Final,
+                                attrs: Vec::new(),
+                                node: syn::ImplItemKind::Const(
+                                    syn::parse_type("Option<u8>").map_err(code_generation_error)?,
+                                    syn::parse_expr(&format!("Some({})", id)).map_err(code_generation_error)?,
+                                ),
+                            }
+                        ],
+                    ),
+                });
+
+            }
+                
+        },
     }
+
+    Ok(items)
 }
 
         
-impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>)> for dsdl_parser::MessageDefinition {
-    fn compile(self, config: &CompileConfig) -> (Vec<syn::ItemKind>, Vec<syn::Attribute>) {
-        let (directives, not_directives): (Vec<dsdl_parser::Line>, Vec<dsdl_parser::Line>) = self.0.into_iter().partition(|x| x.is_directive());
+impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>, Vec<syn::ImplItem>)> for dsdl_parser::MessageDefinition {
+    fn compile(self, config: &CompileConfig) -> Result<(Vec<syn::ItemKind>, Vec<syn::Attribute>, Vec<syn::ImplItem>), CompileError> {
+        let numbered_lines = self.0.into_iter().enumerate().map(|(i, line)| (i + 1, line));
+        let (directives, not_directives): (Vec<_>, Vec<_>) = numbered_lines.partition(|(_, x)| x.is_directive());
         let mut items = Vec::new();
         
         // first scan through directives
         let mut union = false;
 
-        for directive in directives {
+        for (_, directive) in directives {
             if let dsdl_parser::Line::Directive{directive: dsdl_parser::Directive::Union, ..} = directive {
                 union = true;
             }
         }
         let mut current_comments = Vec::new();
         
-        for line in not_directives.clone() {
+        for (_, line) in not_directives.clone() {
             if let dsdl_parser::Line::Comment(comment) = line {
-                current_comments.push(comment.compile(config));
+                current_comments.push(comment.compile(config)?);
             } else {
                 break
             }
         }
         let mut attributes = current_comments.clone();
+        let mut constants = Vec::new();
+        let mut names = ident::Names::default();
         let mut void_number = 0;
         let mut only_primitive_types = true;
 
         if union {
             let mut variants = Vec::new();
             current_comments = Vec::new();
+            let mut comments_above = Vec::new();
             
-            for line in not_directives {
+            for (line_number, line) in not_directives {
+                check_name_clash(&line, union, &mut names).map_err(|error| error.at_line(line_number))?;
                 match line {
-                    dsdl_parser::Line::Empty => current_comments = Vec::new(),
-                    dsdl_parser::Line::Comment(comment) => current_comments.push(comment.compile(config)),
+                    dsdl_parser::Line::Empty => {
+                        current_comments = Vec::new();
+                        comments_above = Vec::new();
+                    },
+                    dsdl_parser::Line::Comment(comment) => {
+                        let comment = comment.compile(config)?;
+                        current_comments.push(comment.clone());
+                        comments_above.push(comment);
+                    },
                     dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(def), comment: opt_comment} => {
+                        comments_above = Vec::new();
                         if let dsdl_parser::Ty::Composite(_) = def.field_type {
                             only_primitive_types = false;
                         }
                         if let Some(comment) = opt_comment {
-                            current_comments.push(comment.compile(config));
+                            current_comments.push(comment.compile(config)?);
                         }
-                        let mut variant: syn::Variant = def.clone().compile(config);
-                        variant.attrs = current_comments.clone();
+                        let mut variant: syn::Variant = def.clone().compile(config).map_err(|error| error.at_line(line_number))?;
+                        variant.attrs = current_comments.iter().cloned().chain(variant.attrs).collect();
                         if def.field_type.is_void() {
                             variant.ident = syn::Ident::from(format!("_V{}", void_number));
                             void_number += 1;
@@ -421,7 +1407,18 @@ impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>)> for dsdl_parser::Message
                         
                         current_comments = Vec::new();
                     },
-                    dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(_), ..} => (), // const definitions is only used in the impls
+                    dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(def), comment: opt_comment} => {
+                        // only the comments directly above a constant are its own, while the comments above a group of
+                        // constants usually describe the field that follows them, so they're not reset
+                        let mut constant: syn::ImplItem = def.compile(config).map_err(|error| error.at_line(line_number))?;
+                        let original_name_docs = constant.attrs;
+                        constant.attrs = ::std::mem::take(&mut comments_above);
+                        if let Some(comment) = opt_comment {
+                            constant.attrs.push(comment.compile(config)?);
+                        }
+                        constant.attrs.extend(original_name_docs);
+                        constants.push(constant);
+                    },
                     dsdl_parser::Line::Directive{..} => unreachable!("All directives was removed at the start"),
                 }
             }
@@ -430,29 +1427,50 @@ impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>)> for dsdl_parser::Message
         } else {
             let mut fields = Vec::new();
             current_comments = Vec::new();
+            let mut comments_above = Vec::new();
             
-            for line in not_directives {
+            for (line_number, line) in not_directives {
+                check_name_clash(&line, union, &mut names).map_err(|error| error.at_line(line_number))?;
                 match line {
-                    dsdl_parser::Line::Empty => current_comments = Vec::new(),
-                    dsdl_parser::Line::Comment(comment) => current_comments.push(comment.compile(config)),
+                    dsdl_parser::Line::Empty => {
+                        current_comments = Vec::new();
+                        comments_above = Vec::new();
+                    },
+                    dsdl_parser::Line::Comment(comment) => {
+                        let comment = comment.compile(config)?;
+                        current_comments.push(comment.clone());
+                        comments_above.push(comment);
+                    },
                     dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(def), comment: opt_comment} => {
+                        comments_above = Vec::new();
                         if let dsdl_parser::Ty::Composite(_) = def.field_type {
                             only_primitive_types = false;
                         }
                         if let Some(comment) = opt_comment {
-                            current_comments.push(comment.compile(config));
+                            current_comments.push(comment.compile(config)?);
                         }
-                        let mut field: syn::Field = def.clone().compile(config);
+                        let mut field: syn::Field = def.clone().compile(config).map_err(|error| error.at_line(line_number))?;
                         if def.field_type.is_void() {
                             field.ident = Some(syn::Ident::from(format!("_v{}", void_number)));
                             void_number += 1;
                         }
-                        field.attrs = current_comments.clone();
+                        field.attrs = current_comments.iter().cloned().chain(field.attrs).collect();
                         fields.push(field);
                         
                         current_comments = Vec::new();
                     },
-                    dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(_), ..} => (), // const definitions is only used in the impls
+                    dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(def), comment: opt_comment} => {
+                        // only the comments directly above a constant are its own, while the comments above a group of
+                        // constants usually describe the field that follows them, so they're not reset
+                        let mut constant: syn::ImplItem = def.compile(config).map_err(|error| error.at_line(line_number))?;
+                        let original_name_docs = constant.attrs;
+                        constant.attrs = ::std::mem::take(&mut comments_above);
+                        if let Some(comment) = opt_comment {
+                            constant.attrs.push(comment.compile(config)?);
+                        }
+                        constant.attrs.extend(original_name_docs);
+                        constants.push(constant);
+                    },
                     dsdl_parser::Line::Directive{..} => unreachable!("All directives was removed at the start"),
                 }
             }
@@ -465,12 +1483,23 @@ impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>)> for dsdl_parser::Message
             syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("UavcanStruct")))
         ];
 
-        match config.derive_default {
-            DeriveDefault::PrimitiveTypes => {
-                if !union && only_primitive_types {
-                    derives.push(syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("Default"))));
-                }
-            },
+        let implement_default = match config.derive_default {
+            DeriveDefault::PrimitiveTypes => !union && only_primitive_types,
+            DeriveDefault::All => true,
+            DeriveDefault::None => false,
+        };
+        let long_arrays = items.iter().any(|item| match *item {
+            syn::ItemKind::Struct(syn::VariantData::Struct(ref fields), _) => fields.iter().any(|field| is_long_array(&field.ty)),
+            _ => false,
+        });
+        // unions and structs with long arrays get an implementation from `default_impl` instead
+        if implement_default && !union && !long_arrays {
+            derives.push(syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("Default"))));
+        }
+
+        if config.serde {
+            derives.push(syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("Serialize"))));
+            derives.push(syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(syn::Ident::from("Deserialize"))));
         }
         
         attributes.push(syn::Attribute{style: syn::AttrStyle::Outer, is_sugared_doc: false, value: syn::MetaItem::List(syn::Ident::from("derive"), derives)});
@@ -480,116 +1509,303 @@ impl Compile<(Vec<syn::ItemKind>, Vec<syn::Attribute>)> for dsdl_parser::Message
             syn::Lit::Str(String::from("uavcan_rs"), syn::StrStyle::Cooked),
         )});
 
-        (items, attributes)
+        Ok((items, attributes, constants))
+    }
+}
+
+/// Checks that the field or constant defined on a line doesn't reuse a name from earlier in the definition, neither in DSDL nor after it's converted to Rust
+fn check_name_clash(line: &dsdl_parser::Line, union: bool, names: &mut ident::Names) -> Result<(), CompileError> {
+    match *line {
+        dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(dsdl_parser::FieldDefinition{name: Some(ref name), ..}), ..} => {
+            let rust_name = if union { ident::variant_name(name.as_ref()) } else { ident::escape(name.as_ref()) };
+            names.insert(name.as_ref(), rust_name)
+        },
+        dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Const(ref def), ..} => names.insert(def.name.as_ref(), ident::escape(def.name.as_ref())),
+        _ => Ok(()),
+    }
+}
+
+
+/// Compiles the type of a field, wrapping it in an array if needed
+/// Returns `with = "::uavcan_rs::serde_field"` for fields of primitive types (or arrays of them), which can't implement serde themselves
+fn serde_with(ty: &syn::Ty) -> Option<syn::MetaItem> {
+    if is_primitive_type(element_type(ty)) {
+        Some(syn::MetaItem::NameValue(syn::Ident::from("with"), syn::Lit::Str(String::from("::uavcan_rs::serde_field"), syn::StrStyle::Cooked)))
+    } else {
+        None
+    }
+}
+
+/// Returns `rename = "name"`, used to keep the DSDL name of fields and variants
+fn serde_rename(name: &str) -> syn::MetaItem {
+    syn::MetaItem::NameValue(syn::Ident::from("rename"), syn::Lit::Str(String::from(name), syn::StrStyle::Cooked))
+}
+
+/// Returns the `#[serde(...)]` attribute containing `items`, if there are any
+fn serde_attribute(items: Vec<syn::MetaItem>) -> Option<syn::Attribute> {
+    if items.is_empty() {
+        return None;
     }
+    Some(syn::Attribute{
+        style: syn::AttrStyle::Outer,
+        value: syn::MetaItem::List(syn::Ident::from("serde"), items.into_iter().map(syn::NestedMetaItem::MetaItem).collect()),
+        is_sugared_doc: false,
+    })
 }
 
+fn compile_field_type(def: &dsdl_parser::FieldDefinition, config: &CompileConfig) -> Result<syn::Ty, CompileError> {
+    let length = match def.array {
+        None => return def.field_type.clone().compile(config),
+        Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => size.saturating_sub(1),
+        Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) | Some(dsdl_parser::ArrayInfo::Static(size)) => size,
+    };
+
+    if length == 0 {
+        let name = def.name.as_ref().map_or("void", |name| name.as_ref());
+        return Err(CompileError::new(CompileErrorKind::InvalidArraySize(String::from(name), length)));
+    }
+
+    let element = def.field_type.clone().compile(config)?;
+    let generic = |name: &str, global: bool, ty: syn::Ty| syn::Ty::Path(
+        None, syn::Path{
+            global,
+            segments: vec![syn::PathSegment{
+                ident: syn::Ident::from(name),
+                parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
+                    lifetimes: Vec::new(),
+                    types: vec![ty],
+                    bindings: Vec::new(),
+                })
+            }],
+        });
+
+    let bit_array = config.bit_arrays && quote!(#element).to_string() == "bool";
+    let array = |element| syn::Ty::Array(Box::new(element), syn::ConstExpr::Lit(syn::Lit::Int(length, syn::IntTy::Unsuffixed)));
+
+    match def.array {
+        Some(dsdl_parser::ArrayInfo::Static(_)) if bit_array => Ok(generic("BitArray", true, array(element))),
+        Some(dsdl_parser::ArrayInfo::Static(_)) => Ok(array(element)),
+        _ if length == 1 && config.optional_fields => Ok(generic("Option", false, element)),
+        _ if bit_array => Ok(generic("DynamicBitArray", true, array(element))),
+        _ => Ok(generic("Dynamic", true, array(element))),
+    }
+}
 
 impl Compile<syn::Field> for dsdl_parser::FieldDefinition {
-    fn compile(self, config: &CompileConfig) -> syn::Field {
-        let ty = match self.array {
-            None => self.field_type.compile(config),
-            Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => syn::Ty::Path(
-                None, syn::Path{
-                    global: true,
-                    segments: vec![syn::PathSegment{
-                        ident: syn::Ident::from("Dynamic"),
-                        parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
-                            lifetimes: Vec::new(),
-                            types: vec![syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size-1, syn::IntTy::Unsuffixed)))],
-                            bindings: Vec::new(),
-                        })
-                    }],
-                }),
-            Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => syn::Ty::Path(
-                None, syn::Path{
-                    global: true,
-                    segments: vec![syn::PathSegment{
-                        ident: syn::Ident::from("Dynamic"),
-                        parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
-                            lifetimes: Vec::new(),
-                            types: vec![syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size, syn::IntTy::Unsuffixed)))],
-                            bindings: Vec::new(),
-                        })
-                    }],
-                }),
-            Some(dsdl_parser::ArrayInfo::Static(size)) => syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size, syn::IntTy::Unsuffixed))),
-        };
+    fn compile(self, config: &CompileConfig) -> Result<syn::Field, CompileError> {
+        let ty = compile_field_type(&self, config)?;
         
-        syn::Field{
-            ident: self.name.map(|x| x.compile(config)),
+        let mut attrs = match self.name {
+            Some(ref name) if ident::is_keyword(name.as_ref()) => ident::original_name_docs(name.as_ref()),
+            _ => Vec::new(),
+        };
+
+        if config.serde {
+            let mut serde_items = Vec::new();
+            if self.field_type.is_void() {
+                serde_items.push(syn::MetaItem::Word(syn::Ident::from("skip")));
+            } else if let Some(with) = serde_with(&ty) {
+                serde_items.push(with);
+            }
+            if let Some(ref name) = self.name {
+                if ident::is_keyword(name.as_ref()) {
+                    serde_items.push(serde_rename(name.as_ref()));
+                }
+            }
+            attrs.extend(serde_attribute(serde_items));
+        }
+
+        Ok(syn::Field{
+            ident: match self.name {
+                Some(name) => Some(name.compile(config)?),
+                None => None,
+            },
             vis: syn::Visibility::Public,
-            attrs: Vec::new(),
+            attrs: attrs,
             ty: ty,
-        }
+        })
     }
 }
 
 impl Compile<syn::Variant> for dsdl_parser::FieldDefinition {
-    fn compile(self, config: &CompileConfig) -> syn::Variant {
-        let ty = match self.array {
-            None => self.field_type.compile(config),
-            Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => syn::Ty::Path(
-                None, syn::Path{
-                    global: true,
-                    segments: vec![syn::PathSegment{
-                        ident: syn::Ident::from("Dynamic"),
-                        parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
-                            lifetimes: Vec::new(),
-                            types: vec![syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size-1, syn::IntTy::Unsuffixed)))],
-                            bindings: Vec::new(),
-                        })
-                    }],
-                }),
-            Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => syn::Ty::Path(
-                None, syn::Path{
-                    global: true,
-                    segments: vec![syn::PathSegment{
-                        ident: syn::Ident::from("Dynamic"),
-                        parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
-                            lifetimes: Vec::new(),
-                            types: vec![syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size, syn::IntTy::Unsuffixed)))],
-                            bindings: Vec::new(),
-                        })
-                    }],
-                }),
-            Some(dsdl_parser::ArrayInfo::Static(size)) => syn::Ty::Array(Box::new(self.field_type.compile(config)), syn::ConstExpr::Lit(syn::Lit::Int(size, syn::IntTy::Unsuffixed))),
+    fn compile(self, config: &CompileConfig) -> Result<syn::Variant, CompileError> {
+        let ty = compile_field_type(&self, config)?;
+
+        // void variants are named by the union they're part of
+        let (ident, mut attrs) = match self.name {
+            Some(ref name) => {
+                name.clone().compile(config)?;
+                let attrs = if ident::is_keyword(&name.as_ref().to_pascal_case()) { ident::original_name_docs(name.as_ref()) } else { Vec::new() };
+                (syn::Ident::from(ident::variant_name(name.as_ref())), attrs)
+            },
+            None => (syn::Ident::from("_"), Vec::new()),
         };
 
-        syn::Variant {
-            ident: syn::Ident::from(String::from(self.name.unwrap()).to_pascal_case()),
-            attrs: Vec::new(),
+        let mut field_attrs = Vec::new();
+        if config.serde {
+            // the variants are tagged with the DSDL name of the field
+            if let Some(ref name) = self.name {
+                attrs.extend(serde_attribute(vec![serde_rename(name.as_ref())]));
+            }
+            field_attrs.extend(serde_attribute(serde_with(&ty).into_iter().collect()));
+        }
+
+        Ok(syn::Variant {
+            ident: ident,
+            attrs: attrs,
             discriminant: None,
             data: syn::VariantData::Tuple(vec![
                 syn::Field{
                     ident: None,
                     vis: syn::Visibility::Inherited,
-                    attrs: Vec::new(),
+                    attrs: field_attrs,
                     ty: ty,
                 }]
             ),
-        }
+        })
+    }
+}
+
+impl Compile<syn::ImplItem> for dsdl_parser::ConstDefinition {
+    fn compile(self, config: &CompileConfig) -> Result<syn::ImplItem, CompileError> {
+        let ty = self.field_type.compile(config)?;
+        let name = String::from(self.name.as_ref());
+        let value = const_value(&ty, self.constant)
+            .map_err(|reason| CompileError::new(CompileErrorKind::InvalidConstant(name.clone(), reason)))?;
+        let attrs = if ident::is_keyword(&name) { ident::original_name_docs(&name) } else { Vec::new() };
+        Ok(syn::ImplItem{
+            ident: self.name.compile(config)?,
+            vis: syn::Visibility::Public,
+            defaultness: syn::Defaultness::Final,
+            attrs: attrs,
+            node: syn::ImplItemKind::Const(
+                ty,
+                syn::parse_expr(&value).map_err(code_generation_error)?,
+            ),
+        })
+    }
+}
+
+/// Returns the name of a compiled primitive type, e.g. `u2` for `::u2`
+fn primitive_type_name(ty: &syn::Ty) -> String {
+    match *ty {
+        syn::Ty::Path(None, ref path) => String::from(path.segments.last().expect("Primitive types have a name").ident.as_ref()),
+        _ => unreachable!("Primitive types are always compiled to paths"),
+    }
+}
+
+/// Returns the rust expression for a constant value of a compiled primitive type, or the reason the value is invalid
+fn const_value(ty: &syn::Ty, constant: dsdl_parser::Const) -> Result<String, String> {
+    let type_name = primitive_type_name(ty);
+
+    if type_name == "bool" {
+        return match constant {
+            dsdl_parser::Const::Bool(value) => Ok(value.to_string()),
+            constant => Ok(format!("{} != 0", integer_value(constant)?)),
+        };
+    }
+
+    let (kind, bits) = type_name.split_at(1);
+    let bits = bits.parse::<u32>().expect("Primitive types have a bit length");
+
+    if kind == "f" {
+        return match bits {
+            16 => Ok(format!("::f16::from_bits({})", half::f16::from_f64(float_value(constant)?).to_bits())),
+            _ => Ok(format!("{:?}", float_value(constant)?)),
+        };
+    }
+
+    let value = integer_value(constant)?;
+    let (min, max) = if kind == "i" {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    };
+    if value < min || value > max {
+        return Err(format!("{} is outside the range of `{}` ({}..={})", value, type_name, min, max));
+    }
+
+    match bits {
+        8 | 16 | 32 | 64 => Ok(value.to_string()),
+        _ => Ok(format!("::{}::new({})", type_name, value)),
+    }
+}
+
+/// Returns the value of a constant used for an integer type
+fn integer_value(constant: dsdl_parser::Const) -> Result<i128, String> {
+    match constant {
+        dsdl_parser::Const::Dec(value) => value.parse().map_err(|_| format!("`{}` is not a valid integer", value)),
+        dsdl_parser::Const::Hex(value) => radix_value(&value, 16),
+        dsdl_parser::Const::Bin(value) => radix_value(&value, 2),
+        dsdl_parser::Const::Bool(value) => Ok(if value {1} else {0}),
+        dsdl_parser::Const::Char(value) => char_value(&value).map(i128::from),
+        dsdl_parser::Const::Float(value) => Err(format!("the float `{}` can't be used for an integer type", value)),
+    }
+}
+
+/// Returns the value of a constant used for a float type
+fn float_value(constant: dsdl_parser::Const) -> Result<f64, String> {
+    match constant {
+        dsdl_parser::Const::Dec(value) | dsdl_parser::Const::Float(value) => value.parse().map_err(|_| format!("`{}` is not a valid float", value)),
+        dsdl_parser::Const::Hex(value) => radix_value(&value, 16).map(|value| value as f64),
+        dsdl_parser::Const::Bin(value) => radix_value(&value, 2).map(|value| value as f64),
+        dsdl_parser::Const::Bool(value) => Ok(if value {1.0} else {0.0}),
+        dsdl_parser::Const::Char(value) => char_value(&value).map(f64::from),
+    }
+}
+
+/// Returns the value of a hex or bin constant, with or without the `0x`/`0b` prefix
+fn radix_value(value: &str, radix: u32) -> Result<i128, String> {
+    let digits = if value.len() > 2 && (value[..2].eq_ignore_ascii_case("0x") || value[..2].eq_ignore_ascii_case("0b")) {
+        &value[2..]
+    } else {
+        value
+    };
+    u64::from_str_radix(digits, radix).map(i128::from).map_err(|_| format!("`{}` is not a valid integer", value))
+}
+
+/// Returns the code point of a (possibly quoted and escaped) char constant
+fn char_value(value: &str) -> Result<u32, String> {
+    let unquoted = if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        &value[1..value.len()-1]
+    } else {
+        value
+    };
+    let mut chars = unquoted.chars();
+    match (chars.next(), chars.next()) {
+        (Some('\\'), Some('n')) => Ok(u32::from('\n')),
+        (Some('\\'), Some('r')) => Ok(u32::from('\r')),
+        (Some('\\'), Some('t')) => Ok(u32::from('\t')),
+        (Some('\\'), Some('0')) => Ok(0),
+        (Some('\\'), Some('x')) => u32::from_str_radix(chars.as_str(), 16).map_err(|_| format!("`{}` is not a valid char", value)),
+        (Some('\\'), Some(c)) => Ok(u32::from(c)),
+        (Some(c), _) => Ok(u32::from(c)),
+        (None, _) => Err(String::from("char constants can't be empty")),
     }
 }
 
 impl Compile<syn::Ident> for dsdl_parser::Ident {
-    fn compile(self, _config: &CompileConfig) -> syn::Ident {
-        syn::Ident::from(self.as_ref())
+    fn compile(self, _config: &CompileConfig) -> Result<syn::Ident, CompileError> {
+        let name = self.as_ref();
+        if !ident::is_valid(name) {
+            return Err(CompileError::new(CompileErrorKind::InvalidIdentifier(String::from(name))));
+        }
+        Ok(syn::Ident::from(ident::escape(name)))
     }
 }
 
 impl Compile<syn::Attribute> for dsdl_parser::Comment {
-    fn compile(self, _config: &CompileConfig) -> syn::Attribute {
-        syn::Attribute{
+    fn compile(self, _config: &CompileConfig) -> Result<syn::Attribute, CompileError> {
+        Ok(syn::Attribute{
             style: syn::AttrStyle::Outer,
             value: syn::MetaItem::NameValue(syn::Ident::from("doc"), syn::Lit::Str(String::from(self.as_ref()), syn::StrStyle::Cooked)),
             is_sugared_doc: true,
-        }
+        })
     }
 }
 
 impl Compile<syn::Ty> for dsdl_parser::Ty {
-    fn compile(self, config: &CompileConfig) -> syn::Ty {
+    fn compile(self, config: &CompileConfig) -> Result<syn::Ty, CompileError> {
         match self {
             dsdl_parser::Ty::Primitive(x) => x.compile(config),
             dsdl_parser::Ty::Composite(x) => x.compile(config),
@@ -598,7 +1814,7 @@ impl Compile<syn::Ty> for dsdl_parser::Ty {
 }
 
 impl Compile<syn::Ty> for dsdl_parser::CompositeType {
-    fn compile(self, config: &CompileConfig) -> syn::Ty {
+    fn compile(self, config: &CompileConfig) -> Result<syn::Ty, CompileError> {
         let mut path = syn::Path {
             global: false,
             segments: Vec::new(),
@@ -606,20 +1822,30 @@ impl Compile<syn::Ty> for dsdl_parser::CompositeType {
         
         if let Some(namespace) = self.namespace {
             path.global = true;
-            for segment in namespace.as_ref().split(".") {
-                path.segments.push(syn::PathSegment{ident: syn::Ident::from(segment), parameters: syn::PathParameters::none()});
+            match config.extern_path(namespace.as_ref()) {
+                Some(extern_path) => {
+                    path.global = extern_path.starts_with("::");
+                    for segment in extern_path.split("::").filter(|segment| !segment.is_empty()) {
+                        path.segments.push(syn::PathSegment{ident: syn::Ident::from(segment), parameters: syn::PathParameters::none()});
+                    }
+                },
+                None => {
+                    for segment in namespace.as_ref().split(".") {
+                        path.segments.push(syn::PathSegment{ident: syn::Ident::from(ident::escape(segment)), parameters: syn::PathParameters::none()});
+                    }
+                },
             }
         }
         
-        path.segments.push(syn::PathSegment{ident: self.name.compile(config), parameters: syn::PathParameters::none()});
+        path.segments.push(syn::PathSegment{ident: self.name.compile(config)?, parameters: syn::PathParameters::none()});
         
-        syn::Ty::Path(None, path)
+        Ok(syn::Ty::Path(None, path))
     }
 }   
 
 impl Compile<syn::Ty> for dsdl_parser::PrimitiveType {
-    fn compile(self, _config: &CompileConfig) -> syn::Ty {
-        match self {
+    fn compile(self, _config: &CompileConfig) -> Result<syn::Ty, CompileError> {
+        Ok(match self {
             dsdl_parser::PrimitiveType::Bool => syn::Ty::Path(None, syn::Path{global: false, segments: vec!(syn::PathSegment{ident: syn::Ident::from("bool"), parameters: syn::PathParameters::none()})}),
             
             dsdl_parser::PrimitiveType::Float16 => syn::Ty::Path(None, syn::Path{global: true, segments: vec!(syn::PathSegment{ident: syn::Ident::from("f16"), parameters: syn::PathParameters::none()})}),
@@ -818,7 +2044,7 @@ impl Compile<syn::Ty> for dsdl_parser::PrimitiveType {
             dsdl_parser::PrimitiveType::Void62 => syn::Ty::Path(None, syn::Path{global: true, segments: vec!(syn::PathSegment{ident: syn::Ident::from("void62"), parameters: syn::PathParameters::none()})}),
             dsdl_parser::PrimitiveType::Void63 => syn::Ty::Path(None, syn::Path{global: true, segments: vec!(syn::PathSegment{ident: syn::Ident::from("void63"), parameters: syn::PathParameters::none()})}),
             dsdl_parser::PrimitiveType::Void64 => syn::Ty::Path(None, syn::Path{global: true, segments: vec!(syn::PathSegment{ident: syn::Ident::from("void64"), parameters: syn::PathParameters::none()})}),
-        }
+        })
     }
 }
 
@@ -832,7 +2058,7 @@ mod tests {
     #[test]
     fn compile_dsdl() {
         let dsdl = DSDL::read("tests/dsdl/").unwrap();
-        dsdl.compile(&CompileConfig::default());
+        dsdl.compile(&CompileConfig::default()).unwrap();
     }    
 
     #[test]
@@ -845,7 +2071,7 @@ mod tests {
         };
         assert!(compile_config.data_type_signature);
         
-        let file = dsdl.compile(&compile_config);
+        let file = dsdl.compile(&compile_config).unwrap();
 
         assert_eq!(quote!(#(#file)*), quote!{
             #[allow(unused_imports)]
@@ -862,6 +2088,7 @@ mod tests {
             #[derive(Debug, Clone, UavcanStruct, Default)]
             #[UavcanCrateName = "uavcan_rs"]
             #[DSDLSignature = "0xbe7710808d2ff575"] 
+            #[FullName = "uavcan.protocol.NodeStatus"]
             #[DataTypeSignature = "0xbe7710808d2ff575"] 
             pub struct NodeStatus {
                 #[doc = ""]
@@ -892,6 +2119,49 @@ mod tests {
                 #[doc = ""]
                 pub vendor_specific_status_code: u16
             }
+
+            impl NodeStatus {
+                #[doc = ""]
+                #[doc = " Publication period may vary within these limits."]
+                #[doc = " It is NOT recommended to change it at run time."]
+                #[doc = ""]
+                pub const MAX_BROADCASTING_PERIOD_MS: u16 = 1000;
+                pub const MIN_BROADCASTING_PERIOD_MS: u16 = 2;
+                #[doc = ""]
+                #[doc = " If a node fails to publish this message in this amount of time, it should be considered offline."]
+                #[doc = ""]
+                pub const OFFLINE_TIMEOUT_MS: u16 = 3000;
+                #[doc = ""]
+                #[doc = " Abstract node health."]
+                #[doc = ""]
+                #[doc = " The node is functioning properly."]
+                pub const HEALTH_OK: ::u2 = ::u2::new(0);
+                #[doc = " A critical parameter went out of range or the node encountered a minor failure."]
+                pub const HEALTH_WARNING: ::u2 = ::u2::new(1);
+                #[doc = " The node encountered a major failure."]
+                pub const HEALTH_ERROR: ::u2 = ::u2::new(2);
+                #[doc = " The node suffered a fatal malfunction."]
+                pub const HEALTH_CRITICAL: ::u2 = ::u2::new(3);
+                #[doc = ""]
+                #[doc = " Current mode."]
+                #[doc = ""]
+                #[doc = " Mode OFFLINE can be actually reported by the node to explicitly inform other network"]
+                #[doc = " participants that the sending node is about to shutdown. In this case other nodes will not"]
+                #[doc = " have to wait OFFLINE_TIMEOUT_MS before they detect that the node is no longer available."]
+                #[doc = ""]
+                #[doc = " Reserved values can be used in future revisions of the specification."]
+                #[doc = ""]
+                #[doc = " Normal operating mode."]
+                pub const MODE_OPERATIONAL: ::u3 = ::u3::new(0);
+                #[doc = " Initialization is in progress; this mode is entered immediately after startup."]
+                pub const MODE_INITIALIZATION: ::u3 = ::u3::new(1);
+                #[doc = " E.g. calibration, the bootloader is running, etc."]
+                pub const MODE_MAINTENANCE: ::u3 = ::u3::new(2);
+                #[doc = " New software/firmware is being loaded."]
+                pub const MODE_SOFTWARE_UPDATE: ::u3 = ::u3::new(3);
+                #[doc = " The node is no longer available."]
+                pub const MODE_OFFLINE: ::u3 = ::u3::new(7);
+            }
             
             impl ::uavcan_rs::Message for NodeStatus {
                 const TYPE_ID: Option<u16> = Some(341);
@@ -903,7 +2173,7 @@ mod tests {
     #[test]
     fn compile_service() {
         let dsdl = DSDL::read("tests/dsdl/").unwrap();
-        let file = dsdl.get_file(&String::from("uavcan.protocol.GetNodeInfo")).unwrap().clone().compile(&CompileConfig::default());
+        let file = dsdl.get_file(&String::from("uavcan.protocol.GetNodeInfo")).unwrap().clone().compile(&CompileConfig::default()).unwrap();
         
         assert_eq!(quote!(#(#file)*), quote!{
             pub mod uavcan {
@@ -915,11 +2185,13 @@ mod tests {
                     #[derive(Debug, Clone, UavcanStruct, Default)]
                     #[UavcanCrateName = "uavcan_rs"]
                     #[DSDLSignature = "0xa80dc8995053e685"]
+                    #[FullName = "uavcan.protocol.GetNodeInfo"]
                     pub struct GetNodeInfoRequest {}
 
                     #[derive(Debug, Clone, UavcanStruct)]
                     #[UavcanCrateName = "uavcan_rs"]
                     #[DSDLSignature = "0xa80dc8995053e685"]
+                    #[FullName = "uavcan.protocol.GetNodeInfo"]
                     pub struct GetNodeInfoResponse {
                         #[doc = ""]
                         #[doc = " Current node status"]
@@ -960,7 +2232,7 @@ mod tests {
     #[test]
     fn compile_enum() {
         let dsdl = DSDL::read("tests/dsdl/").unwrap();
-        let file = dsdl.get_file(&String::from("uavcan.protocol.param.Value")).unwrap().clone().compile(&CompileConfig::default());
+        let file = dsdl.get_file(&String::from("uavcan.protocol.param.Value")).unwrap().clone().compile(&CompileConfig::default()).unwrap();
         
         assert_eq!(quote!(#(#file)*), quote!{
             pub mod uavcan {
@@ -975,6 +2247,7 @@ mod tests {
                         #[derive(Debug, Clone, UavcanStruct)]
                         #[UavcanCrateName = "uavcan_rs"]
                         #[DSDLSignature = "0xc3d96f448f2b00a1"]
+                        #[FullName = "uavcan.protocol.param.Value"]
                         pub enum Value {
                             #[doc = " Empty field, used to represent an undefined value."]
                             Empty(Empty),
@@ -996,7 +2269,7 @@ mod tests {
     #[test]
     fn compile_struct() {
         let dsdl = DSDL::read("tests/dsdl/").unwrap();
-        let file = dsdl.get_file(&String::from("uavcan.protocol.NodeStatus")).unwrap().clone().compile(&CompileConfig::default());
+        let file = dsdl.get_file(&String::from("uavcan.protocol.NodeStatus")).unwrap().clone().compile(&CompileConfig::default()).unwrap();
         
         assert_eq!(quote!(#(#file)*), quote!{
             pub mod uavcan {
@@ -1009,6 +2282,7 @@ mod tests {
                     #[derive(Debug, Clone, UavcanStruct, Default)]
                     #[UavcanCrateName = "uavcan_rs"]
                     #[DSDLSignature = "0xf0868d0c1a7c6f1"] 
+                    #[FullName = "uavcan.protocol.NodeStatus"]
                     pub struct NodeStatus {
                         #[doc = ""]
                         #[doc = " Uptime counter should never overflow."]
@@ -1038,6 +2312,49 @@ mod tests {
                         #[doc = ""]
                         pub vendor_specific_status_code: u16
                     }
+
+                    impl NodeStatus {
+                        #[doc = ""]
+                        #[doc = " Publication period may vary within these limits."]
+                        #[doc = " It is NOT recommended to change it at run time."]
+                        #[doc = ""]
+                        pub const MAX_BROADCASTING_PERIOD_MS: u16 = 1000;
+                        pub const MIN_BROADCASTING_PERIOD_MS: u16 = 2;
+                        #[doc = ""]
+                        #[doc = " If a node fails to publish this message in this amount of time, it should be considered offline."]
+                        #[doc = ""]
+                        pub const OFFLINE_TIMEOUT_MS: u16 = 3000;
+                        #[doc = ""]
+                        #[doc = " Abstract node health."]
+                        #[doc = ""]
+                        #[doc = " The node is functioning properly."]
+                        pub const HEALTH_OK: ::u2 = ::u2::new(0);
+                        #[doc = " A critical parameter went out of range or the node encountered a minor failure."]
+                        pub const HEALTH_WARNING: ::u2 = ::u2::new(1);
+                        #[doc = " The node encountered a major failure."]
+                        pub const HEALTH_ERROR: ::u2 = ::u2::new(2);
+                        #[doc = " The node suffered a fatal malfunction."]
+                        pub const HEALTH_CRITICAL: ::u2 = ::u2::new(3);
+                        #[doc = ""]
+                        #[doc = " Current mode."]
+                        #[doc = ""]
+                        #[doc = " Mode OFFLINE can be actually reported by the node to explicitly inform other network"]
+                        #[doc = " participants that the sending node is about to shutdown. In this case other nodes will not"]
+                        #[doc = " have to wait OFFLINE_TIMEOUT_MS before they detect that the node is no longer available."]
+                        #[doc = ""]
+                        #[doc = " Reserved values can be used in future revisions of the specification."]
+                        #[doc = ""]
+                        #[doc = " Normal operating mode."]
+                        pub const MODE_OPERATIONAL: ::u3 = ::u3::new(0);
+                        #[doc = " Initialization is in progress; this mode is entered immediately after startup."]
+                        pub const MODE_INITIALIZATION: ::u3 = ::u3::new(1);
+                        #[doc = " E.g. calibration, the bootloader is running, etc."]
+                        pub const MODE_MAINTENANCE: ::u3 = ::u3::new(2);
+                        #[doc = " New software/firmware is being loaded."]
+                        pub const MODE_SOFTWARE_UPDATE: ::u3 = ::u3::new(3);
+                        #[doc = " The node is no longer available."]
+                        pub const MODE_OFFLINE: ::u3 = ::u3::new(7);
+                    }
                     
                     impl ::uavcan_rs::Message for NodeStatus {
                         const TYPE_ID: Option<u16> = Some(341);
@@ -1077,7 +2394,7 @@ mod tests {
                      comment: Some(Comment::from_str("#test comment3").unwrap())
                  },
             ]
-        ).compile(&CompileConfig::default());
+        ).compile(&CompileConfig::default()).unwrap();
 
         let struct_body = if let syn::ItemKind::Struct(variant_data, _) = body.0[0].clone() {
             variant_data
@@ -1104,6 +2421,58 @@ mod tests {
         }), quote!{#struct_body});
     }
 
+    #[test]
+    fn compile_keywords() {
+        let field = |name: &str| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(PrimitiveType::Uint8),
+                array: None,
+                name: Some(dsdl_parser::Ident::from(name)),
+            }),
+            comment: None,
+        };
+
+        let struct_body = dsdl_parser::MessageDefinition(vec![
+            Line::Comment(Comment::from_str("#the kind of node").unwrap()),
+            field("type"),
+            field("mode"),
+        ]).compile(&CompileConfig::default()).unwrap();
+
+        let fields = if let syn::ItemKind::Struct(ref variant_data, _) = struct_body.0[0] {
+            variant_data
+        } else {
+            unreachable!("This is a struct")
+        };
+        assert_eq!(quote!(#fields), quote!(
+            {
+                #[doc = "the kind of node"]
+                #[doc = ""]
+                #[doc = " DSDL name: `type`"]
+                pub type_: u8,
+                pub mode: u8
+            }
+        ));
+
+        let enum_body = dsdl_parser::MessageDefinition(vec![
+            Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+            field("self"),
+            field("other"),
+        ]).compile(&CompileConfig::default()).unwrap();
+
+        let variants = if let syn::ItemKind::Enum(ref variants, _) = enum_body.0[0] {
+            variants
+        } else {
+            unreachable!("This is an enum")
+        };
+        assert_eq!(quote!(#(#variants),*), quote!(
+            #[doc = ""]
+            #[doc = " DSDL name: `self`"]
+            Self_(u8),
+            Other(u8)
+        ));
+    }
+
     #[test]
     fn compile_enum_body() {
         let body = dsdl_parser::MessageDefinition(
@@ -1134,7 +2503,7 @@ mod tests {
                      comment: Some(Comment::from_str("#test comment3").unwrap())
                  },
             ]
-        ).compile(&CompileConfig::default());
+        ).compile(&CompileConfig::default()).unwrap();
 
         let enum_body = if let syn::ItemKind::Enum(variants, _) = body.0[0].clone() {
             variants
@@ -1166,6 +2535,748 @@ mod tests {
         }, quote!{#def1});
     }
     
+    #[test]
+    fn compile_constants() {
+        let constant = |field_type, name, constant| Line::Definition {
+            definition: AttributeDefinition::Const(dsdl_parser::ConstDefinition {
+                cast_mode: None,
+                field_type,
+                name: dsdl_parser::Ident::from(name),
+                constant,
+            }),
+            comment: None,
+        };
+
+        let body = dsdl_parser::MessageDefinition(
+            vec![Line::Comment(Comment::from_str("#about constant").unwrap()),
+                 constant(PrimitiveType::Uint16, "DEC", Const::Dec(String::from("1000"))),
+                 Line::Empty,
+                 constant(PrimitiveType::Int8, "NEGATIVE", Const::Dec(String::from("-3"))),
+                 constant(PrimitiveType::Uint8, "HEX", Const::Hex(String::from("0x1f"))),
+                 constant(PrimitiveType::Uint4, "BIN", Const::Bin(String::from("0b1010"))),
+                 constant(PrimitiveType::Int3, "SMALL", Const::Dec(String::from("-4"))),
+                 constant(PrimitiveType::Uint8, "CHAR", Const::Char(String::from("'a'"))),
+                 constant(PrimitiveType::Bool, "BOOL", Const::Bool(true)),
+                 constant(PrimitiveType::Float32, "FLOAT", Const::Float(String::from("1.5"))),
+                 constant(PrimitiveType::Float64, "FLOAT_FROM_INT", Const::Dec(String::from("2"))),
+                 constant(PrimitiveType::Float16, "HALF", Const::Float(String::from("1.0"))),
+            ]
+        ).compile(&CompileConfig::default()).unwrap();
+
+        let constants = body.2;
+        
+        assert_eq!(quote!{
+            #[doc = "about constant"]
+            pub const DEC: u16 = 1000;
+            pub const NEGATIVE: i8 = -3;
+            pub const HEX: u8 = 31;
+            pub const BIN: ::u4 = ::u4::new(10);
+            pub const SMALL: ::i3 = ::i3::new(-4);
+            pub const CHAR: u8 = 97;
+            pub const BOOL: bool = true;
+            pub const FLOAT: f32 = 1.5;
+            pub const FLOAT_FROM_INT: f64 = 2.0;
+            pub const HALF: ::f16 = ::f16::from_bits(15360);
+        }, quote!{#(#constants)*});
+    }
+    
+    #[test]
+    fn compile_constant_comments() {
+        let constant = |name, value: &str, comment: &str| Line::Definition {
+            definition: AttributeDefinition::Const(dsdl_parser::ConstDefinition {
+                cast_mode: None,
+                field_type: PrimitiveType::Uint2,
+                name: dsdl_parser::Ident::from(name),
+                constant: Const::Dec(String::from(value)),
+            }),
+            comment: Some(Comment::from_str(comment).unwrap()),
+        };
+
+        let body = dsdl_parser::MessageDefinition(
+            vec![Line::Comment(Comment::from_str("# Abstract node health.").unwrap()),
+                 constant("HEALTH_OK", "0", "# The node is functioning properly."),
+                 constant("HEALTH_WARNING", "1", "# A critical parameter went out of range."),
+                 Line::Definition {
+                     definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                         cast_mode: None,
+                         field_type: Ty::Primitive(PrimitiveType::Uint2),
+                         array: None,
+                         name: Some(dsdl_parser::Ident::from("health")),
+                     }),
+                     comment: None,
+                 },
+            ]
+        ).compile(&CompileConfig::default()).unwrap();
+
+        let fields = if let syn::ItemKind::Struct(syn::VariantData::Struct(ref fields), _) = body.0[0] {
+            fields
+        } else {
+            unreachable!("This is a struct")
+        };
+        let constants = body.2;
+
+        assert_eq!(quote!{
+            #[doc = " Abstract node health."]
+            pub health: ::u2
+        }, quote!{#(#fields)*});
+
+        assert_eq!(quote!{
+            #[doc = " Abstract node health."]
+            #[doc = " The node is functioning properly."]
+            pub const HEALTH_OK: ::u2 = ::u2::new(0);
+            #[doc = " A critical parameter went out of range."]
+            pub const HEALTH_WARNING: ::u2 = ::u2::new(1);
+        }, quote!{#(#constants)*});
+    }
+    
+    #[test]
+    fn compile_constant_enums() {
+        let constant = |name, value: &str, comment: &str| Line::Definition {
+            definition: AttributeDefinition::Const(dsdl_parser::ConstDefinition {
+                cast_mode: None,
+                field_type: PrimitiveType::Uint2,
+                name: dsdl_parser::Ident::from(name),
+                constant: Const::Dec(String::from(value)),
+            }),
+            comment: Some(Comment::from_str(comment).unwrap()),
+        };
+
+        let body = dsdl_parser::MessageDefinition(
+            vec![Line::Comment(Comment::from_str("# Abstract node health.").unwrap()),
+                 constant("HEALTH_OK", "0", "# The node is functioning properly."),
+                 constant("HEALTH_WARNING", "1", "# A critical parameter went out of range."),
+                 Line::Definition {
+                     definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                         cast_mode: None,
+                         field_type: Ty::Primitive(PrimitiveType::Uint2),
+                         array: None,
+                         name: Some(dsdl_parser::Ident::from("health")),
+                     }),
+                     comment: None,
+                 },
+                 Line::Definition {
+                     definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                         cast_mode: None,
+                         field_type: Ty::Primitive(PrimitiveType::Uint3),
+                         array: None,
+                         name: Some(dsdl_parser::Ident::from("mode")),
+                     }),
+                     comment: None,
+                 },
+            ]
+        );
+
+        let items = constant_enums("NodeStatus", &body, &CompileConfig{constant_enums: true, .. Default::default()}, Layout::Namespace).unwrap();
+
+        assert_eq!(quote!{
+            pub mod node_status {
+                #[doc = " Abstract node health."]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                pub enum Health {
+                    #[doc = " The node is functioning properly."]
+                    Ok,
+                    #[doc = " A critical parameter went out of range."]
+                    Warning,
+                }
+                
+                impl ::uavcan_rs::TryFrom<::u2> for Health {
+                    type Error = ::u2;
+                    fn try_from(value: ::u2) -> Result<Self, Self::Error> {
+                        if value == super::NodeStatus::HEALTH_OK {
+                            Ok(Health::Ok)
+                        } else if value == super::NodeStatus::HEALTH_WARNING {
+                            Ok(Health::Warning)
+                        } else {
+                            Err(value)
+                        }
+                    }
+                }
+                
+                impl From<Health> for ::u2 {
+                    fn from(value: Health) -> Self {
+                        match value {
+                            Health::Ok => super::NodeStatus::HEALTH_OK,
+                            Health::Warning => super::NodeStatus::HEALTH_WARNING,
+                        }
+                    }
+                }
+            }
+            
+            impl NodeStatus {
+                pub fn health(&self) -> Result<node_status::Health, ::u2> {
+                    ::uavcan_rs::TryFrom::try_from(self.health)
+                }
+                pub fn set_health(&mut self, value: node_status::Health) {
+                    self.health = value.into();
+                }
+            }
+        }, quote!{#(#items)*});
+
+        let items = constant_enums("NodeStatus", &body, &CompileConfig{constant_enums: true, .. Default::default()}, Layout::TypeModule).unwrap();
+
+        assert_eq!(quote!{
+            #[doc = " Abstract node health."]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum Health {
+                #[doc = " The node is functioning properly."]
+                Ok,
+                #[doc = " A critical parameter went out of range."]
+                Warning,
+            }
+
+            impl ::uavcan_rs::TryFrom<::u2> for Health {
+                type Error = ::u2;
+                fn try_from(value: ::u2) -> Result<Self, Self::Error> {
+                    if value == NodeStatus::HEALTH_OK {
+                        Ok(Health::Ok)
+                    } else if value == NodeStatus::HEALTH_WARNING {
+                        Ok(Health::Warning)
+                    } else {
+                        Err(value)
+                    }
+                }
+            }
+
+            impl From<Health> for ::u2 {
+                fn from(value: Health) -> Self {
+                    match value {
+                        Health::Ok => NodeStatus::HEALTH_OK,
+                        Health::Warning => NodeStatus::HEALTH_WARNING,
+                    }
+                }
+            }
+
+            impl NodeStatus {
+                pub fn health(&self) -> Result<Health, ::u2> {
+                    ::uavcan_rs::TryFrom::try_from(self.health)
+                }
+                pub fn set_health(&mut self, value: Health) {
+                    self.health = value.into();
+                }
+            }
+        }, quote!{#(#items)*});
+    }
+
+    #[test]
+    fn compile_constructors() {
+        let field = |field_type, array, name: Option<&str>| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type,
+                array,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        };
+        let node_status = || Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")});
+
+        let (item_kinds, _, _) = dsdl_parser::MessageDefinition(
+            vec![field(Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(Ty::Primitive(PrimitiveType::Void3), None, None),
+                 field(Ty::Primitive(PrimitiveType::Float16), Some(dsdl_parser::ArrayInfo::Static(2)), Some("gain")),
+                 field(node_status(), None, Some("status")),
+                 field(node_status(), Some(dsdl_parser::ArrayInfo::DynamicLeq(2)), Some("peers")),
+                 field(Ty::Primitive(PrimitiveType::Uint8), Some(dsdl_parser::ArrayInfo::DynamicLeq(16)), Some("name")),
+            ]
+        ).compile(&CompileConfig::default()).unwrap();
+
+        let items = constructors("Status", &item_kinds).unwrap();
+        let allow = syn::Ident::from("clippy::too_many_arguments");
+
+        assert_eq!(quote!{
+            impl Status {
+                #[allow(#allow)]
+                pub fn new(health: u8, gain: &[f32], status: ::uavcan::protocol::NodeStatus, peers: &[::uavcan::protocol::NodeStatus], name: impl AsRef<[u8]>) -> Result<Self, ::uavcan_rs::types::FieldError> {
+                    Ok(Status {
+                        health: ::uavcan_rs::types::FromNative::from_native(health).map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, "health"))?,
+                        _v0: Default::default(),
+                        gain: ::uavcan_rs::types::FromNative::from_native(gain).map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, "gain"))?,
+                        status: ::uavcan_rs::types::FromNative::from_native(status).map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, "status"))?,
+                        peers: ::uavcan_rs::types::FromNative::from_native(peers).map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, "peers"))?,
+                        name: ::uavcan_rs::types::FromNative::from_native(name.as_ref()).map_err(|error| ::uavcan_rs::types::NativeError::in_field(error, "name"))?
+                    })
+                }
+                pub fn builder() -> StatusBuilder {
+                    StatusBuilder::default()
+                }
+            }
+
+            #[derive(Debug, Clone, Default)]
+            pub struct StatusBuilder {
+                health: Option<::u2>,
+                gain: Option<[::f16; 2]>,
+                status: Option<::uavcan::protocol::NodeStatus>,
+                peers: Option<::Dynamic<[::uavcan::protocol::NodeStatus; 2]> >,
+                name: Option<::Dynamic<[u8; 16]> >,
+                _error: Option<::uavcan_rs::types::FieldError>
+            }
+
+            impl StatusBuilder {
+                pub fn health(mut self, value: u8) -> Self {
+                    match ::uavcan_rs::types::FromNative::from_native(value) {
+                        Ok(value) => self.health = Some(value),
+                        Err(error) => if self._error.is_none() { self._error = Some(::uavcan_rs::types::NativeError::in_field(error, "health")) },
+                    }
+                    self
+                }
+                pub fn gain(mut self, value: &[f32]) -> Self {
+                    match ::uavcan_rs::types::FromNative::from_native(value) {
+                        Ok(value) => self.gain = Some(value),
+                        Err(error) => if self._error.is_none() { self._error = Some(::uavcan_rs::types::NativeError::in_field(error, "gain")) },
+                    }
+                    self
+                }
+                pub fn status(mut self, value: ::uavcan::protocol::NodeStatus) -> Self {
+                    match ::uavcan_rs::types::FromNative::from_native(value) {
+                        Ok(value) => self.status = Some(value),
+                        Err(error) => if self._error.is_none() { self._error = Some(::uavcan_rs::types::NativeError::in_field(error, "status")) },
+                    }
+                    self
+                }
+                pub fn peers(mut self, value: &[::uavcan::protocol::NodeStatus]) -> Self {
+                    match ::uavcan_rs::types::FromNative::from_native(value) {
+                        Ok(value) => self.peers = Some(value),
+                        Err(error) => if self._error.is_none() { self._error = Some(::uavcan_rs::types::NativeError::in_field(error, "peers")) },
+                    }
+                    self
+                }
+                pub fn name(mut self, value: impl AsRef<[u8]>) -> Self {
+                    match ::uavcan_rs::types::FromNative::from_native(value.as_ref()) {
+                        Ok(value) => self.name = Some(value),
+                        Err(error) => if self._error.is_none() { self._error = Some(::uavcan_rs::types::NativeError::in_field(error, "name")) },
+                    }
+                    self
+                }
+                pub fn build(self) -> Result<Status, ::uavcan_rs::types::FieldError> {
+                    if let Some(error) = self._error {
+                        return Err(error);
+                    }
+                    Ok(Status {
+                        health: self.health.ok_or(::uavcan_rs::types::FieldError::Missing("health"))?,
+                        _v0: Default::default(),
+                        gain: self.gain.ok_or(::uavcan_rs::types::FieldError::Missing("gain"))?,
+                        status: self.status.ok_or(::uavcan_rs::types::FieldError::Missing("status"))?,
+                        peers: self.peers.ok_or(::uavcan_rs::types::FieldError::Missing("peers"))?,
+                        name: self.name.ok_or(::uavcan_rs::types::FieldError::Missing("name"))?
+                    })
+                }
+            }
+        }, quote!{#(#items)*});
+
+        // unions and structs without fields don't get constructors
+        let (union_kinds, _, _) = dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(Ty::Primitive(PrimitiveType::Uint3), None, Some("mode")),
+            ]
+        ).compile(&CompileConfig::default()).unwrap();
+        assert!(constructors("Mode", &union_kinds).unwrap().is_empty());
+        let (empty_kinds, _, _) = dsdl_parser::MessageDefinition(vec![field(Ty::Primitive(PrimitiveType::Void8), None, None)]).compile(&CompileConfig::default()).unwrap();
+        assert!(constructors("Empty", &empty_kinds).unwrap().is_empty());
+    }
+
+    #[test]
+    fn compile_serde() {
+        let field = |field_type, array, name: Option<&str>| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type,
+                array,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        };
+        let node_status = || Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")});
+        let config = CompileConfig{serde: true, ..CompileConfig::default()};
+
+        let (item_kinds, attributes, _) = dsdl_parser::MessageDefinition(
+            vec![field(Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(Ty::Primitive(PrimitiveType::Void3), None, None),
+                 field(Ty::Primitive(PrimitiveType::Float16), Some(dsdl_parser::ArrayInfo::Static(2)), Some("gain")),
+                 field(node_status(), Some(dsdl_parser::ArrayInfo::DynamicLeq(2)), Some("peers")),
+                 field(Ty::Primitive(PrimitiveType::Uint8), Some(dsdl_parser::ArrayInfo::DynamicLeq(16)), Some("type")),
+            ]
+        ).compile(&config).unwrap();
+        let item = syn::Item{ident: syn::Ident::from("Status"), vis: syn::Visibility::Public, attrs: attributes, node: item_kinds[0].clone()};
+        assert_eq!(quote!{
+            #[derive(Debug, Clone, UavcanStruct, Serialize, Deserialize)]
+            #[UavcanCrateName = "uavcan_rs"]
+            pub struct Status {
+                #[serde(with = "::uavcan_rs::serde_field")]
+                pub health: ::u2,
+                #[serde(skip)]
+                pub _v0: ::void3,
+                #[serde(with = "::uavcan_rs::serde_field")]
+                pub gain: [::f16; 2],
+                pub peers: ::Dynamic<[::uavcan::protocol::NodeStatus; 2] >,
+                #[doc = ""]
+                #[doc = " DSDL name: `type`"]
+                #[serde(with = "::uavcan_rs::serde_field", rename = "type")]
+                pub type_: ::Dynamic<[u8; 16] >
+            }
+        }, quote!{#item});
+
+        let (item_kinds, attributes, _) = dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(node_status(), None, Some("node_status")),
+                 field(Ty::Primitive(PrimitiveType::Void3), None, None),
+            ]
+        ).compile(&config).unwrap();
+        let item = syn::Item{ident: syn::Ident::from("Mode"), vis: syn::Visibility::Public, attrs: attributes, node: item_kinds[0].clone()};
+        // unions are externally tagged with the DSDL names of the fields
+        assert_eq!(quote!{
+            #[derive(Debug, Clone, UavcanStruct, Serialize, Deserialize)]
+            #[UavcanCrateName = "uavcan_rs"]
+            pub enum Mode {
+                #[serde(rename = "health")]
+                Health(#[serde(with = "::uavcan_rs::serde_field")] ::u2),
+                #[serde(rename = "node_status")]
+                NodeStatus(::uavcan::protocol::NodeStatus),
+                _V0(#[serde(with = "::uavcan_rs::serde_field")] ::void3),
+            }
+        }, quote!{#item});
+    }
+
+    #[test]
+    fn compile_reflect() {
+        let field = |cast_mode, field_type, array, name: Option<&str>| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode,
+                field_type,
+                array,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        };
+        let constant = |field_type, name: &str, constant| Line::Definition {
+            definition: AttributeDefinition::Const(dsdl_parser::ConstDefinition {
+                cast_mode: None,
+                field_type,
+                name: dsdl_parser::Ident::from(name),
+                constant,
+            }),
+            comment: None,
+        };
+        let config = CompileConfig{reflect: true, ..CompileConfig::default()};
+
+        let definition = dsdl_parser::MessageDefinition(
+            vec![constant(PrimitiveType::Uint2, "HEALTH_OK", dsdl_parser::Const::Dec(String::from("0"))),
+                 constant(PrimitiveType::Int8, "OFFSET", dsdl_parser::Const::Dec(String::from("-3"))),
+                 constant(PrimitiveType::Float16, "GAIN", dsdl_parser::Const::Float(String::from("0.5"))),
+                 field(None, Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(None, Ty::Primitive(PrimitiveType::Void3), None, None),
+                 field(Some(dsdl_parser::CastMode::Truncated), Ty::Primitive(PrimitiveType::Float16), Some(dsdl_parser::ArrayInfo::Static(2)), Some("gain")),
+                 field(None, Ty::Composite(dsdl_parser::CompositeType{namespace: None, name: dsdl_parser::Ident::from("Peer")}), Some(dsdl_parser::ArrayInfo::DynamicLess(4)), Some("peers")),
+                 field(None, Ty::Primitive(PrimitiveType::Bool), None, Some("type")),
+            ]
+        );
+        let (item_kinds, _, _) = definition.clone().compile(&config).unwrap();
+        let items = reflect_impl("Status", "com.example", &definition, &item_kinds, &config).unwrap();
+        assert_eq!(quote!{
+            impl ::uavcan_rs::reflect::Reflect for Status {
+                const FIELDS: &'static [::uavcan_rs::reflect::FieldInfo] = &[
+                    ::uavcan_rs::reflect::FieldInfo{name: "health", ty: "uint2", array: ::uavcan_rs::reflect::ArrayKind::Scalar, cast_mode: Some(::uavcan_rs::reflect::CastMode::Saturated)},
+                    ::uavcan_rs::reflect::FieldInfo{name: "", ty: "void3", array: ::uavcan_rs::reflect::ArrayKind::Scalar, cast_mode: None},
+                    ::uavcan_rs::reflect::FieldInfo{name: "gain", ty: "float16", array: ::uavcan_rs::reflect::ArrayKind::Static(2), cast_mode: Some(::uavcan_rs::reflect::CastMode::Truncated)},
+                    ::uavcan_rs::reflect::FieldInfo{name: "peers", ty: "com.example.Peer", array: ::uavcan_rs::reflect::ArrayKind::Dynamic(3), cast_mode: None},
+                    ::uavcan_rs::reflect::FieldInfo{name: "type", ty: "bool", array: ::uavcan_rs::reflect::ArrayKind::Scalar, cast_mode: Some(::uavcan_rs::reflect::CastMode::Saturated)}
+                ];
+                const CONSTANTS: &'static [::uavcan_rs::reflect::ConstantInfo] = &[
+                    ::uavcan_rs::reflect::ConstantInfo{name: "HEALTH_OK", ty: "uint2", value: ::uavcan_rs::reflect::Value::Unsigned(0)},
+                    ::uavcan_rs::reflect::ConstantInfo{name: "OFFSET", ty: "int8", value: ::uavcan_rs::reflect::Value::Signed(-3)},
+                    ::uavcan_rs::reflect::ConstantInfo{name: "GAIN", ty: "float16", value: ::uavcan_rs::reflect::Value::Float(0.5)}
+                ];
+                const UNION: bool = false;
+                fn visit<V: ::uavcan_rs::reflect::Visitor>(&self, visitor: &mut V) {
+                    ::uavcan_rs::reflect::VisitField::visit_field(&self.health, &Self::FIELDS[0], visitor);
+                    ::uavcan_rs::reflect::VisitField::visit_field(&self._v0, &Self::FIELDS[1], visitor);
+                    ::uavcan_rs::reflect::VisitField::visit_field(&self.gain, &Self::FIELDS[2], visitor);
+                    ::uavcan_rs::reflect::VisitField::visit_field(&self.peers, &Self::FIELDS[3], visitor);
+                    ::uavcan_rs::reflect::VisitField::visit_field(&self.type_, &Self::FIELDS[4], visitor);
+                }
+            }
+        }, quote!{#(#items)*});
+
+        // only the active variant of a union is visited
+        let definition = dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(None, Ty::Primitive(PrimitiveType::Uint2), None, Some("health")),
+                 field(None, Ty::Primitive(PrimitiveType::Void3), None, None),
+            ]
+        );
+        let (item_kinds, _, _) = definition.clone().compile(&config).unwrap();
+        let items = reflect_impl("Mode", "com.example", &definition, &item_kinds, &config).unwrap();
+        assert_eq!(quote!{
+            impl ::uavcan_rs::reflect::Reflect for Mode {
+                const FIELDS: &'static [::uavcan_rs::reflect::FieldInfo] = &[
+                    ::uavcan_rs::reflect::FieldInfo{name: "health", ty: "uint2", array: ::uavcan_rs::reflect::ArrayKind::Scalar, cast_mode: Some(::uavcan_rs::reflect::CastMode::Saturated)},
+                    ::uavcan_rs::reflect::FieldInfo{name: "", ty: "void3", array: ::uavcan_rs::reflect::ArrayKind::Scalar, cast_mode: None}
+                ];
+                const CONSTANTS: &'static [::uavcan_rs::reflect::ConstantInfo] = &[];
+                const UNION: bool = true;
+                fn visit<V: ::uavcan_rs::reflect::Visitor>(&self, visitor: &mut V) {
+                    match *self {
+                        Mode::Health(ref value) => ::uavcan_rs::reflect::VisitField::visit_field(value, &Self::FIELDS[0], visitor),
+                        Mode::_V0(ref value) => ::uavcan_rs::reflect::VisitField::visit_field(value, &Self::FIELDS[1], visitor),
+                    }
+                }
+            }
+        }, quote!{#(#items)*});
+    }
+
+    #[test]
+    fn compile_text() {
+        let field = |field_type, name: Option<&str>| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(field_type),
+                array: None,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        };
+        let config = CompileConfig{text: true, ..CompileConfig::default()};
+
+        let definition = dsdl_parser::MessageDefinition(
+            vec![field(PrimitiveType::Uint2, Some("health")),
+                 field(PrimitiveType::Void3, None),
+                 field(PrimitiveType::Bool, Some("type")),
+            ]
+        );
+        let (item_kinds, _, _) = definition.clone().compile(&config).unwrap();
+        let items = text_impl("Status", &definition, &item_kinds).unwrap();
+        assert_eq!(quote!{
+            impl ::uavcan_rs::text::Parse for Status {
+                fn parse_fields(parser: &mut ::uavcan_rs::text::Parser) -> Result<Self, ::uavcan_rs::text::ParseError> {
+                    Ok(Status {
+                        health: parser.field(&<Self as ::uavcan_rs::reflect::Reflect>::FIELDS[0])?,
+                        _v0: Default::default(),
+                        type_: parser.field(&<Self as ::uavcan_rs::reflect::Reflect>::FIELDS[2])?
+                    })
+                }
+            }
+            impl ::uavcan_rs::text::fmt::Display for Status {
+                fn fmt(&self, f: &mut ::uavcan_rs::text::fmt::Formatter) -> ::uavcan_rs::text::fmt::Result {
+                    ::uavcan_rs::text::write(self, f)
+                }
+            }
+            impl ::uavcan_rs::text::FromStr for Status {
+                type Err = ::uavcan_rs::text::ParseError;
+                fn from_str(text: &str) -> Result<Self, Self::Err> {
+                    ::uavcan_rs::text::parse(text)
+                }
+            }
+        }, quote!{#(#items)*});
+
+        // a void variant is written as nothing, and parsed when no variant is given
+        let definition = dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(PrimitiveType::Uint2, Some("health")),
+                 field(PrimitiveType::Void3, None),
+            ]
+        );
+        let (item_kinds, _, _) = definition.clone().compile(&config).unwrap();
+        let items = text_impl("Mode", &definition, &item_kinds).unwrap();
+        let first = &items[..1];
+        assert_eq!(quote!{
+            impl ::uavcan_rs::text::Parse for Mode {
+                fn parse_fields(parser: &mut ::uavcan_rs::text::Parser) -> Result<Self, ::uavcan_rs::text::ParseError> {
+                    match parser.variant(<Self as ::uavcan_rs::reflect::Reflect>::FIELDS)? {
+                        0 => Ok(Mode::Health(parser.field(&<Self as ::uavcan_rs::reflect::Reflect>::FIELDS[0])?)),
+                        1 => Ok(Mode::_V0(Default::default())),
+                        _ => unreachable!("The variant is one of the fields"),
+                    }
+                }
+            }
+        }, quote!{#(#first)*});
+    }
+
+    #[test]
+    fn compile_arbitrary() {
+        let field = |field_type, name: Option<&str>| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(field_type),
+                array: None,
+                name: name.map(dsdl_parser::Ident::from),
+            }),
+            comment: None,
+        };
+        let config = CompileConfig{arbitrary: true, ..CompileConfig::default()};
+
+        let definition = dsdl_parser::MessageDefinition(
+            vec![field(PrimitiveType::Uint2, Some("health")),
+                 field(PrimitiveType::Void3, None),
+                 field(PrimitiveType::Bool, Some("type")),
+            ]
+        );
+        let (item_kinds, _, _) = definition.compile(&config).unwrap();
+        let items = arbitrary_impl("Status", &item_kinds).unwrap();
+        assert_eq!(quote!{
+            impl<'a> ::uavcan_rs::arbitrary_field::Arbitrary<'a> for Status {
+                fn arbitrary(u: &mut ::uavcan_rs::arbitrary_field::Unstructured<'a>) -> ::uavcan_rs::arbitrary_field::Result<Self> {
+                    Ok(Status {
+                        health: ::uavcan_rs::arbitrary_field::arbitrary(u)?,
+                        _v0: ::uavcan_rs::arbitrary_field::arbitrary(u)?,
+                        type_: ::uavcan_rs::arbitrary_field::arbitrary(u)?
+                    })
+                }
+            }
+        }, quote!{#(#items)*});
+
+        let definition = dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(PrimitiveType::Uint2, Some("health")),
+                 field(PrimitiveType::Void3, None),
+            ]
+        );
+        let (item_kinds, _, _) = definition.compile(&config).unwrap();
+        let items = arbitrary_impl("Mode", &item_kinds).unwrap();
+        // `quote!` splits `..=`, so the expected item is parsed instead
+        let expected = syn::parse_item(r#"
+            impl<'a> ::uavcan_rs::arbitrary_field::Arbitrary<'a> for Mode {
+                fn arbitrary(u: &mut ::uavcan_rs::arbitrary_field::Unstructured<'a>) -> ::uavcan_rs::arbitrary_field::Result<Self> {
+                    match u.int_in_range(0..=1)? {
+                        0 => Ok(Mode::Health(::uavcan_rs::arbitrary_field::arbitrary(u)?)),
+                        1 => Ok(Mode::_V0(::uavcan_rs::arbitrary_field::arbitrary(u)?)),
+                        _ => unreachable!("The variant is one of the fields"),
+                    }
+                }
+            }
+        "#).unwrap();
+        assert_eq!(quote!{#expected}, quote!{#(#items)*});
+    }
+
+    #[test]
+    fn compile_arbitrary_visitor() {
+        // the types are visited with their absolute paths, behind the features of their namespaces
+        let dsdl = DSDL::read("tests/dsdl/uavcan/protocol/341.NodeStatus.uavcan").unwrap();
+        let config = CompileConfig{arbitrary: true, namespace_features: true, ..CompileConfig::default()};
+        let visitor = arbitrary_types_visitor(&dsdl, &config, |_| true).unwrap();
+        assert_eq!(quote!{
+            #[doc = " Visits every compiled type, like for checking them with `uavcan::arbitrary_field::check_round_trip`"]
+            #[allow(unused_variables)]
+            pub fn visit_arbitrary_types<V: ::uavcan_rs::arbitrary_field::TypeVisitor>(visitor: &mut V) {
+                #[cfg(feature = "protocol")]
+                visitor.visit::<::uavcan::protocol::NodeStatus>();
+            }
+        }, quote!{#visitor});
+    }
+
+    #[test]
+    fn compile_default() {
+        let field = |field_type, array, name: &str| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type,
+                array,
+                name: Some(dsdl_parser::Ident::from(name)),
+            }),
+            comment: None,
+        };
+        let all = CompileConfig{derive_default: DeriveDefault::All, ..CompileConfig::default()};
+        let none = CompileConfig{derive_default: DeriveDefault::None, ..CompileConfig::default()};
+
+        // `Default` can't be derived for unions, they default to their first variant
+        let union = || dsdl_parser::MessageDefinition(
+            vec![Line::Directive{directive: dsdl_parser::Directive::Union, comment: None},
+                 field(Ty::Primitive(PrimitiveType::Uint2), None, "health"),
+                 field(Ty::Primitive(PrimitiveType::Uint3), None, "mode"),
+            ]
+        );
+        let (union_kinds, _, _) = union().compile(&all).unwrap();
+        let items = default_impl("Mode", &union_kinds, &all).unwrap();
+        assert_eq!(quote!{
+            impl Default for Mode {
+                fn default() -> Self {
+                    Mode::Health(Default::default())
+                }
+            }
+        }, quote!{#(#items)*});
+        let (union_kinds, _, _) = union().compile(&CompileConfig::default()).unwrap();
+        assert!(default_impl("Mode", &union_kinds, &CompileConfig::default()).unwrap().is_empty());
+
+        // nor for arrays longer than 32 elements
+        let long_array = || dsdl_parser::MessageDefinition(
+            vec![field(Ty::Primitive(PrimitiveType::Uint2), None, "health"),
+                 field(Ty::Primitive(PrimitiveType::Uint8), Some(dsdl_parser::ArrayInfo::Static(64)), "data"),
+            ]
+        );
+        let (struct_kinds, attributes, _) = long_array().compile(&CompileConfig::default()).unwrap();
+        assert_eq!(quote!{#(#attributes)*}.as_str(), "# [ derive ( Debug , Clone , UavcanStruct ) ] # [ UavcanCrateName = \"uavcan_rs\" ]");
+        let items = default_impl("Block", &struct_kinds, &CompileConfig::default()).unwrap();
+        assert_eq!(quote!{
+            impl Default for Block {
+                fn default() -> Self {
+                    Block {
+                        health: Default::default(),
+                        data: ::uavcan_rs::types::__array_from_fn(|_| Default::default())
+                    }
+                }
+            }
+        }, quote!{#(#items)*});
+        let (struct_kinds, attributes, _) = long_array().compile(&none).unwrap();
+        assert_eq!(quote!{#(#attributes)*}.as_str(), "# [ derive ( Debug , Clone , UavcanStruct ) ] # [ UavcanCrateName = \"uavcan_rs\" ]");
+        assert!(default_impl("Block", &struct_kinds, &none).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn compile_errors() {
+        let field = |field_type, array, name: &str| Line::Definition {
+            definition: AttributeDefinition::Field(dsdl_parser::FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(field_type),
+                array,
+                name: Some(dsdl_parser::Ident::from(name)),
+            }),
+            comment: None,
+        };
+
+        let constant = |field_type, name: &str, constant| Line::Definition {
+            definition: AttributeDefinition::Const(dsdl_parser::ConstDefinition {
+                cast_mode: None,
+                field_type,
+                name: dsdl_parser::Ident::from(name),
+                constant,
+            }),
+            comment: None,
+        };
+
+        let error = |lines| dsdl_parser::MessageDefinition(lines).compile(&CompileConfig::default()).unwrap_err();
+
+        let clash = error(vec![
+            Line::Comment(Comment::from_str("# comment").unwrap()),
+            field(PrimitiveType::Uint8, None, "value"),
+            Line::Empty,
+            field(PrimitiveType::Uint16, None, "value"),
+        ]);
+        assert_eq!(clash.kind(), &error::CompileErrorKind::NameClash(String::from("value")));
+        assert_eq!(clash.line(), Some(4));
+
+        let empty_array = error(vec![field(PrimitiveType::Uint8, Some(ArrayInfo::DynamicLess(1)), "empty")]);
+        assert_eq!(empty_array.kind(), &error::CompileErrorKind::InvalidArraySize(String::from("empty"), 0));
+        assert_eq!(empty_array.line(), Some(1));
+
+        let invalid = error(vec![field(PrimitiveType::Uint8, None, "node-id")]);
+        assert_eq!(invalid.kind(), &error::CompileErrorKind::InvalidIdentifier(String::from("node-id")));
+
+        let escaped_clash = error(vec![field(PrimitiveType::Uint8, None, "type"), field(PrimitiveType::Uint8, None, "type_")]);
+        assert_eq!(escaped_clash.kind(), &error::CompileErrorKind::ConvertedNameClash(String::from("type"), String::from("type_"), String::from("type_")));
+        assert_eq!(escaped_clash.line(), Some(2));
+
+        let overflow = error(vec![constant(PrimitiveType::Uint4, "BIG", Const::Dec(String::from("16")))]);
+        assert_eq!(overflow.kind(), &error::CompileErrorKind::InvalidConstant(String::from("BIG"), String::from("16 is outside the range of `u4` (0..=15)")));
+
+        let float_for_int = error(vec![constant(PrimitiveType::Uint8, "FLOAT", Const::Float(String::from("1.5")))]);
+        assert_eq!(float_for_int.kind(), &error::CompileErrorKind::InvalidConstant(String::from("FLOAT"), String::from("the float `1.5` can't be used for an integer type")));
+
+        assert_eq!(
+            format!("{}", clash.in_file("uavcan/protocol/341.NodeStatus.uavcan")),
+            "error: the name `value` is defined multiple times\n  --> uavcan/protocol/341.NodeStatus.uavcan:4",
+        );
+    }
+
     #[test]
     fn compile_variant_def() {
         let simple_field: syn::Variant = dsdl_parser::FieldDefinition{
@@ -1173,7 +3284,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Uint3),
             array: None,
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(Name(::u3)), quote!{#simple_field});
 
@@ -1182,7 +3293,7 @@ mod tests {
             field_type: Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}),
             array: None,
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(Name(::uavcan::protocol::NodeStatus)), quote!{#composite_field});
 
@@ -1191,7 +3302,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Uint3),
             array: Some(dsdl_parser::ArrayInfo::Static(19)),
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(Name([::u3; 19])), quote!{#array_field});
 
@@ -1200,7 +3311,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Int29),
             array: Some(dsdl_parser::ArrayInfo::DynamicLeq(191)),
             name: Some(dsdl_parser::Ident::from("long_name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(LongName(::Dynamic<[::i29; 191]>)), quote!{#dynleq_array_field});
         
@@ -1209,7 +3320,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Bool),
             array: Some(dsdl_parser::ArrayInfo::DynamicLeq(370)),
             name: Some(dsdl_parser::Ident::from("very_long_name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
         
         assert_eq!(quote!(VeryLongName(::Dynamic<[bool; 370]>)), quote!{#dynless_array_field});
 
@@ -1222,7 +3333,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Uint3),
             array: None,
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(pub name: ::u3), quote!{#simple_field});
 
@@ -1231,7 +3342,7 @@ mod tests {
             field_type: Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}),
             array: None,
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(pub name: ::uavcan::protocol::NodeStatus), quote!{#composite_field});
 
@@ -1240,7 +3351,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Uint3),
             array: Some(dsdl_parser::ArrayInfo::Static(19)),
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(pub name: [::u3; 19]), quote!{#array_field});
 
@@ -1249,7 +3360,7 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Int29),
             array: Some(dsdl_parser::ArrayInfo::DynamicLeq(191)),
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
 
         assert_eq!(quote!(pub name: ::Dynamic<[::i29; 191]>), quote!{#dynleq_array_field});
         
@@ -1258,50 +3369,120 @@ mod tests {
             field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Bool),
             array: Some(dsdl_parser::ArrayInfo::DynamicLeq(370)),
             name: Some(dsdl_parser::Ident::from("name")),
-        }.compile(&CompileConfig::default());
+        }.compile(&CompileConfig::default()).unwrap();
         
         assert_eq!(quote!(pub name: ::Dynamic<[bool; 370]>), quote!{#dynless_array_field});
 
     }
+
+    #[test]
+    fn compile_optional_fields() {
+        let config = CompileConfig{optional_fields: true, serde: true, ..CompileConfig::default()};
+        let field = |array| dsdl_parser::FieldDefinition{
+            cast_mode: None,
+            field_type: dsdl_parser::Ty::Primitive(PrimitiveType::Uint3),
+            array: Some(array),
+            name: Some(dsdl_parser::Ident::from("name")),
+        };
+
+        let leq_field: syn::Field = field(dsdl_parser::ArrayInfo::DynamicLeq(1)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: Option<::u3>), quote!{#leq_field});
+
+        let less_field: syn::Field = field(dsdl_parser::ArrayInfo::DynamicLess(2)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: Option<::u3>), quote!{#less_field});
+
+        // longer arrays, and any array without the option, are still `Dynamic`
+        let long_field: syn::Field = field(dsdl_parser::ArrayInfo::DynamicLeq(2)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: ::Dynamic<[::u3; 2]>), quote!{#long_field});
+        let dynamic_field: syn::Field = field(dsdl_parser::ArrayInfo::DynamicLeq(1)).compile(&CompileConfig::default()).unwrap();
+        assert_eq!(quote!(pub name: ::Dynamic<[::u3; 1]>), quote!{#dynamic_field});
+
+        assert_eq!(
+            native_parameter(&leq_field.ty, "value"),
+            (String::from("Option<u8>"), String::from("value.map(::uavcan_rs::types::FromNative::from_native).transpose()")),
+        );
+    }
+
+    #[test]
+    fn compile_bit_arrays() {
+        let config = CompileConfig{bit_arrays: true, optional_fields: true, serde: true, ..CompileConfig::default()};
+        let field = |field_type, array| dsdl_parser::FieldDefinition{
+            cast_mode: None,
+            field_type: dsdl_parser::Ty::Primitive(field_type),
+            array: Some(array),
+            name: Some(dsdl_parser::Ident::from("name")),
+        };
+
+        let static_field: syn::Field = field(PrimitiveType::Bool, dsdl_parser::ArrayInfo::Static(100)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: ::BitArray<[bool; 100]>), quote!{#static_field});
+
+        let dynamic_field: syn::Field = field(PrimitiveType::Bool, dsdl_parser::ArrayInfo::DynamicLess(100)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: ::DynamicBitArray<[bool; 99]>), quote!{#dynamic_field});
+
+        // optional fields take precedence, and arrays of other types aren't affected
+        let optional_field: syn::Field = field(PrimitiveType::Bool, dsdl_parser::ArrayInfo::DynamicLeq(1)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: Option<bool>), quote!{#optional_field});
+        let uint_field: syn::Field = field(PrimitiveType::Uint3, dsdl_parser::ArrayInfo::Static(4)).compile(&config).unwrap();
+        assert_eq!(quote!(#[serde(with = "::uavcan_rs::serde_field")] pub name: [::u3; 4]), quote!{#uint_field});
+
+        assert_eq!(
+            native_parameter(&dynamic_field.ty, "value"),
+            (String::from("&[bool]"), String::from("::uavcan_rs::types::FromNative::from_native(value)")),
+        );
+    }
         
 
     #[test]
     fn compile_type() {
-        let composite = Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}).compile(&CompileConfig::default());
+        let composite = Ty::Composite(dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}).compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::uavcan::protocol::NodeStatus), quote!{#composite});
 
-        let primitive = Ty::Primitive(PrimitiveType::Uint2).compile(&CompileConfig::default());
+        let primitive = Ty::Primitive(PrimitiveType::Uint2).compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::u2), quote!{#primitive});
 
     }
     
     #[test]
     fn compile_composite_type() {
-        let t = dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}.compile(&CompileConfig::default());
+        let t = dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::uavcan::protocol::NodeStatus), quote!{#t});
+
+        let mut config = CompileConfig::default();
+        config.extern_paths.push((String::from("uavcan"), String::from("::dsdl::uavcan")));
+        config.extern_paths.push((String::from("com.example"), String::from("example_dsdl")));
+        let extern_type = dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("uavcan.protocol")), name: dsdl_parser::Ident::from("NodeStatus")}.compile(&config).unwrap();
+        assert_eq!(quote!(::dsdl::uavcan::protocol::NodeStatus), quote!{#extern_type});
+        let relative_type = dsdl_parser::CompositeType{namespace: Some(dsdl_parser::Ident::from("com.example.type")), name: dsdl_parser::Ident::from("Sensor")}.compile(&config).unwrap();
+        assert_eq!(quote!(example_dsdl::type_::Sensor), quote!{#relative_type});
     }
     
+    #[test]
+    fn compile_feature_gate() {
+        let gate = feature_gate("uavcan.equipment.esc");
+        assert_eq!(quote!(#[cfg(feature = "equipment-esc")]), quote!{#gate});
+    }
+
     #[test]
     fn compile_primitive_type() {
-        let uint2 = PrimitiveType::Uint2.compile(&CompileConfig::default());
+        let uint2 = PrimitiveType::Uint2.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::u2), quote!{#uint2});
         
-        let int9 = PrimitiveType::Int9.compile(&CompileConfig::default());
+        let int9 = PrimitiveType::Int9.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::i9), quote!{#int9});
         
-        let void23 = PrimitiveType::Void23.compile(&CompileConfig::default());
+        let void23 = PrimitiveType::Void23.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(::void23), quote!{#void23});
         
-        let b = PrimitiveType::Bool.compile(&CompileConfig::default());
+        let b = PrimitiveType::Bool.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(bool), quote!{#b});
         
-        let float64 = PrimitiveType::Float64.compile(&CompileConfig::default());
+        let float64 = PrimitiveType::Float64.compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!(f64), quote!{#float64});
     }
     
     #[test]
     fn compile_comment() {
-        let comment = Comment::from_str("# test comment").unwrap().compile(&CompileConfig::default());
+        let comment = Comment::from_str("# test comment").unwrap().compile(&CompileConfig::default()).unwrap();
         assert_eq!(quote!{#[doc = " test comment"]
         }, quote!{#comment});
     }
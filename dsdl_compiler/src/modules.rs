@@ -0,0 +1,327 @@
+//! Compiling DSDL into a tree of modules, with a file for every type
+//!
+//! Instead of a single file, the compiled DSDL can be written as a directory tree mirroring the DSDL namespaces.
+//! The code is formatted, and every file starts with a header noting where it was generated from.
+//!
+//! ```text
+//! dsdl.rs                          the root, containing the namespace modules
+//! uavcan/mod.rs
+//! uavcan/protocol/mod.rs           nested namespaces, and re-exports of the types
+//! uavcan/protocol/node_status.rs   `NodeStatus`, together with its constants and constant enums
+//! ```
+//!
+//! The types are re-exported by their namespace module, so they have the same paths (like `uavcan::protocol::NodeStatus`) as when compiled into a single file.
+//! The root is included at the crate root, just like the single file.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate uavcan;
+//!
+//! include!(concat!(env!("OUT_DIR"), "/dsdl.rs"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use syn;
+
+use inflections::Inflect;
+
+use dsdl_parser;
+use dsdl_parser::DSDL;
+
+use builder::OUTPUT_FILE_NAME;
+use error::CompileError;
+use features::feature_name;
+use format::format_items;
+use validate::validate;
+use CompileConfig;
+
+use super::{add_preamble, arbitrary_types_visitor, code_generation_error, file_items, file_path, Layout};
+use ident;
+
+/// A file of compiled DSDL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// The path of the file, relative to the output directory
+    pub path: PathBuf,
+
+    /// The formatted code
+    pub contents: String,
+}
+
+/// A namespace module, and what it contains
+#[derive(Debug, Default)]
+struct Namespace {
+    namespaces: Vec<String>,
+    types: Vec<TypeModule>,
+}
+
+/// The module a type is compiled into
+#[derive(Debug)]
+struct TypeModule {
+    name: String,
+    public: bool,
+    exports: Vec<String>,
+    feature: Option<String>,
+}
+
+/// Validates and compiles a DSDL tree into a tree of modules
+///
+/// ```
+/// use dsdl_compiler::DSDL;
+/// use dsdl_compiler::CompileConfig;
+/// use dsdl_compiler::modules::compile_modules;
+///
+/// let dsdl = DSDL::read("tests/dsdl/").unwrap();
+/// let files = compile_modules(&dsdl, &CompileConfig::default()).unwrap();
+///
+/// assert!(files.iter().any(|file| file.path.ends_with("dsdl.rs")));
+/// ```
+pub fn compile_modules(dsdl: &DSDL, config: &CompileConfig) -> Result<Vec<GeneratedFile>, Vec<CompileError>> {
+    validate(dsdl, config)?;
+    module_tree(dsdl, config, |_| true)
+}
+
+/// Writes generated files into `dir`, creating the directories they're in
+pub fn write_files(dir: &Path, files: &[GeneratedFile]) -> io::Result<()> {
+    for file in files {
+        let path = dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.contents)?;
+    }
+    Ok(())
+}
+
+/// Compiles the files of a (validated) DSDL tree that are accepted by `filter` into a tree of modules, returning the errors of every file that fails to compile
+pub(crate) fn module_tree<F: Fn(&dsdl_parser::File) -> bool>(dsdl: &DSDL, config: &CompileConfig, filter: F) -> Result<Vec<GeneratedFile>, Vec<CompileError>> {
+    let mut namespaces: BTreeMap<Vec<String>, Namespace> = BTreeMap::new();
+    namespaces.insert(Vec::new(), Namespace::default());
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+
+    // types in extern namespaces are compiled in another crate
+    for file in dsdl.files().into_iter().filter(|file| filter(file) && config.extern_path(&file.name.namespace).is_none()) {
+        let items = match file_items(dsdl, file, config, Layout::TypeModule) {
+            Ok(items) => items,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            },
+        };
+        let mut module = type_module(file, &items);
+        if config.namespace_features {
+            module.feature = Some(feature_name(&file.name.namespace));
+        }
+        let namespace: Vec<String> = file.name.namespace.split('.').map(ident::escape).collect();
+
+        let mut path: PathBuf = namespace.iter().collect();
+        path.push(format!("{}.rs", module.name));
+        match format_items(&items) {
+            Ok(code) => files.push(GeneratedFile {
+                path,
+                contents: format!("{}\n{}", type_header(dsdl, file), code),
+            }),
+            Err(error) => errors.push(error.in_file(&file_path(&file.name))),
+        }
+
+        for depth in 0..namespace.len() {
+            let parent = namespaces.entry(namespace[..depth].to_vec()).or_default();
+            if !parent.namespaces.contains(&namespace[depth]) {
+                parent.namespaces.push(namespace[depth].clone());
+            }
+        }
+        namespaces.entry(namespace).or_default().types.push(module);
+    }
+
+    // the root module holds the preamble, and the functions covering every type
+    let mut root_items = Vec::new();
+    if config.arbitrary {
+        match arbitrary_types_visitor(dsdl, config, &filter) {
+            Ok(item) => root_items.push(item),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    for (path, namespace) in &namespaces {
+        if let Err(error) = check_module_names(path, namespace) {
+            errors.push(error);
+        }
+
+        if path.is_empty() {
+            match root_contents(namespace, root_items.clone()) {
+                Ok(contents) => files.push(GeneratedFile {
+                    path: PathBuf::from(OUTPUT_FILE_NAME),
+                    contents: format!("{}\n{}", root_header(), contents),
+                }),
+                Err(error) => errors.push(error),
+            }
+        } else {
+            files.push(GeneratedFile {
+                path: path.iter().collect::<PathBuf>().join("mod.rs"),
+                contents: format!("// Generated by dsdl_compiler from the DSDL namespace `{}`, do not edit.\n\n{}", path.join("."), namespace_contents(namespace)),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(files)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Returns the module a compiled type is placed in, and the items it exports to the namespace
+fn type_module(file: &dsdl_parser::File, items: &[syn::Item]) -> TypeModule {
+    let type_name = ident::escape(&file.name.name);
+    let mut type_names = match file.definition {
+        dsdl_parser::TypeDefinition::Message(_) => vec![type_name.clone()],
+        dsdl_parser::TypeDefinition::Service(_) => vec![type_name.clone() + "Request", type_name.clone() + "Response"],
+    };
+    // the builders are used next to the types they build
+    let builder_names: Vec<String> = type_names.iter().map(|name| format!("{}Builder", name)).collect();
+    type_names.extend(builder_names);
+
+    let public_items: Vec<&syn::Item> = items.iter().filter(|item| item.vis == syn::Visibility::Public).collect();
+    let mut exports = Vec::new();
+    let mut public = false;
+    for item in public_items {
+        let name = item.ident.to_string();
+        let is_module = matches!(item.node, syn::ItemKind::Mod(_));
+        if type_names.contains(&name) || is_module {
+            if !exports.contains(&name) {
+                exports.push(name);
+            }
+        } else {
+            // like the constant enums of a message, these are used through the type module
+            public = true;
+        }
+    }
+
+    TypeModule {
+        name: ident::escape(&type_name.to_snake_case()),
+        public,
+        exports,
+        feature: None,
+    }
+}
+
+/// Checks that the modules and re-exports in a namespace have different names
+fn check_module_names(path: &[String], namespace: &Namespace) -> Result<(), CompileError> {
+    let mut names: Vec<&String> = namespace.namespaces.iter().collect();
+    for module in &namespace.types {
+        names.push(&module.name);
+        names.extend(module.exports.iter());
+    }
+
+    match names.iter().enumerate().find(|&(i, name)| names[..i].contains(name)) {
+        Some((_, name)) => Err(code_generation_error(format!("more than one item is named `{}` in the module `{}`", name, path.join("::")))),
+        None => Ok(()),
+    }
+}
+
+fn root_header() -> String {
+    String::from("// Generated by dsdl_compiler, do not edit.\n//\n// Include this file at the crate root, the namespace modules are in the directories next to it.\n")
+}
+
+fn type_header(dsdl: &DSDL, file: &dsdl_parser::File) -> String {
+    let full_name = format!("{}", file.name);
+    let mut header = format!("// Generated by dsdl_compiler from `{}`, do not edit.\n//\n", file_path(&file.name));
+    header.push_str(&format!("// Full name: {}\n", full_name));
+    header.push_str(&format!("// DSDL signature: 0x{:016x}\n", file.clone().normalize().dsdl_signature()));
+    if let Some(data_type_signature) = dsdl.data_type_signature(&full_name) {
+        header.push_str(&format!("// Data type signature: 0x{:016x}\n", data_type_signature));
+    }
+    header
+}
+
+fn root_contents(namespace: &Namespace, mut items: Vec<syn::Item>) -> Result<String, CompileError> {
+    add_preamble(&mut items);
+    let mut contents = format_items(&items)?;
+    for module in &namespace.namespaces {
+        contents.push_str(&format!("\npub mod {};\n", module));
+    }
+    Ok(contents)
+}
+
+fn namespace_contents(namespace: &Namespace) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    if !namespace.namespaces.is_empty() {
+        blocks.push(namespace.namespaces.iter().map(|module| format!("pub mod {};\n", module)).collect());
+    }
+    for module in &namespace.types {
+        let gate = match module.feature {
+            Some(ref feature) => format!("#[cfg(feature = \"{}\")]\n", feature),
+            None => String::new(),
+        };
+        let mut block = format!("{}{}mod {};\n", gate, if module.public { "pub " } else { "" }, module.name);
+        match module.exports.len() {
+            0 => (),
+            1 => block.push_str(&format!("{}pub use self::{}::{};\n", gate, module.name, module.exports[0])),
+            _ => block.push_str(&format!("{}pub use self::{}::{{{}}};\n", gate, module.name, module.exports.join(", "))),
+        }
+        blocks.push(block);
+    }
+    blocks.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_modules() {
+        let namespace = Namespace {
+            namespaces: vec![String::from("file")],
+            types: vec![
+                TypeModule{name: String::from("node_status"), public: true, exports: vec![String::from("NodeStatus")], feature: None},
+                TypeModule{
+                    name: String::from("get_node_info"),
+                    public: false,
+                    exports: vec![String::from("GetNodeInfoRequest"), String::from("GetNodeInfoResponse")],
+                    feature: Some(String::from("protocol")),
+                },
+            ],
+        };
+
+        assert!(check_module_names(&[String::from("uavcan"), String::from("protocol")], &namespace).is_ok());
+        assert_eq!(namespace_contents(&namespace), "\
+pub mod file;
+
+pub mod node_status;
+pub use self::node_status::NodeStatus;
+
+#[cfg(feature = \"protocol\")]
+mod get_node_info;
+#[cfg(feature = \"protocol\")]
+pub use self::get_node_info::{GetNodeInfoRequest, GetNodeInfoResponse};
+");
+
+        let clash = Namespace {
+            namespaces: vec![String::from("node_status")],
+            types: vec![TypeModule{name: String::from("node_status"), public: true, exports: vec![String::from("NodeStatus")], feature: None}],
+        };
+        assert!(check_module_names(&[String::from("uavcan")], &clash).is_err());
+    }
+
+    #[test]
+    fn compile_module_tree() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+        let files = compile_modules(&dsdl, &CompileConfig::default()).unwrap();
+
+        let node_status = files.iter().find(|file| file.path == Path::new("uavcan/protocol/node_status.rs")).unwrap();
+        assert!(node_status.contents.starts_with("// Generated by dsdl_compiler from `uavcan/protocol/341.NodeStatus.uavcan`, do not edit."));
+        assert!(node_status.contents.contains("pub struct NodeStatus {\n"));
+
+        let protocol = files.iter().find(|file| file.path == Path::new("uavcan/protocol/mod.rs")).unwrap();
+        assert!(protocol.contents.contains("pub use self::node_status::NodeStatus;"));
+
+        let root = files.iter().find(|file| file.path == Path::new(OUTPUT_FILE_NAME)).unwrap();
+        assert!(root.contents.contains("\npub mod uavcan;\n"));
+    }
+}
@@ -0,0 +1,124 @@
+//! Reading DSDL from several root directories
+//!
+//! `DSDL::read` reads a single directory. To let types refer to types in another root (like vendor types using `uavcan.protocol` types),
+//! the root namespaces of every root are copied into one directory that is read as a whole.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use dsdl_parser::DSDL;
+
+/// An error that occurred when reading DSDL from several roots
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading, copying or parsing the DSDL failed
+    Io(io::Error),
+
+    /// A root namespace (and the two roots containing it) that's defined in more than one root
+    DuplicateNamespace(String, PathBuf, PathBuf),
+}
+
+/// Reads DSDL from several root directories, like the directories containing the `uavcan` and `com` namespace directories
+///
+/// A single root is read directly. When there are more roots, their root namespaces are copied into `merge_dir` (which is cleared first) before being read.
+pub fn read<P: AsRef<Path>>(roots: &[P], merge_dir: &Path) -> Result<DSDL, ReadError> {
+    if roots.len() == 1 {
+        return DSDL::read(roots[0].as_ref()).map_err(ReadError::Io);
+    }
+
+    if merge_dir.exists() {
+        fs::remove_dir_all(merge_dir).map_err(ReadError::Io)?;
+    }
+    fs::create_dir_all(merge_dir).map_err(ReadError::Io)?;
+
+    let mut namespaces: Vec<(String, PathBuf)> = Vec::new();
+    for root in roots {
+        let root = root.as_ref();
+        for entry in fs::read_dir(root).map_err(ReadError::Io)? {
+            let path = entry.map_err(ReadError::Io)?.path();
+            if !path.is_dir() {
+                continue;
+            }
+
+            let namespace = path.file_name().expect("Entries of a directory have a name").to_string_lossy().into_owned();
+            if let Some((_, other_root)) = namespaces.iter().find(|(other, _)| *other == namespace) {
+                return Err(ReadError::DuplicateNamespace(namespace, other_root.clone(), root.to_path_buf()));
+            }
+
+            copy_dsdl(&path, &merge_dir.join(&namespace)).map_err(ReadError::Io)?;
+            namespaces.push((namespace, root.to_path_buf()));
+        }
+    }
+
+    DSDL::read(merge_dir).map_err(ReadError::Io)
+}
+
+/// Copies the DSDL files (and the directories containing them) from `from` into `to`
+fn copy_dsdl(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        let destination = to.join(path.file_name().expect("Entries of a directory have a name"));
+        if path.is_dir() {
+            copy_dsdl(&path, &destination)?;
+        } else if path.extension().is_some_and(|extension| extension == "uavcan") {
+            fs::copy(&path, &destination)?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref error) => write!(f, "errored when reading DSDL: {}", error),
+            ReadError::DuplicateNamespace(ref namespace, ref first, ref second) => write!(
+                f, "the root namespace `{}` is defined in both `{}` and `{}`", namespace, first.display(), second.display(),
+            ),
+        }
+    }
+}
+
+impl ::std::error::Error for ReadError {}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+
+    use super::*;
+
+    fn write_dsdl(root: &Path, file: &str) {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap();
+    }
+
+    #[test]
+    fn merge_roots() {
+        let test_dir = env::temp_dir().join("dsdl_compiler_merge_roots");
+        let (standard, vendor, other_vendor, merged) = (test_dir.join("standard"), test_dir.join("vendor"), test_dir.join("other_vendor"), test_dir.join("merged"));
+        write_dsdl(&standard, "uavcan/protocol/341.NodeStatus.uavcan");
+        write_dsdl(&vendor, "com/example/20000.Status.uavcan");
+        write_dsdl(&vendor, "com/example/notes.txt");
+        write_dsdl(&other_vendor, "uavcan/Status.uavcan");
+
+        read(&[&standard, &vendor], &merged).unwrap();
+        assert!(merged.join("uavcan/protocol/341.NodeStatus.uavcan").exists());
+        assert!(merged.join("com/example/20000.Status.uavcan").exists());
+        assert!(!merged.join("com/example/notes.txt").exists());
+
+        match read(&[&standard, &other_vendor], &merged) {
+            Err(ReadError::DuplicateNamespace(namespace, first, second)) => {
+                assert_eq!(namespace, "uavcan");
+                assert_eq!((first, second), (standard.clone(), other_vendor.clone()));
+            },
+            _ => panic!("the uavcan namespace is defined in two roots"),
+        }
+
+        fs::remove_dir_all(test_dir).unwrap();
+    }
+}
@@ -0,0 +1,360 @@
+//! Semantic validation of DSDL, done before any code is generated
+//!
+//! `dsdl_parser` only checks that the DSDL is syntactically correct. The checks in this module makes sure the definitions also follow the rules of the [specification](http://uavcan.org/Specification/3._Data_structure_description_language/).
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use dsdl_parser;
+use dsdl_parser::DSDL;
+
+use error::CompileError;
+use error::CompileErrorKind;
+
+use super::{check_composite_types, check_name_clash, constant_enums_module, file_path, has_constant_enums, primitive_type_name, type_id};
+use ident;
+use Compile;
+use CompileConfig;
+
+/// The largest data type ID a message can have
+pub const MAX_MESSAGE_TYPE_ID: u64 = 65535;
+
+/// The largest data type ID a service can have
+pub const MAX_SERVICE_TYPE_ID: u64 = 255;
+
+/// The largest number of bits a message, service request or service response can be serialized into
+///
+/// This is the largest transfer payload (439 bytes) supported by the protocol.
+pub const MAX_BIT_LENGTH: u64 = 439 * 8;
+
+/// Validates a DSDL tree, returning every violation that was found
+///
+/// ```
+/// use dsdl_compiler::DSDL;
+/// use dsdl_compiler::CompileConfig;
+/// use dsdl_compiler::validate::validate;
+///
+/// let dsdl = DSDL::read("tests/dsdl/").unwrap();
+/// assert!(validate(&dsdl, &CompileConfig::default()).is_ok());
+/// ```
+pub fn validate(dsdl: &DSDL, config: &CompileConfig) -> Result<(), Vec<CompileError>> {
+    let mut errors = Vec::new();
+    let mut message_ids = HashMap::new();
+    let mut service_ids = HashMap::new();
+
+    for file in dsdl.files() {
+        let path = file_path(&file.name);
+
+        if let Err(error) = check_composite_types(dsdl, file) {
+            errors.push(error);
+        }
+
+        if let Some(ref id) = type_id(&file.name, config) {
+            let (max_id, used_ids) = match file.definition {
+                dsdl_parser::TypeDefinition::Message(_) => (MAX_MESSAGE_TYPE_ID, &mut message_ids),
+                dsdl_parser::TypeDefinition::Service(_) => (MAX_SERVICE_TYPE_ID, &mut service_ids),
+            };
+            match id.parse::<u64>() {
+                Ok(id) if id <= max_id => {
+                    if let Some(other) = used_ids.insert(id, format!("{}", file.name)) {
+                        errors.push(CompileError::new(CompileErrorKind::DuplicateTypeId(id, other)).in_file(&path));
+                    }
+                },
+                _ => errors.push(CompileError::new(CompileErrorKind::InvalidTypeId(id.clone(), max_id)).in_file(&path)),
+            }
+        }
+
+        let definitions = match file.definition {
+            dsdl_parser::TypeDefinition::Message(ref message) => vec![(0, message)],
+            dsdl_parser::TypeDefinition::Service(ref service) => vec![(0, &service.request), (service.request.0.len() + 1, &service.response)],
+        };
+
+        for (offset, definition) in definitions {
+            errors.extend(validate_message(definition).into_iter().map(|error| error.offset_line(offset).in_file(&path)));
+
+            match max_bit_length(dsdl, &file.name.namespace, definition, &mut vec![format!("{}", file.name)]) {
+                Ok(bit_length) if bit_length > MAX_BIT_LENGTH => {
+                    errors.push(CompileError::new(CompileErrorKind::BitLengthTooLarge(bit_length, MAX_BIT_LENGTH)).in_file(&path));
+                },
+                Ok(_) => (),
+                Err(error) => errors.push(error.in_file(&path)),
+            }
+        }
+    }
+
+    for (full_name, _) in config.type_ids.iter() {
+        if dsdl.get_file(full_name).is_none() {
+            errors.push(CompileError::new(CompileErrorKind::UnknownMappedType(String::from(full_name))));
+        }
+    }
+
+    errors.extend(check_module_names(&module_names(dsdl, config)));
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validates a single message definition (or one half of a service), without following composite types
+fn validate_message(message: &dsdl_parser::MessageDefinition) -> Vec<CompileError> {
+    let mut errors = Vec::new();
+    let lines: Vec<(usize, &dsdl_parser::Line)> = message.0.iter().enumerate().map(|(i, line)| (i + 1, line)).collect();
+
+    let union_line = lines.iter().find(|&&(_, line)| is_union(line)).map(|&(line_number, _)| line_number);
+    let fields: Vec<(usize, &dsdl_parser::FieldDefinition)> = lines.iter()
+        .filter_map(|&(line_number, line)| match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref def), ..} => Some((line_number, def)),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(union_line) = union_line {
+        if fields.len() < 2 {
+            errors.push(CompileError::new(CompileErrorKind::UnionTooSmall(fields.len())).at_line(union_line));
+        }
+    }
+
+    let mut names = ident::Names::default();
+    for &(line_number, line) in &lines {
+        if let Err(error) = check_name_clash(line, union_line.is_some(), &mut names) {
+            errors.push(error.at_line(line_number));
+        }
+    }
+
+    errors
+}
+
+/// Returns the modules placed in the namespace modules, as the namespace they're in, their DSDL name and their Rust name
+///
+/// These are the namespace modules themselves, and the modules holding the constant enums of a type.
+fn module_names(dsdl: &DSDL, config: &CompileConfig) -> Vec<(String, String, String)> {
+    // types in extern namespaces are compiled in another crate
+    let files: Vec<&dsdl_parser::File> = dsdl.files().into_iter().filter(|file| config.extern_path(&file.name.namespace).is_none()).collect();
+
+    let mut namespaces = BTreeSet::new();
+    for file in &files {
+        let segments: Vec<&str> = file.name.namespace.split('.').collect();
+        for depth in 1..segments.len() + 1 {
+            namespaces.insert((segments[..depth - 1].join("."), segments[..depth].join("."), ident::escape(segments[depth - 1])));
+        }
+    }
+    let mut modules: Vec<(String, String, String)> = namespaces.into_iter().collect();
+
+    for file in &files {
+        let definitions = match file.definition {
+            dsdl_parser::TypeDefinition::Message(ref message) => vec![("", message)],
+            dsdl_parser::TypeDefinition::Service(ref service) => vec![("Request", &service.request), ("Response", &service.response)],
+        };
+        for (suffix, definition) in definitions {
+            if has_constant_enums(definition, config) {
+                let type_name = ident::escape(&file.name.name) + suffix;
+                modules.push((file.name.namespace.clone(), format!("{}{}", file.name, suffix), constant_enums_module(&type_name)));
+            }
+        }
+    }
+
+    modules
+}
+
+/// Checks that the modules in each namespace module have different names in Rust
+fn check_module_names(modules: &[(String, String, String)]) -> Vec<CompileError> {
+    let mut names: HashMap<&str, ident::Names> = HashMap::new();
+    modules.iter()
+        .filter_map(|(namespace, dsdl_name, rust_name)| names.entry(namespace.as_str()).or_default().insert(dsdl_name, rust_name.clone()).err())
+        .collect()
+}
+
+fn is_union(line: &dsdl_parser::Line) -> bool {
+    matches!(*line, dsdl_parser::Line::Directive{directive: dsdl_parser::Directive::Union, ..})
+}
+
+/// Returns the largest number of bits a message definition can be serialized into
+///
+/// `visiting` holds the full names of the types currently being measured, starting with the validated type. Reaching the validated type again fails, as it contains itself.
+/// Composite types that can't be found don't add to the length, they're reported by `check_composite_types`.
+/// Neither do other recursive types it contains, they're reported when they're validated themselves.
+fn max_bit_length(dsdl: &DSDL, namespace: &str, message: &dsdl_parser::MessageDefinition, visiting: &mut Vec<String>) -> Result<u64, CompileError> {
+    let field_lengths: Vec<u64> = message.0.iter()
+        .filter_map(|line| match *line {
+            dsdl_parser::Line::Definition{definition: dsdl_parser::AttributeDefinition::Field(ref def), ..} => Some(def),
+            _ => None,
+        })
+        .map(|def| field_max_bit_length(dsdl, namespace, def, visiting))
+        .collect::<Result<_, _>>()?;
+
+    if message.0.iter().any(is_union) {
+        let tag_length = bits_needed(field_lengths.len().saturating_sub(1) as u64);
+        Ok(tag_length + field_lengths.into_iter().max().unwrap_or(0))
+    } else {
+        Ok(field_lengths.into_iter().fold(0, u64::saturating_add))
+    }
+}
+
+fn field_max_bit_length(dsdl: &DSDL, namespace: &str, def: &dsdl_parser::FieldDefinition, visiting: &mut Vec<String>) -> Result<u64, CompileError> {
+    let element_length = match def.field_type {
+        dsdl_parser::Ty::Primitive(ref primitive_type) => primitive_bit_length(primitive_type),
+        dsdl_parser::Ty::Composite(ref composite) => {
+            let full_name = match composite.namespace {
+                Some(ref namespace) => format!("{}.{}", namespace.as_ref(), composite.name.as_ref()),
+                None => format!("{}.{}", namespace, composite.name.as_ref()),
+            };
+            match dsdl.get_file(&full_name) {
+                Some(_) if visiting.first() == Some(&full_name) => return Err(CompileError::new(CompileErrorKind::RecursiveType(full_name))),
+                Some(file) if !visiting.contains(&full_name) => match file.definition {
+                    dsdl_parser::TypeDefinition::Message(ref message) => {
+                        visiting.push(full_name);
+                        let length = max_bit_length(dsdl, &file.name.namespace, message, visiting);
+                        visiting.pop();
+                        length?
+                    },
+                    dsdl_parser::TypeDefinition::Service(_) => 0,
+                },
+                _ => 0,
+            }
+        },
+    };
+
+    Ok(match def.array {
+        None => element_length,
+        Some(dsdl_parser::ArrayInfo::Static(size)) => element_length.saturating_mul(size),
+        Some(dsdl_parser::ArrayInfo::DynamicLeq(size)) => bits_needed(size) + element_length.saturating_mul(size),
+        Some(dsdl_parser::ArrayInfo::DynamicLess(size)) => {
+            let size = size.saturating_sub(1);
+            bits_needed(size) + element_length.saturating_mul(size)
+        },
+    })
+}
+
+/// Returns the number of bits needed to represent `value`
+pub(crate) fn bits_needed(value: u64) -> u64 {
+    u64::from(64 - value.leading_zeros())
+}
+
+pub(crate) fn primitive_bit_length(primitive_type: &dsdl_parser::PrimitiveType) -> u64 {
+    let ty = primitive_type.clone().compile(&CompileConfig::default()).expect("Primitive types always compile");
+    // primitive types are compiled into their kind followed by their bit length, e.g. `u16` or `void3`
+    match primitive_type_name(&ty).trim_start_matches(|c: char| c.is_alphabetic()) {
+        "" => 1,
+        bits => bits.parse().expect("Primitive types end with their bit length"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::str::FromStr;
+
+    use super::*;
+    use dsdl_parser::*;
+
+    fn field(field_type: PrimitiveType, array: Option<ArrayInfo>, name: &str) -> Line {
+        Line::Definition {
+            definition: AttributeDefinition::Field(FieldDefinition {
+                cast_mode: None,
+                field_type: Ty::Primitive(field_type),
+                array,
+                name: Some(dsdl_parser::Ident::from(name)),
+            }),
+            comment: None,
+        }
+    }
+
+    fn union() -> Line {
+        Line::Directive{directive: Directive::Union, comment: None}
+    }
+
+    #[test]
+    fn validate_dsdl() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+        assert_eq!(validate(&dsdl, &CompileConfig::default()), Ok(()));
+    }
+
+    #[test]
+    fn validate_union() {
+        let too_small = MessageDefinition(vec![
+            Line::Comment(Comment::from_str("# a union needs a choice").unwrap()),
+            union(),
+            field(PrimitiveType::Uint8, None, "only"),
+        ]);
+        assert_eq!(validate_message(&too_small), vec![CompileError::new(CompileErrorKind::UnionTooSmall(1)).at_line(2)]);
+
+        let clash = MessageDefinition(vec![
+            union(),
+            field(PrimitiveType::Uint8, None, "foo_bar"),
+            field(PrimitiveType::Uint16, None, "fooBar"),
+        ]);
+        assert_eq!(
+            validate_message(&clash),
+            vec![CompileError::new(CompileErrorKind::ConvertedNameClash(String::from("foo_bar"), String::from("fooBar"), String::from("FooBar"))).at_line(3)],
+        );
+
+        let valid = MessageDefinition(vec![
+            union(),
+            field(PrimitiveType::Uint8, None, "first"),
+            field(PrimitiveType::Uint16, None, "second"),
+        ]);
+        assert!(validate_message(&valid).is_empty());
+    }
+
+    #[test]
+    fn validate_recursive() {
+        let dir = env::temp_dir().join("dsdl_compiler_validate_recursive");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("test")).unwrap();
+        fs::write(dir.join("test/Recursive.uavcan"), "uint8 value\nRecursive[<=2] children\n").unwrap();
+        // containing a recursive type is only reported for the recursive type itself
+        fs::write(dir.join("test/Outer.uavcan"), "Recursive inner\n").unwrap();
+
+        let dsdl = DSDL::read(&dir).unwrap();
+        assert_eq!(validate(&dsdl, &CompileConfig::default()), Err(vec![
+            CompileError::new(CompileErrorKind::RecursiveType(String::from("test.Recursive"))).in_file("test/Recursive.uavcan"),
+        ]));
+    }
+
+    #[test]
+    fn module_name_clashes() {
+        let module = |namespace: &str, dsdl_name: &str, rust_name: String| (String::from(namespace), String::from(dsdl_name), rust_name);
+        let modules = vec![
+            module("uavcan", "uavcan.protocol", ident::escape("protocol")),
+            module("uavcan.protocol", "uavcan.protocol.node_status", ident::escape("node_status")),
+            module("uavcan.protocol", "uavcan.protocol.NodeStatus", constant_enums_module("NodeStatus")),
+            module("uavcan", "uavcan.NodeStatus", constant_enums_module("NodeStatus")),
+            module("test", "test.type", ident::escape("type")),
+            module("test", "test.Type", constant_enums_module("Type")),
+        ];
+        assert_eq!(check_module_names(&modules), vec![
+            CompileError::new(CompileErrorKind::ConvertedNameClash(String::from("uavcan.protocol.node_status"), String::from("uavcan.protocol.NodeStatus"), String::from("node_status"))),
+            CompileError::new(CompileErrorKind::ConvertedNameClash(String::from("test.type"), String::from("test.Type"), String::from("type_"))),
+        ]);
+
+        assert!(check_module_names(&[modules[0].clone(), modules[2].clone(), modules[3].clone()]).is_empty());
+    }
+
+    #[test]
+    fn max_bit_length_primitive() {
+        let dsdl = DSDL::read("tests/dsdl/").unwrap();
+
+        let message = MessageDefinition(vec![
+            field(PrimitiveType::Uint3, None, "a"),
+            field(PrimitiveType::Void5, None, "b"),
+            field(PrimitiveType::Float16, Some(ArrayInfo::Static(4)), "c"),
+            field(PrimitiveType::Bool, Some(ArrayInfo::DynamicLeq(15)), "d"),
+            field(PrimitiveType::Uint8, Some(ArrayInfo::DynamicLess(16)), "e"),
+        ]);
+        assert_eq!(max_bit_length(&dsdl, "test", &message, &mut Vec::new()), Ok(3 + 5 + 4*16 + (4 + 15) + (4 + 15*8)));
+
+        let union_message = MessageDefinition(vec![
+            union(),
+            field(PrimitiveType::Uint3, None, "a"),
+            field(PrimitiveType::Uint32, None, "b"),
+            field(PrimitiveType::Bool, None, "c"),
+        ]);
+        assert_eq!(max_bit_length(&dsdl, "test", &union_message, &mut Vec::new()), Ok(2 + 32));
+
+        let too_large = MessageDefinition(vec![field(PrimitiveType::Uint64, Some(ArrayInfo::Static(64)), "a")]);
+        assert!(max_bit_length(&dsdl, "test", &too_large, &mut Vec::new()).unwrap() > MAX_BIT_LENGTH);
+    }
+}
@@ -1,320 +1,441 @@
 #![recursion_limit="128"]
 
-extern crate regex;
 extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
 extern crate syn;
 #[macro_use]
 extern crate quote;
 
-use regex::Regex;
 use proc_macro::TokenStream;
-use syn::Body;
-use syn::Ident;
-use quote::Tokens;
-
-enum UavcanType {
-    PrimitiveType,
-    DynamicArray,
-    StaticArray,
-    Struct,
+use proc_macro2::TokenStream as Tokens;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+
+/// A field of a struct, or a variant of a union
+struct Field<'a> {
+    /// How the field is accessed, by name or by position for tuple structs
+    member: syn::Member,
+    /// The name of the field in the DSDL definition
+    name: String,
+    ty: &'a syn::Type,
+    cast_mode: &'static str,
+    /// The enum variant holding the field, for unions
+    variant: Option<&'a syn::Ident>,
 }
 
 
-#[proc_macro_derive(UavcanStruct, attributes(DSDLSignature, DataTypeSignature, UavcanCrateName))]
+#[proc_macro_derive(UavcanStruct, attributes(DSDLSignature, DataTypeSignature, UavcanCrateName, FullName, CastMode))]
 pub fn uavcan_sized(input: TokenStream) -> TokenStream {
-    let s = input.to_string();
-    let ast = syn::parse_macro_input(&s).unwrap();
-    let gen = impl_uavcan_struct(&ast);
-    gen.parse().unwrap()
+    let gen = syn::parse::<syn::DeriveInput>(input).and_then(|ast| impl_uavcan_struct(&ast));
+    gen.unwrap_or_else(|error| error.to_compile_error()).into()
 }
 
-fn impl_uavcan_struct(ast: &syn::DeriveInput) -> quote::Tokens {
+fn impl_uavcan_struct(ast: &syn::DeriveInput) -> syn::Result<Tokens> {
     let name = &ast.ident;
 
     // first handle the attributes
-    let mut dsdl_signature = quote!{0x00};
-    let mut data_type_signature = quote!{0x00};
+    let mut dsdl_signature = None;
+    let mut data_type_signature = None;
     let mut crate_name = quote!{uavcan};
-    
+    let mut full_name = name.unraw().to_string();
+
     for attr in &ast.attrs {
-        if let syn::MetaItem::NameValue(ref ident, ref lit) = attr.value {
-            if ident == "DSDLSignature" {
-                if let syn::Lit::Str(ref lit_str, _) = *lit {
-                    let value = Ident::from(lit_str.clone()); // hack needed since only string literals is supported for attributes
-                    dsdl_signature = quote!{#value};
+        if let Some(value) = string_attribute(attr, "DSDLSignature", "0x123456789abc")? {
+            dsdl_signature = Some(signature_literal(attr, &value)?);
+        } else if let Some(value) = string_attribute(attr, "DataTypeSignature", "0x123456789abc")? {
+            data_type_signature = Some(signature_literal(attr, &value)?);
+        } else if let Some(value) = string_attribute(attr, "UavcanCrateName", "uavcan_alternative")? {
+            let ident = syn::parse_str::<syn::Ident>(&value)
+                .map_err(|_| syn::Error::new_spanned(attr, "Crate name must be on the form \"uavcan_alternative\""))?;
+            crate_name = quote!{#ident};
+        } else if let Some(value) = string_attribute(attr, "FullName", "uavcan.protocol.NodeStatus")? {
+            full_name = value;
+        }
+    }
+    let krate = quote!{::#crate_name};
+
+    let (fields, union) = match ast.data {
+        syn::Data::Struct(ref data) => (struct_fields(&data.fields)?, false),
+        syn::Data::Enum(ref data) => (union_fields(data)?, true),
+        syn::Data::Union(ref data) => return Err(syn::Error::new_spanned(
+            data.union_token,
+            "UavcanStruct can't be derived for Rust unions, derive it for an enum to get a DSDL union",
+        )),
+    };
+
+    // the field types are looked up through `Serializable` and `DsdlField`, spanned so unsupported types are pointed out
+    let serializable: Vec<Tokens> = fields.iter().map(|field| {
+        let ty = field.ty;
+        quote_spanned!{ty.span()=> <#ty as #krate::Serializable>}
+    }).collect();
+    let dsdl_field: Vec<Tokens> = fields.iter().map(|field| {
+        let ty = field.ty;
+        quote_spanned!{ty.span()=> <#ty as #krate::DsdlField>}
+    }).collect();
+
+    // the normalized DSDL definition is built from string parts since the field types are only known by the compiler
+    let mut dsdl_definition = Vec::new();
+    if union {
+        dsdl_definition.push(quote!{"@union\n"});
+    }
+    for (field, dsdl_field) in fields.iter().zip(&dsdl_field) {
+        let cast_mode = format!("{} ", field.cast_mode);
+        let name = format!(" {}\n", field.name);
+        dsdl_definition.push(quote!{if #dsdl_field::PRIMITIVE && !#dsdl_field::VOID { #cast_mode } else { "" }});
+        dsdl_definition.push(quote!{#dsdl_field::DSDL_TYPE});
+        dsdl_definition.push(quote!{#dsdl_field::DSDL_ARRAY});
+        dsdl_definition.push(quote!{if #dsdl_field::VOID { "\n" } else { #name }});
+    }
+
+    let bit_length_min;
+    let flattened_fields;
+    let serialize_body;
+    let deserialize_body;
+    let placeholder;
+
+    if union {
+        // the tag is the index of the active variant, with as few bits as needed for the last index
+        let tag_bits = 64 - (fields.len() as u64).saturating_sub(1).leading_zeros() as usize;
+        let variants: Vec<&syn::Ident> = fields.iter().map(|field| field.variant.expect("Union fields are variants")).collect();
+        let tags: Vec<u64> = (0..fields.len() as u64).collect();
+
+        bit_length_min = if fields.is_empty() {
+            quote!{0}
+        } else {
+            quote!{{
+                let mut min = usize::max_value();
+                #(if #serializable::BIT_LENGTH_MIN < min {
+                    min = #serializable::BIT_LENGTH_MIN;
+                })*
+                #tag_bits + min
+            }}
+        };
+
+        // the tag is the first flattened field, followed by the fields of the active variant
+        flattened_fields = quote!{{
+            let mut max = 0;
+            #(if #serializable::FLATTENED_FIELDS_NUMBER > max {
+                max = #serializable::FLATTENED_FIELDS_NUMBER;
+            })*
+            1 + max
+        }};
+
+        let variants_ref = &variants;
+        let serializable_ref = &serializable;
+        let tags_len = fields.len() as u64;
+
+        placeholder = match (variants.first(), serializable.first()) {
+            (Some(variant), Some(serializable)) => quote!{#name::#variant(#serializable::placeholder())},
+            _ => quote!{panic!("A union without variants has no values")},
+        };
+
+        // variants without any flattened fields (empty structs) have nothing to (de)serialize after the tag
+        let serialize_variants = variants.iter().zip(&serializable).map(|(variant, serializable)| quote!{
+            #name::#variant(ref value) => if #serializable::FLATTENED_FIELDS_NUMBER == 0 {
+                #krate::SerializationResult::Finished
+            } else {
+                #krate::Serializable::serialize(value, &mut current_field, bit, last_field, buffer)
+            }
+        });
+        let deserialize_variants = variants.iter().zip(&serializable).map(|(variant, serializable)| quote!{
+            #name::#variant(ref mut value) => if #serializable::FLATTENED_FIELDS_NUMBER == 0 {
+                #krate::DeserializationResult::Finished
+            } else {
+                #krate::Serializable::deserialize(value, &mut current_field, bit, last_field, buffer)
+            }
+        });
+        serialize_body = quote!{
+            if *flattened_field == 0 {
+                let tag: u64 = match *self { #(#name::#variants_ref(_) => #tags,)* };
+                let tag_bits_remaining = #tag_bits - *bit;
+                let buffer_bits_remaining = buffer.bits_remaining();
+                if tag_bits_remaining == 0 {
+                    *flattened_field = 1;
+                    *bit = 0;
+                } else if buffer_bits_remaining == 0 {
+                    return #krate::SerializationResult::BufferFull;
+                } else if buffer_bits_remaining >= tag_bits_remaining {
+                    buffer.push_bits(tag_bits_remaining, tag >> *bit);
+                    *flattened_field = 1;
+                    *bit = 0;
                 } else {
-                    panic!("DSDLSignature must be on the form \"0x123456789abc\"");
+                    buffer.push_bits(buffer_bits_remaining, tag >> *bit);
+                    *bit += buffer_bits_remaining;
+                    return #krate::SerializationResult::BufferFull;
                 }
-            } else if ident == "DataTypeSignature" {
-                if let syn::Lit::Str(ref lit_str, _) = *lit {
-                    let value = Ident::from(lit_str.clone()); // hack needed since only string literals is supported for attributes
-                    data_type_signature = quote!{#value};
+            } else {
+                let mut current_field = *flattened_field - 1;
+                let result = match *self {
+                    #(#serialize_variants,)*
+                };
+                if result == #krate::SerializationResult::Finished {
+                    *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+                    *bit = 0;
+                } else {
+                    *flattened_field = 1 + current_field;
+                    return #krate::SerializationResult::BufferFull;
+                }
+            }
+        };
+
+        // a partially deserialized tag is kept as the variant it selects, the bits read so far are always a valid tag
+        let select_variant = quote!{
+            match tag {
+                #(#tags => *self = #name::#variants_ref(#serializable_ref::placeholder()),)*
+                _ => (),
+            }
+        };
+        deserialize_body = quote!{
+            if *flattened_field == 0 {
+                let mut tag: u64 = if *bit == 0 { 0 } else { match *self { #(#name::#variants_ref(_) => #tags,)* } };
+                let tag_bits_remaining = #tag_bits - *bit;
+                let buffer_len = buffer.bit_length();
+                if tag_bits_remaining == 0 {
+                    #select_variant
+                    *flattened_field = 1;
+                    *bit = 0;
+                } else if buffer_len == 0 {
+                    return #krate::DeserializationResult::BufferInsufficient;
+                } else if buffer_len < tag_bits_remaining {
+                    tag |= buffer.pop_bits(buffer_len) << *bit;
+                    #select_variant
+                    *bit += buffer_len;
+                    return #krate::DeserializationResult::BufferInsufficient;
                 } else {
-                    panic!("Data type signature must be on the form \"0x123456789abc\"");
+                    tag |= buffer.pop_bits(tag_bits_remaining) << *bit;
+                    #select_variant
+                    *bit = 0;
+                    // a tag not selecting any variant leaves nothing more to deserialize
+                    *flattened_field = if tag < #tags_len { 1 } else { Self::FLATTENED_FIELDS_NUMBER };
                 }
-            } else if ident == "UavcanCrateName" {
-                if let syn::Lit::Str(ref lit_str, _) = *lit {
-                    let value = Ident::from(lit_str.clone()); // hack needed since only string literals is supported for attributes
-                    crate_name = quote!{#value};
+            } else {
+                let mut current_field = *flattened_field - 1;
+                let result = match *self {
+                    #(#deserialize_variants,)*
+                };
+                if result == #krate::DeserializationResult::Finished {
+                    *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+                    *bit = 0;
                 } else {
-                    panic!("Crate name must be on the form \"uavcan_alternative\"");
+                    *flattened_field = 1 + current_field;
+                    return #krate::DeserializationResult::BufferInsufficient;
                 }
             }
+        };
+    } else {
+        bit_length_min = quote!{0 #(+ #serializable::BIT_LENGTH_MIN)*};
+        flattened_fields = quote!{0 #(+ #serializable::FLATTENED_FIELDS_NUMBER)*};
+
+        let members = fields.iter().map(|field| &field.member);
+        let serializable_ref = &serializable;
+        placeholder = quote!{#name{#(#members: #serializable_ref::placeholder()),*}};
+
+        let mut serialize_fields = Vec::new();
+        let mut deserialize_fields = Vec::new();
+        let mut field_index = quote!{0};
+
+        for (i, (field, serializable)) in fields.iter().zip(&serializable).enumerate() {
+            let member = &field.member;
+            let field_length = quote!{#serializable::FLATTENED_FIELDS_NUMBER};
+            let last_field = i == fields.len()-1;
+
+            serialize_fields.push(quote!{if *flattened_field >= (#field_index) && *flattened_field < (#field_index) + #field_length {
+                let mut current_field = *flattened_field - (#field_index);
+                if #krate::Serializable::serialize(&self.#member, &mut current_field, bit, #last_field && last_field, buffer) == #krate::SerializationResult::Finished {
+                    *flattened_field = (#field_index) + current_field;
+                    *bit = 0;
+                } else {
+                    *flattened_field = (#field_index) + current_field;
+                    return #krate::SerializationResult::BufferFull;
+                }
+            }});
 
+            deserialize_fields.push(quote!{if *flattened_field >= (#field_index) && *flattened_field < (#field_index) + #field_length {
+                let mut current_field = *flattened_field - (#field_index);
+                if #krate::Serializable::deserialize(&mut self.#member, &mut current_field, bit, #last_field && last_field, buffer) == #krate::DeserializationResult::Finished {
+                    *flattened_field = (#field_index) + current_field;
+                    *bit = 0;
+                } else {
+                    *flattened_field = (#field_index) + current_field;
+                    return #krate::DeserializationResult::BufferInsufficient;
+                }
+            }});
+
+            field_index = quote!{#field_index + #field_length};
         }
+
+        serialize_body = quote!{#(#serialize_fields)else*};
+        deserialize_body = quote!{#(#deserialize_fields)else*};
     }
 
-    let mut bit_length_min = Tokens::new();
-    let mut flattened_fields = Tokens::new();
-    let mut serialize_body = Tokens::new();
-    let mut deserialize_body = Tokens::new();
-    
-    match ast.body {
-        Body::Enum(ref variants) => {
-            // MIN and MAX bit length for enums is not implemented yet
-            bit_length_min.append(quote!{0});
-
-            flattened_fields.append(quote!{0});
-            
-            for variant in variants {
-                if variant.data.fields().len() != 1 {
-                    panic!("Enum variants must have exactly one field");
-                } else if let Some(field) = variant.data.fields().first() {
-                    let field_type = &field.ty;
-                    
-                    match classify_type(field_type) {
-                        UavcanType::PrimitiveType | UavcanType::DynamicArray | UavcanType::StaticArray => flattened_fields.append(quote!{ + 1}),
-                        UavcanType::Struct => flattened_fields.append(quote!{ + #field_type::FLATTENED_FIELDS_NUMBER}),
-                    }
-                }
-            }
+    // signatures not given as attributes are computed at compile time from the DSDL definition
+    let dsdl_signature = dsdl_signature.unwrap_or_else(|| quote!{
+        #krate::crc::dsdl_signature(Self::FULL_NAME, Self::DSDL_DEFINITION_PARTS)
+    });
 
-            serialize_body = quote!(unimplemented!("Serialization is not implemented for enum yet"));
-            deserialize_body = quote!(unimplemented!("Serialization is not implemented for enum yet"));
-
-        },
-        Body::Struct(syn::VariantData::Struct(ref fields)) => {
-            let mut field_index = Tokens::new();
-            
-            bit_length_min.append(quote!{0});
-            flattened_fields.append(quote!{0});
-            field_index.append(quote!{0});
-            
-            for (i, field) in fields.iter().enumerate() {
-                let field_ident = &field.ident;
-                let field_type = &field.ty;
-
-                let last_field = if i == fields.len()-1 {
-                    quote!{true}
-                } else {
-                    quote!{false}
-                };
-                
-                
-                match classify_type(field_type) {
-                    UavcanType::PrimitiveType => bit_length_min.append(quote!{ + <#field_type as ::#crate_name::Serializable>::BIT_LENGTH_MIN}),
-                    UavcanType::StaticArray => bit_length_min.append(quote!{ + <#field_type as ::#crate_name::Serializable>::BIT_LENGTH_MIN}),
-                    UavcanType::DynamicArray => {
-                        let array_type = array_from_dynamic(field_type);
-                        bit_length_min.append(quote!{ + <::#crate_name::types::Dynamic<#array_type> as ::#crate_name::Serializable>::BIT_LENGTH_MIN});
-                    },
-                    UavcanType::Struct => bit_length_min.append(quote!{ + <#field_type as ::#crate_name::Serializable>::BIT_LENGTH_MIN}),
-                }
-                
-                match classify_type(field_type) {
-                    UavcanType::PrimitiveType => flattened_fields.append(quote!{ + 1}),
-                    UavcanType::StaticArray => flattened_fields.append(quote!{ + <#field_type as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER}),
-                    UavcanType::DynamicArray => {
-                        let array_type = array_from_dynamic(field_type);
-                        flattened_fields.append(quote!{ + <::#crate_name::types::Dynamic<#array_type> as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER});
-                    },
-                    UavcanType::Struct => flattened_fields.append(quote!{ + <#field_type as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER}),
-                }
-            
-
-                if i != 0 { serialize_body.append(quote!{ else });}
-                if i != 0 { deserialize_body.append(quote!{ else });}
-                
-                let field_length = match classify_type(field_type) {
-                    UavcanType::PrimitiveType => quote!(1),
-                    UavcanType::StaticArray => quote!(<#field_type as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER),
-                    UavcanType::DynamicArray => {
-                        let array_type = array_from_dynamic(field_type);
-                        quote!{<::#crate_name::types::Dynamic<#array_type> as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER}
-                    },
-                    UavcanType::Struct => quote!{<#field_type as ::#crate_name::Serializable>::FLATTENED_FIELDS_NUMBER},
+    let data_type_signature = data_type_signature.unwrap_or_else(|| if fields.is_empty() {
+        quote!{Self::DSDL_SIGNATURE}
+    } else {
+        quote!{{
+            let mut crc = #krate::crc::SignatureCRC::extend(Self::DSDL_SIGNATURE);
+            #(if let Some(signature) = #dsdl_field::NESTED_SIGNATURE {
+                crc.add_nested_signature(signature);
+            })*
+            crc.value()
+        }}
+    });
+
+    // fields whose types depend on the type parameters must be serializable for the type to be
+    let mut generics = ast.generics.clone();
+    let type_params: Vec<syn::Ident> = generics.type_params().map(|param| param.ident.clone()).collect();
+    if !type_params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for field in fields.iter().filter(|field| mentions_type_params(field.ty, &type_params)) {
+            let ty = field.ty;
+            where_clause.predicates.push(parse_quote!{#ty: #krate::Serializable + #krate::DsdlField});
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // the definition is concatenated at compile time, which is only possible when its length doesn't depend on generic parameters
+    let dsdl_definition_str = if ast.generics.params.is_empty() {
+        quote!{
+            impl #name {
+                /// The normalized DSDL definition (the content of the `.uavcan` file)
+                #[allow(dead_code)]
+                pub const DSDL_DEFINITION: &'static str = {
+                    const PARTS: &'static [&'static str] = <#name as #krate::Struct>::DSDL_DEFINITION_PARTS;
+                    const BYTES: [u8; #krate::dsdl_definition_length(PARTS)] = #krate::dsdl_definition_bytes(PARTS);
+                    #krate::dsdl_definition_str(&BYTES)
                 };
-                
-                serialize_body.append(quote!{if *flattened_field >= (#field_index) && *flattened_field < (#field_index) + #field_length {
-                    let mut current_field = *flattened_field - (#field_index);
-                    if ::#crate_name::Serializable::serialize(&self.#field_ident, &mut current_field, bit, #last_field && last_field, buffer) == ::#crate_name::SerializationResult::Finished {
-                        *flattened_field = (#field_index) + current_field;
-                        *bit = 0;
-                    } else {
-                        *flattened_field = (#field_index) + current_field;
-                        return ::#crate_name::SerializationResult::BufferFull;
-                    }
-                }});
-
-                deserialize_body.append(quote!{if *flattened_field >= (#field_index) && *flattened_field < (#field_index) + #field_length {
-                    let mut current_field = *flattened_field - (#field_index);
-                    if ::#crate_name::Serializable::deserialize(&mut self.#field_ident, &mut current_field, bit, #last_field && last_field, buffer) == ::#crate_name::DeserializationResult::Finished {
-                        *flattened_field = (#field_index) + current_field;
-                        *bit = 0;
-                    } else {
-                        *flattened_field = (#field_index) + current_field;
-                        return ::#crate_name::DeserializationResult::BufferInsufficient;
-                    }
-                }});
-                
-                field_index.append(quote!{ + #field_length});
             }
-        },
-        Body::Struct(syn::VariantData::Unit) => {
-            bit_length_min = quote!(0);
-            flattened_fields = quote!(0);
-            serialize_body = quote!{
-                assert_eq!(*flattened_fields, 0);
-                *bit = 0;
-                *flattened_fields = 1;
-            };
-            deserialize_body = quote!{
-                assert_eq!(*flattened_fields, 0);
-                *bit = 0;
-                *flattened_fields = 1;
-            };
-
-        },
-        _ => panic!("UavcanStruct is only derivable for enums and named structs"),
+        }
+    } else {
+        quote!{}
     };
 
-    
-    quote!{
-        impl ::#crate_name::Struct for #name {
+    Ok(quote!{
+        impl #impl_generics #krate::Struct for #name #ty_generics #where_clause {
+            const FULL_NAME: &'static str = #full_name;
+            const DSDL_DEFINITION_PARTS: &'static [&'static str] = &[#(#dsdl_definition),*];
             const DSDL_SIGNATURE: u64 = #dsdl_signature;
             const DATA_TYPE_SIGNATURE: u64 = #data_type_signature;
         }
 
-        impl ::#crate_name::Serializable for #name {
+        #dsdl_definition_str
+
+        impl #impl_generics #krate::Serializable for #name #ty_generics #where_clause {
             const BIT_LENGTH_MIN: usize = #bit_length_min;
             const FLATTENED_FIELDS_NUMBER: usize = #flattened_fields;
             #[allow(unused_comparisons)]
             #[allow(unused_variables)]
-            fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut ::#crate_name::SerializationBuffer) -> ::#crate_name::SerializationResult {
+            fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut #krate::SerializationBuffer) -> #krate::SerializationResult {
                 assert!(*flattened_field < Self::FLATTENED_FIELDS_NUMBER);
                 while *flattened_field != Self::FLATTENED_FIELDS_NUMBER{
                     assert!(*flattened_field < Self::FLATTENED_FIELDS_NUMBER);
                     #serialize_body
                 }
-                ::#crate_name::SerializationResult::Finished
+                #krate::SerializationResult::Finished
             }
 
             #[allow(unused_comparisons)]
             #[allow(unused_variables)]
-            fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut ::#crate_name::DeserializationBuffer) -> ::#crate_name::DeserializationResult {
+            fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut #krate::DeserializationBuffer) -> #krate::DeserializationResult {
                 assert!(*flattened_field < Self::FLATTENED_FIELDS_NUMBER);
                 while *flattened_field != Self::FLATTENED_FIELDS_NUMBER{
                     assert!(*flattened_field < Self::FLATTENED_FIELDS_NUMBER);
                     #deserialize_body
                 }
-                ::#crate_name::DeserializationResult::Finished
+                #krate::DeserializationResult::Finished
             }
 
-
+            fn placeholder() -> Self {
+                #placeholder
+            }
        }
 
-    }
-}
-
-fn classify_type(ty: &syn::Ty) -> UavcanType {
-    if is_primitive_type(ty) {
-        UavcanType::PrimitiveType
-    } else if is_dynamic_array(ty) {
-        UavcanType::DynamicArray
-    } else if is_static_array(ty) {
-        UavcanType::StaticArray
-    } else {
-        UavcanType::Struct
-    }
-}
-
-fn is_primitive_type(ty: &syn::Ty) -> bool {
-    is_unsigned_primitive_type(ty) || is_signed_primitive_type(ty) || is_void_primitive_type(ty) || is_float_primitive_type(ty) || is_bool_primitive_type(ty)
+    })
 }
 
-fn is_bool_primitive_type(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *ty {
-        let re = Regex::new(r"bool").unwrap();
-        re.is_match(path.segments.as_slice().last().unwrap().ident.as_ref())
-    } else {
-        false
-    }
+/// Returns the fields of a struct, the fields of tuple structs are named after their position (`field0`, `field1`, ...)
+fn struct_fields(fields: &syn::Fields) -> syn::Result<Vec<Field<'_>>> {
+    fields.iter().enumerate().map(|(i, field)| {
+        let (member, name) = match field.ident {
+            Some(ref ident) => (syn::Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (syn::Member::Unnamed(syn::Index::from(i)), format!("field{}", i)),
+        };
+        Ok(Field{member, name, ty: &field.ty, cast_mode: cast_mode(&field.attrs)?, variant: None})
+    }).collect()
 }
 
-fn is_unsigned_primitive_type(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *ty {
-        let re = Regex::new(r"u([2-9]|[1-5][0-9]|6[0-4])").unwrap();
-        re.is_match(path.segments.as_slice().last().unwrap().ident.as_ref())
-    } else {
-        false
-    }
+/// Returns the variants of a union as fields, named after the variant in snake case
+fn union_fields(data: &syn::DataEnum) -> syn::Result<Vec<Field<'_>>> {
+    data.variants.iter().map(|variant| {
+        if variant.fields.len() != 1 {
+            return Err(syn::Error::new_spanned(variant, "Enum variants must have exactly one field"));
+        }
+        let field = variant.fields.iter().next().expect("The number of fields is checked above");
+        Ok(Field{
+            member: syn::Member::Unnamed(syn::Index::from(0)),
+            name: to_snake_case(&variant.ident.unraw().to_string()),
+            ty: &field.ty,
+            cast_mode: cast_mode(&variant.attrs)?,
+            variant: Some(&variant.ident),
+        })
+    }).collect()
 }
 
-fn is_signed_primitive_type(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *ty {
-        let re = Regex::new(r"i([2-9]|[1-5][0-9]|6[0-4])").unwrap();
-        re.is_match(path.segments.as_slice().last().unwrap().ident.as_ref())
-    } else {
-        false
+/// Returns the value of `#[name = "..."]`, or `None` if the attribute has another name
+fn string_attribute(attr: &syn::Attribute, name: &str, example: &str) -> syn::Result<Option<String>> {
+    if !attr.path.is_ident(name) {
+        return Ok(None);
     }
-}
-
-fn is_float_primitive_type(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *ty {
-        let re = Regex::new(r"f(16)|(32)|64").unwrap();
-        re.is_match(path.segments.as_slice().last().unwrap().ident.as_ref())
-    } else {
-        false
+    match attr.parse_meta() {
+        Ok(syn::Meta::NameValue(syn::MetaNameValue{lit: syn::Lit::Str(ref lit), ..})) => Ok(Some(lit.value())),
+        _ => Err(syn::Error::new_spanned(attr, format!("{} must be on the form #[{} = \"{}\"]", name, name, example))),
     }
 }
 
-fn is_void_primitive_type(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *ty {
-        let re = Regex::new(r"void([1-9]|[1-5][0-9]|6[0-4])").unwrap();
-        re.is_match(path.segments.as_slice().last().unwrap().ident.as_ref())
-    } else {
-        false
+/// Parses a signature given as a string attribute, since only string literals are supported for attributes
+fn signature_literal(attr: &syn::Attribute, value: &str) -> syn::Result<Tokens> {
+    match syn::parse_str::<syn::LitInt>(value) {
+        Ok(ref lit) if lit.base10_parse::<u64>().is_ok() => Ok(quote!{#lit}),
+        _ => Err(syn::Error::new_spanned(attr, "Signatures must be on the form \"0x123456789abc\"")),
     }
 }
 
-fn is_static_array(ty: &syn::Ty) -> bool {
-    if let syn::Ty::Array(_, _) = *ty {
-        true
-    } else {
-        false
+fn cast_mode(attrs: &[syn::Attribute]) -> syn::Result<&'static str> {
+    for attr in attrs {
+        match string_attribute(attr, "CastMode", "saturated")? {
+            Some(ref value) if value == "saturated" => return Ok("saturated"),
+            Some(ref value) if value == "truncated" => return Ok("truncated"),
+            Some(_) => return Err(syn::Error::new_spanned(attr, "CastMode must be either \"saturated\" or \"truncated\"")),
+            None => (),
+        }
     }
+    Ok("saturated")
 }
 
-fn is_dynamic_array(type_name: &syn::Ty) -> bool {
-    if let syn::Ty::Path(_, ref path) = *type_name {
-        if path.segments.as_slice().last().unwrap().ident == syn::parse::ident("Dynamic").expect("") {
-            return true;
-        }
+/// Returns true if the type refers to any of the type parameters
+fn mentions_type_params(ty: &syn::Type, type_params: &[syn::Ident]) -> bool {
+    fn mentions(tokens: Tokens, type_params: &[syn::Ident]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ref ident) => type_params.contains(ident),
+            proc_macro2::TokenTree::Group(ref group) => mentions(group.stream(), type_params),
+            _ => false,
+        })
     }
-    false
+    mentions(quote!{#ty}, type_params)
 }
 
-fn array_from_dynamic(type_name: &syn::Ty) -> Option<syn::Ty> {
-    if let syn::Ty::Path(_, ref path) = *type_name {
-        if path.segments.as_slice().last().unwrap().ident == syn::Ident::from("Dynamic") {
-            if let syn::PathSegment{
-                parameters: syn::PathParameters::AngleBracketed(syn::AngleBracketedParameterData{
-                    ref types, ..
-                }), ..
-            } = *path.segments.as_slice().last().unwrap() {
-                return Some(types[0].clone());
-            }
+fn to_snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            snake_case.push('_');
         }
+        snake_case.extend(c.to_lowercase());
     }
-    None
+    snake_case
 }
@@ -0,0 +1,330 @@
+//! Arbitrary values for round trip testing
+//!
+//! `ux` and `f16` are defined in other crates, so they can't implement `Arbitrary` here.
+//! Instead, types generated with arbitrary support create their fields with `arbitrary_field::arbitrary`,
+//! which works for primitive fields (in range of the field, so `uint2` is one of 0 to 3), arrays, optional fields
+//! and nested types implementing `Arbitrary`. `Dynamic` arrays, packed bit arrays and void types implement
+//! `Arbitrary` directly.
+//!
+//! `check_round_trip` sends an arbitrary value through the transport, split into frames of an arbitrary length,
+//! and checks that the received value is the same. It's meant to be driven by a fuzzer or a property testing
+//! library feeding it random bytes.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate uavcan;
+//!
+//! use uavcan::arbitrary_field::{self, Arbitrary, Unstructured};
+//! use uavcan::types::*;
+//!
+//! #[derive(UavcanStruct)]
+//! #[FullName = "com.example.Reading"]
+//! struct Reading {
+//!     sensor: u5,
+//!     values: Dynamic<[f16; 4]>,
+//! }
+//!
+//! impl<'a> Arbitrary<'a> for Reading {
+//!     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary_field::Result<Self> {
+//!         Ok(Reading {
+//!             sensor: arbitrary_field::arbitrary(u)?,
+//!             values: arbitrary_field::arbitrary(u)?,
+//!         })
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let data = [0x5a; 64];
+//! arbitrary_field::check_round_trip::<Reading>(&mut Unstructured::new(&data)).unwrap();
+//! # }
+//! ```
+
+use std::vec::Vec;
+
+pub use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use Frame;
+use Struct;
+
+use frame_assembler::FrameAssembler;
+use frame_disassembler::FrameDisassembler;
+use serializer::{SerializationBuffer, SerializationResult, Serializer};
+use transfer::{TransferFrame, TransferFrameID, TransferID};
+use types::f16;
+
+/// A field type that can be created with `arbitrary_field`
+pub trait ArbitraryField: Sized {
+    fn arbitrary_field(u: &mut Unstructured) -> Result<Self>;
+}
+
+/// Creates an arbitrary field, used by the `Arbitrary` implementations of generated types
+pub fn arbitrary<T: ArbitraryField>(u: &mut Unstructured) -> Result<T> {
+    T::arbitrary_field(u)
+}
+
+/// Visits every type of a set, like the `visit_arbitrary_types` function generated by the DSDL compiler
+pub trait TypeVisitor {
+    fn visit<T: Struct + for<'a> Arbitrary<'a>>(&mut self);
+}
+
+/// The data lengths of the transport frames used by `check_round_trip`, from the smallest useful frame to CAN FD frames
+const FRAME_LENGTHS: [usize; 12] = [3, 4, 5, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Sends an arbitrary value of `T` through frames of an arbitrary length, and checks that the received value is the same
+///
+/// The values are compared by their serialized form, which doesn't need `PartialEq` and treats NaN as equal to itself.
+/// `u` decides the frame length and IDs, the rest of its data is used to create the value.
+///
+/// ## Panics
+/// Panics if the frames can't be assembled, or if the received value is different.
+pub fn check_round_trip<T: Struct + for<'a> Arbitrary<'a>>(u: &mut Unstructured) -> Result<()> {
+    let frame_length = *u.choose(&FRAME_LENGTHS)?;
+    let id = TransferFrameID::new(u.int_in_range(0..=0x1fff_ffff)?);
+    let transfer_id = TransferID::new(u.int_in_range(0..=0x1f)?);
+
+    // `Struct` isn't `Clone`, so the value is created twice from the same data
+    let data = u.bytes(u.len())?;
+    let expected = serialize(T::arbitrary(&mut Unstructured::new(data))?);
+    let value = T::arbitrary(&mut Unstructured::new(data))?;
+
+    let received = match frame_length {
+        3 => transmit::<T, TestFrame<3>>(value, id, transfer_id),
+        4 => transmit::<T, TestFrame<4>>(value, id, transfer_id),
+        5 => transmit::<T, TestFrame<5>>(value, id, transfer_id),
+        7 => transmit::<T, TestFrame<7>>(value, id, transfer_id),
+        8 => transmit::<T, TestFrame<8>>(value, id, transfer_id),
+        12 => transmit::<T, TestFrame<12>>(value, id, transfer_id),
+        16 => transmit::<T, TestFrame<16>>(value, id, transfer_id),
+        20 => transmit::<T, TestFrame<20>>(value, id, transfer_id),
+        24 => transmit::<T, TestFrame<24>>(value, id, transfer_id),
+        32 => transmit::<T, TestFrame<32>>(value, id, transfer_id),
+        48 => transmit::<T, TestFrame<48>>(value, id, transfer_id),
+        64 => transmit::<T, TestFrame<64>>(value, id, transfer_id),
+        _ => unreachable!("The frame length is one of FRAME_LENGTHS"),
+    };
+
+    assert_eq!(
+        serialize(received), expected,
+        "`{}` changed when sent in frames of {} bytes", T::FULL_NAME, frame_length,
+    );
+    Ok(())
+}
+
+/// Disassembles `value` into frames of type `F` and assembles them again
+fn transmit<T: Struct, F: TransferFrame>(value: T, id: TransferFrameID, transfer_id: TransferID) -> T {
+    let mut disassembler = FrameDisassembler::from_uavcan_frame(Frame::from_parts(id, value), transfer_id);
+    let mut assembler = FrameAssembler::new();
+    while let Some(frame) = disassembler.next_transfer_frame::<F>() {
+        assembler.add_transfer_frame(frame).unwrap_or_else(|error| panic!("`{}` can't be assembled: {:?}", T::FULL_NAME, error));
+    }
+    let frame = assembler.build().unwrap_or_else(|error| panic!("`{}` can't be built: {:?}", T::FULL_NAME, error));
+    frame.into_parts().1
+}
+
+/// Serializes `value` the way it's sent in a single frame transfer
+fn serialize<T: Struct>(value: T) -> Vec<u8> {
+    let mut serializer = Serializer::from_structure(value);
+    let mut bytes = Vec::new();
+    loop {
+        let mut data = [0u8; 8];
+        let mut buffer = SerializationBuffer::with_empty_buffer(&mut data);
+        if let SerializationResult::Finished = serializer.serialize(&mut buffer) {
+            let length = buffer.bit_length().div_ceil(8);
+            bytes.extend_from_slice(&buffer.data[0..length]);
+            return bytes;
+        } else {
+            bytes.extend_from_slice(buffer.data);
+        }
+    }
+}
+
+/// A transport frame with `N` bytes of data
+struct TestFrame<const N: usize> {
+    id: TransferFrameID,
+    length: usize,
+    data: [u8; N],
+}
+
+impl<const N: usize> TransferFrame for TestFrame<N> {
+    const MAX_DATA_LENGTH: usize = N;
+
+    fn new(id: TransferFrameID) -> Self {
+        TestFrame{id, length: 0, data: [0; N]}
+    }
+
+    fn id(&self) -> TransferFrameID {
+        self.id
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data[0..self.length]
+    }
+
+    fn data_as_mut(&mut self) -> &mut [u8] {
+        &mut self.data[0..self.length]
+    }
+
+    fn set_data_length(&mut self, length: usize) {
+        assert!(length <= N);
+        self.length = length;
+    }
+}
+
+impl<T: Struct + for<'a> Arbitrary<'a>> ArbitraryField for T {
+    fn arbitrary_field(u: &mut Unstructured) -> Result<Self> {
+        T::arbitrary(u)
+    }
+}
+
+macro_rules! impl_native{
+    ($($type:ident),*) => {$(
+        impl ArbitraryField for $type {
+            fn arbitrary_field(u: &mut Unstructured) -> Result<Self> {
+                $type::arbitrary(u)
+            }
+        }
+    )*};
+}
+
+impl_native!(bool, u8, u16, u32, u64, i8, i16, i32, i64);
+
+macro_rules! impl_float{
+    ($($type:ident),*) => {$(
+        /// NaN is always `NAN`, which is what deserializing turns every NaN into
+        impl ArbitraryField for $type {
+            fn arbitrary_field(u: &mut Unstructured) -> Result<Self> {
+                let value = $type::arbitrary(u)?;
+                Ok(if value.is_nan() { $type::NAN } else { value })
+            }
+        }
+    )*};
+}
+
+impl_float!(f32, f64);
+
+/// Any bit pattern, including infinities and NaN
+impl ArbitraryField for f16 {
+    fn arbitrary_field(u: &mut Unstructured) -> Result<Self> {
+        Ok(f16::from_bits(u16::arbitrary(u)?))
+    }
+}
+
+impl<T: ArbitraryField> ArbitraryField for Option<T> {
+    fn arbitrary_field(u: &mut Unstructured) -> Result<Self> {
+        if bool::arbitrary(u)? {
+            Ok(Some(T::arbitrary_field(u)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use types::*;
+
+    /// Pseudo-random bytes for `Unstructured`, the same for every run
+    fn random_data(seed: u64, length: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..length).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        }).collect()
+    }
+
+    #[derive(UavcanStruct)]
+    #[FullName = "test.Empty"]
+    struct Empty {}
+
+    #[derive(UavcanStruct)]
+    #[FullName = "test.Value"]
+    enum Value {
+        Empty(Empty),
+        Integer(i13),
+        Real(f16),
+        Double(f64),
+        Text(Dynamic<[u8; 12]>),
+    }
+
+    #[derive(UavcanStruct)]
+    #[FullName = "test.Inner"]
+    struct Inner {
+        flags: BitArray<[bool; 11]>,
+        _v0: void3,
+        values: Dynamic<[u5; 7]>,
+    }
+
+    // every field type, with a union and a tail array optimized array of composite types last
+    #[derive(UavcanStruct)]
+    #[FullName = "test.Outer"]
+    struct Outer {
+        a: u2,
+        b: [i7; 3],
+        c: Option<f16>,
+        d: DynamicBitArray<[bool; 20]>,
+        e: f64,
+        f: bool,
+        g: Value,
+        h: Dynamic<[Inner; 5]>,
+    }
+
+    macro_rules! impl_arbitrary{
+        ($type:ident {}) => {
+            impl<'a> Arbitrary<'a> for $type {
+                fn arbitrary(_: &mut Unstructured<'a>) -> Result<Self> {
+                    Ok($type{})
+                }
+            }
+        };
+        ($type:ident {$($field:ident),*}) => {
+            impl<'a> Arbitrary<'a> for $type {
+                fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+                    Ok($type{$($field: arbitrary(u)?),*})
+                }
+            }
+        };
+    }
+
+    impl_arbitrary!(Empty {});
+    impl_arbitrary!(Inner {flags, _v0, values});
+    impl_arbitrary!(Outer {a, b, c, d, e, f, g, h});
+
+    impl<'a> Arbitrary<'a> for Value {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            Ok(match u.int_in_range(0..=4)? {
+                0 => Value::Empty(arbitrary(u)?),
+                1 => Value::Integer(arbitrary(u)?),
+                2 => Value::Real(arbitrary(u)?),
+                3 => Value::Double(arbitrary(u)?),
+                _ => Value::Text(arbitrary(u)?),
+            })
+        }
+    }
+
+    #[test]
+    fn primitive_fields() {
+        for seed in 0..100 {
+            let data = random_data(seed, 64);
+            let mut u = Unstructured::new(&data);
+            let value: u2 = arbitrary(&mut u).unwrap();
+            assert!(u8::from(value) <= 3);
+            let value: i3 = arbitrary(&mut u).unwrap();
+            assert!(i8::from(value) >= -4 && i8::from(value) <= 3);
+            let array: Dynamic<[u2; 3]> = arbitrary(&mut u).unwrap();
+            assert!(array.length() <= 3);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for seed in 0..500 {
+            let data = random_data(seed, 512);
+            check_round_trip::<Outer>(&mut Unstructured::new(&data)).unwrap();
+            check_round_trip::<Value>(&mut Unstructured::new(&data)).unwrap();
+        }
+    }
+}
@@ -1,35 +1,105 @@
+//! The checksums used by Uavcan
+//!
+//! `TransferCRC` (CRC-16-CCITT) protects multi frame transfers, while `SignatureCRC` (CRC-64-WE)
+//! is used for computing DSDL signatures and data type signatures.
+//!
+//! Both checksums are table driven. The default tables have 256 entries, which means
+//! 512 bytes for `TransferCRC` and 2 KiB for `SignatureCRC`. For highly resource constrained
+//! systems the feature `small-crc-tables` will replace these with 16 entry tables
+//! (processing a nibble at the time), trading some speed for a smaller footprint.
+
+#[cfg(not(feature="small-crc-tables"))]
+const TABLE_INDEX_BITS: usize = 8;
+
+#[cfg(feature="small-crc-tables")]
+const TABLE_INDEX_BITS: usize = 4;
+
+const TABLE_SIZE: usize = 1 << TABLE_INDEX_BITS;
+const TABLE_INDEX_MASK: u8 = (TABLE_SIZE - 1) as u8;
+
+const CRC16_CCITT_POLY: u16 = 0x1021;
+const CRC64_WE_POLY: u64 = 0x42F0_E1EB_A9EA_3693;
+const CRC64_WE_MASK: u64 = 0xFFFF_FFFF_FFFF_FFFF;
+
+const CRC16_TABLE: [u16; TABLE_SIZE] = crc16_table();
+const CRC64_TABLE: [u64; TABLE_SIZE] = crc64_table();
+
+const fn crc16_table() -> [u16; TABLE_SIZE] {
+    let mut table = [0u16; TABLE_SIZE];
+    let mut i = 0;
+    while i < TABLE_SIZE {
+        let mut value = (i as u16) << (16 - TABLE_INDEX_BITS);
+        let mut bit = 0;
+        while bit < TABLE_INDEX_BITS {
+            value = if value & 0x8000 != 0 { (value << 1) ^ CRC16_CCITT_POLY } else { value << 1 };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+const fn crc64_table() -> [u64; TABLE_SIZE] {
+    let mut table = [0u64; TABLE_SIZE];
+    let mut i = 0;
+    while i < TABLE_SIZE {
+        let mut value = (i as u64) << (64 - TABLE_INDEX_BITS);
+        let mut bit = 0;
+        while bit < TABLE_INDEX_BITS {
+            value = if value & 0x8000_0000_0000_0000 != 0 { (value << 1) ^ CRC64_WE_POLY } else { value << 1 };
+            bit += 1;
+        }
+        table[i] = value;
+        i += 1;
+    }
+    table
+}
+
+
+/// The CRC-16-CCITT used for multi frame transfers
+///
+/// The CRC is initialized with `0xffff` and the data type signature before any payload is added.
+///
+/// # Examples
+/// ```
+/// use uavcan::crc::TransferCRC;
+///
+/// let mut crc = TransferCRC::from_signature(0xd654a48e0c049d75);
+/// crc.add(&[1, 2, 3]);
+///
+/// let value: u16 = crc.into();
+/// ```
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct TransferCRC(u16);
 
 impl TransferCRC {
-    pub fn from_signature(data_type_signature: u64) -> TransferCRC {
+    /// Creates a new `TransferCRC` with the data type signature added (little endian) as the first bytes
+    pub const fn from_signature(data_type_signature: u64) -> TransferCRC {
         let mut crc = TransferCRC(0xffff);
-        for i in 0..8 {
-            crc.add_byte(&((data_type_signature >> 8*i) as u8));
+        let mut i = 0;
+        while i < 8 {
+            crc.add_byte((data_type_signature >> (8*i)) as u8);
+            i += 1;
         }
         crc
     }
-    
-    fn add_byte(&mut self, data: &u8) {
-        match self {
-            &mut TransferCRC(ref mut value) => {
-        
-                *value ^= (*data as u16) << 8;
-                
-                for _bit in 0..8 {
-                    if (*value & 0x8000) != 0 {
-                        *value = (*value << 1) ^ 0x1021;
-                    } else {
-                        *value <<= 1;
-                    }
-                }
-            },
+
+    const fn add_byte(&mut self, data: u8) {
+        let mut shift = 8;
+        while shift > 0 {
+            shift -= TABLE_INDEX_BITS;
+            let index = ((self.0 >> (16 - TABLE_INDEX_BITS)) as u8 ^ (data >> shift)) & TABLE_INDEX_MASK;
+            self.0 = (self.0 << TABLE_INDEX_BITS) ^ CRC16_TABLE[index as usize];
         }
     }
-    
-    pub fn add(&mut self, data: &[u8]) {
-        for b in data {
-            self.add_byte(b);
+
+    /// Add data to the CRC
+    pub const fn add(&mut self, data: &[u8]) {
+        let mut i = 0;
+        while i < data.len() {
+            self.add_byte(data[i]);
+            i += 1;
         }
     }
 }
@@ -47,15 +117,125 @@ impl From<u16> for TransferCRC {
     }
 }
 
+
+/// The CRC-64-WE used for DSDL signatures and data type signatures
+///
+/// The DSDL signature of a type is the `SignatureCRC` of its normalized DSDL definition.
+/// The data type signature is found by extending the DSDL signature with the data type signatures of all nested types.
+///
+/// All methods are `const fn` to allow signatures to be computed at compile time.
+///
+/// # Examples
+/// ```
+/// use uavcan::crc::SignatureCRC;
+///
+/// // The DSDL signature of `uavcan.protocol.debug.LogMessage`
+/// let dsdl_signature = 0xe9862b78d38762ba;
+///
+/// // Extend with the data type signature of the nested `uavcan.protocol.debug.LogLevel`
+/// let mut crc = SignatureCRC::extend(dsdl_signature);
+/// crc.add_nested_signature(0x711bf141af572346);
+///
+/// assert_eq!(crc.value(), 0xd654a48e0c049d75);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SignatureCRC(u64);
+
+impl SignatureCRC {
+    /// Creates a new `SignatureCRC` without any data added
+    pub const fn new() -> SignatureCRC {
+        SignatureCRC(CRC64_WE_MASK)
+    }
+
+    /// Creates a `SignatureCRC` that continues from an already computed signature
+    pub const fn extend(signature: u64) -> SignatureCRC {
+        SignatureCRC(signature ^ CRC64_WE_MASK)
+    }
+
+    const fn add_byte(&mut self, data: u8) {
+        let mut shift = 8;
+        while shift > 0 {
+            shift -= TABLE_INDEX_BITS;
+            let index = ((self.0 >> (64 - TABLE_INDEX_BITS)) as u8 ^ (data >> shift)) & TABLE_INDEX_MASK;
+            self.0 = (self.0 << TABLE_INDEX_BITS) ^ CRC64_TABLE[index as usize];
+        }
+    }
+
+    /// Add data to the CRC
+    pub const fn add(&mut self, data: &[u8]) {
+        let mut i = 0;
+        while i < data.len() {
+            self.add_byte(data[i]);
+            i += 1;
+        }
+    }
+
+    /// Extend the signature with the data type signature of a nested type
+    ///
+    /// This follows the procedure from the specification: first the nested data type signature
+    /// and then the current signature value are added as little endian bytes.
+    pub const fn add_nested_signature(&mut self, data_type_signature: u64) {
+        let current = self.value();
+        self.add(&data_type_signature.to_le_bytes());
+        self.add(&current.to_le_bytes());
+    }
+
+    /// Returns the current value of the signature
+    pub const fn value(&self) -> u64 {
+        self.0 ^ CRC64_WE_MASK
+    }
+}
+
+impl Default for SignatureCRC {
+    fn default() -> Self {
+        SignatureCRC::new()
+    }
+}
+
+impl From<SignatureCRC> for u64 {
+    fn from(crc: SignatureCRC) -> Self {
+        crc.value()
+    }
+}
+
+/// Computes the DSDL signature from the full data type name and the parts of the DSDL definition
+///
+/// The definition parts must be normalized and every line must end with a line break (like `Struct::DSDL_DEFINITION_PARTS`).
+/// This function is only exposed so `Struct` can be derived.
+#[doc(hidden)]
+pub const fn dsdl_signature(full_name: &str, definition: &[&str]) -> u64 {
+    let mut crc = SignatureCRC::new();
+    crc.add(full_name.as_bytes());
+
+    // The normalized definition separates the lines by line breaks, but has no trailing line break
+    let mut i = 0;
+    while i < definition.len() {
+        let part = definition[i].as_bytes();
+        if i == 0 {
+            crc.add(b"\n");
+        }
+        if i == definition.len() - 1 && !part.is_empty() && part[part.len() - 1] == b'\n' {
+            crc.add(part.split_at(part.len() - 1).0);
+        } else {
+            crc.add(part);
+        }
+        i += 1;
+    }
+
+    crc.value()
+}
+
 #[cfg(test)]
 mod tests {
 
     use crc::TransferCRC;
-    
+    use crc::SignatureCRC;
+    use crc::dsdl_signature;
+
     #[test]
     fn test_add_byte() {
         let mut crc = TransferCRC(0xffff);
-        crc.add_byte(&1);
+        crc.add_byte(1);
         assert_eq!(u16::from(crc), 0xf1d1);
     }
 
@@ -71,4 +251,59 @@ mod tests {
         let crc = TransferCRC::from_signature(0xd654a48e0c049d75);
         assert_eq!(u16::from(crc), 0x4570);
     }
+
+    #[test]
+    fn transfer_crc_check_value() {
+        let mut crc = TransferCRC(0xffff);
+        crc.add(b"123456789");
+        assert_eq!(u16::from(crc), 0x29b1);
+    }
+
+    #[test]
+    fn signature_crc_check_value() {
+        let mut crc = SignatureCRC::new();
+        crc.add(b"123456789");
+        assert_eq!(crc.value(), 0x62ec59e3f1a4f00a);
+    }
+
+    #[test]
+    fn node_status_dsdl_signature() {
+        let mut crc = SignatureCRC::new();
+        crc.add(b"uavcan.protocol.NodeStatus\n\
+                  saturated uint32 uptime_sec\n\
+                  saturated uint2 health\n\
+                  saturated uint3 mode\n\
+                  saturated uint3 sub_mode\n\
+                  saturated uint16 vendor_specific_status_code");
+        assert_eq!(crc.value(), 0x0f0868d0c1a7c6f1);
+    }
+
+    #[test]
+    fn dsdl_signature_from_parts() {
+        const SIGNATURE: u64 = dsdl_signature(
+            "uavcan.protocol.debug.LogMessage",
+            &["uavcan.protocol.debug.LogLevel", " level\n", "saturated uint8[<=31] source\n", "saturated uint8[<=90] text\n"],
+        );
+        assert_eq!(SIGNATURE, 0xe9862b78d38762ba);
+    }
+
+    #[test]
+    fn extend_signature() {
+        let mut crc = SignatureCRC::new();
+        crc.add(b"123");
+
+        let mut extended = SignatureCRC::extend(crc.value());
+        extended.add(b"456789");
+        assert_eq!(extended.value(), 0x62ec59e3f1a4f00a);
+    }
+
+    #[test]
+    fn data_type_signature() {
+        const SIGNATURE: u64 = {
+            let mut crc = SignatureCRC::extend(0xe9862b78d38762ba);
+            crc.add_nested_signature(0x711bf141af572346);
+            crc.value()
+        };
+        assert_eq!(SIGNATURE, 0xd654a48e0c049d75);
+    }
 }
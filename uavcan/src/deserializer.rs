@@ -1,5 +1,3 @@
-use lib::core::mem;
-
 pub use serializer::SerializationBuffer as DeserializationBuffer;
 
 use {
@@ -22,11 +20,7 @@ pub struct Deserializer<T: Struct> {
 
 impl<T: Struct> Deserializer<T> {
     pub fn new() -> Deserializer<T> {
-        let structure: T;
-        unsafe {
-            structure = mem::zeroed();
-        };            
-        Deserializer{structure: structure, field_index: 0, bit_index: 0}
+        Deserializer{structure: T::placeholder(), field_index: 0, bit_index: 0}
     }
 
     pub fn deserialize(&mut self, input: &mut [u8]) -> DeserializationResult {
@@ -34,8 +28,13 @@ impl<T: Struct> Deserializer<T> {
         self.structure.deserialize(&mut self.field_index, &mut self.bit_index, true, &mut buffer)
     }
 
-    pub fn into_structure(self) -> Result<T, ()> {
-        Ok(self.structure)    
+    pub fn into_structure(mut self) -> Result<T, ()> {
+        // the transfer has ended, which a tail array optimized field still in progress is told with an empty buffer
+        if self.field_index < T::FLATTENED_FIELDS_NUMBER {
+            let mut buffer = DeserializationBuffer::with_full_buffer(&mut []);
+            self.structure.deserialize(&mut self.field_index, &mut self.bit_index, true, &mut buffer);
+        }
+        Ok(self.structure)
     }
 }
 
@@ -105,6 +104,26 @@ mod tests {
         
     }
 
+    #[test]
+    fn deserialize_overwrites_fields() {
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Message {
+            flag: bool,
+            value: u7,
+            text: Dynamic<[u8; 4]>,
+        }
+
+        let mut message = Message{flag: true, value: u7::new(127), text: Dynamic::<[u8; 4]>::with_data(&[0xff, 0xff])};
+        {
+            let mut input = [0b0000_0001, b'o', b'k'];
+            let mut buffer = DeserializationBuffer::with_full_buffer(&mut input);
+            message.deserialize(&mut 0, &mut 0, true, &mut buffer);
+        }
+
+        assert_eq!(message, Message{flag: false, value: u7::new(1), text: Dynamic::<[u8; 4]>::with_data(b"ok")});
+    }
+
     #[test]
     fn deserialize_dynamic_array() {
 
@@ -130,6 +149,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deserialize_empty_dynamic_array() {
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct TestMessage {
+            pad: u4,
+            text: Dynamic<[u8; 12]>,
+            value: u8,
+        }
+
+        let mut deserializer: Deserializer<TestMessage> = Deserializer::new();
+
+        deserializer.deserialize(&mut [0b0001_0000, 0xff]);
+
+        assert_eq!(deserializer.into_structure().unwrap(),
+                   TestMessage{
+                       pad: u4::new(1),
+                       text: Dynamic::<[u8; 12]>::new(),
+                       value: 0xff,
+                   }
+        );
+    }
+
     #[test]
     fn tail_array_optimization_struct() {
         #[derive(Debug, PartialEq, UavcanStruct, Clone)]
@@ -182,6 +224,139 @@ mod tests {
     }
 
 
+    #[test]
+    fn optional_fields() {
+        use serializer::{Serializer, SerializationBuffer};
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Message {
+            a: Option<u8>,
+            b: Option<u16>,
+        }
+
+        let round_trip = |message: Message| {
+            let mut serializer: Serializer<Message> = Serializer::from_structure(message);
+            let mut array: [u8; 4] = [0; 4];
+            let length = {
+                let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
+                serializer.serialize(&mut buffer);
+                buffer.bit_length().div_ceil(8)
+            };
+            let mut deserializer: Deserializer<Message> = Deserializer::new();
+            deserializer.deserialize(&mut array[..length]);
+            let whole = deserializer.into_structure().unwrap();
+
+            // like a transfer split into frames
+            let split = length / 2;
+            let mut deserializer: Deserializer<Message> = Deserializer::new();
+            deserializer.deserialize(&mut array[..split]);
+            deserializer.deserialize(&mut array[split..length]);
+            assert_eq!(deserializer.into_structure().unwrap(), whole);
+
+            whole
+        };
+
+        for message in [
+            Message{a: Some(0xff), b: Some(0x1234)},
+            Message{a: None, b: Some(0x1234)},
+            Message{a: Some(0xff), b: None},
+            Message{a: None, b: None},
+        ].iter() {
+            assert_eq!(&round_trip(message.clone()), message);
+        }
+    }
+
+    #[test]
+    fn unions() {
+        use serializer::{Serializer, SerializationBuffer};
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Empty {}
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        enum Value {
+            Empty(Empty),
+            Integer(u8),
+            Text(Dynamic<[u8; 4]>),
+        }
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Message {
+            a: Value,
+            b: Value,
+        }
+
+        let round_trip = |message: Message| {
+            let mut serializer: Serializer<Message> = Serializer::from_structure(message);
+            let mut array: [u8; 8] = [0; 8];
+            let length = {
+                let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
+                serializer.serialize(&mut buffer);
+                buffer.bit_length().div_ceil(8)
+            };
+            let mut deserializer: Deserializer<Message> = Deserializer::new();
+            deserializer.deserialize(&mut array[..length]);
+            let whole = deserializer.into_structure().unwrap();
+
+            // split in every byte, so the tags are split as well
+            let mut deserializer: Deserializer<Message> = Deserializer::new();
+            for i in 0..length {
+                deserializer.deserialize(&mut array[i..i+1]);
+            }
+            assert_eq!(deserializer.into_structure().unwrap(), whole);
+
+            whole
+        };
+
+        for message in [
+            Message{a: Value::Integer(10), b: Value::Text(Dynamic::<[u8; 4]>::with_data(&[1, 2]))},
+            Message{a: Value::Text(Dynamic::<[u8; 4]>::with_data(&[3])), b: Value::Integer(0xff)},
+            Message{a: Value::Empty(Empty{}), b: Value::Empty(Empty{})},
+            Message{a: Value::Integer(0), b: Value::Text(Dynamic::<[u8; 4]>::new())},
+        ].iter() {
+            assert_eq!(&round_trip(message.clone()), message);
+        }
+    }
+
+    #[test]
+    fn bit_arrays() {
+        use serializer::{Serializer, SerializationBuffer};
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Message {
+            a: u3,
+            b: BitArray<[bool; 70]>,
+            c: DynamicBitArray<[bool; 100]>,
+            d: u8,
+        }
+
+        let mut bits = [false; 100];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = i % 3 == 0 || i % 7 == 0;
+        }
+        let message = Message{
+            a: u3::new(5),
+            b: BitArray::from_native(&bits[..70]).unwrap(),
+            c: DynamicBitArray::with_data(&bits[..90]),
+            d: 0xa5,
+        };
+
+        let mut serializer: Serializer<Message> = Serializer::from_structure(message.clone());
+        let mut array = [0u8; 25];
+        let length = {
+            let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
+            serializer.serialize(&mut buffer);
+            buffer.bit_length().div_ceil(8)
+        };
+
+        // like a transfer split into frames, the bits are split between buffers
+        let mut deserializer: Deserializer<Message> = Deserializer::new();
+        for chunk in array[..length].chunks_mut(7) {
+            deserializer.deserialize(chunk);
+        }
+        assert_eq!(deserializer.into_structure().unwrap(), message);
+    }
+
     #[test]
     fn dynamic_array_of_structs() {
         #[derive(Debug, PartialEq, Clone, UavcanStruct)]
@@ -212,7 +387,7 @@ mod tests {
         actuator_message.commands.push(actuator_command);
         
         let mut deserializer: Deserializer<ArrayCommand> = Deserializer::new();
-        deserializer.deserialize(&mut [0, 3, f16::from_f32(1.0).as_bits() as u8, (f16::from_f32(1.0).as_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).as_bits() as u8), (f16::from_f32(1.0).as_bits() >> 8) as u8]);
+        deserializer.deserialize(&mut [0, 3, f16::from_f32(1.0).to_bits() as u8, (f16::from_f32(1.0).to_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).to_bits() as u8), (f16::from_f32(1.0).to_bits() >> 8) as u8]);
         
         assert_eq!(deserializer.into_structure().unwrap(), actuator_message);                   
 
@@ -246,7 +421,7 @@ mod tests {
         };
         
         let mut deserializer: Deserializer<ArrayCommand> = Deserializer::new();
-        deserializer.deserialize(&mut [0, 3, f16::from_f32(1.0).as_bits() as u8, (f16::from_f32(1.0).as_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).as_bits() as u8), (f16::from_f32(1.0).as_bits() >> 8) as u8]);
+        deserializer.deserialize(&mut [0, 3, f16::from_f32(1.0).to_bits() as u8, (f16::from_f32(1.0).to_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).to_bits() as u8), (f16::from_f32(1.0).to_bits() >> 8) as u8]);
         assert_eq!(deserializer.into_structure().unwrap(), actuator_message);                   
         
     }
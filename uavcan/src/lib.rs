@@ -25,6 +25,12 @@ extern crate bit_field;
 extern crate embedded_types;
 extern crate ux;
 extern crate half;
+#[cfg(feature="serde")]
+extern crate serde;
+#[cfg(all(test, feature="serde"))]
+extern crate serde_test;
+#[cfg(feature="arbitrary")]
+extern crate arbitrary;
 
 mod lib {
     pub mod core {
@@ -46,17 +52,30 @@ mod uavcan {
 #[doc(hidden)]
 pub use uavcan_derive::*;
 
+/// Re-exported so code generated by the DSDL compiler can implement `TryFrom`
+/// without depending on the edition or `std` support of the crate it's included in.
+#[doc(hidden)]
+pub use lib::core::convert::TryFrom;
+
 pub mod transfer;
 pub mod types;
-mod crc;
+pub mod crc;
 mod deserializer;
 mod frame_assembler;
 mod serializer;
 mod frame_disassembler;
 pub mod node;
+pub mod reflect;
+pub mod text;
+#[cfg(feature="serde")]
+pub mod serde_field;
+#[cfg(feature="arbitrary")]
+pub mod arbitrary_field;
 
 use bit_field::BitField;
 
+use lib::core::fmt;
+
 use transfer::TransferFrameID;
 
 
@@ -123,9 +142,15 @@ pub trait Serializable {
     ///
     /// assert_eq!(Foo::BIT_LENGTH_MIN, 2 + 62*4 + 2);
     ///
-    /// // Enums have the minimum of all variants `MIN_BIT_LENGTH` as their `MIN_BIT_LENGTH`.
-    /// // (But this is not implemented yet)
+    /// // Enums have the bits of the tag and the minimum of all variants `MIN_BIT_LENGTH` as their `MIN_BIT_LENGTH`.
+    /// #[derive(UavcanStruct)]
+    /// enum Bar {
+    ///     V1(u2),
+    ///     V2([i62; 4]),
+    ///     V3(Dynamic<[void11; 3]>),
+    /// }
     ///
+    /// assert_eq!(Bar::BIT_LENGTH_MIN, 2 + 2);
     /// # }
     /// ```
     const BIT_LENGTH_MIN: usize;
@@ -133,7 +158,7 @@ pub trait Serializable {
     /// Number of primitive fields after flattening of data type.
     ///
     /// Flattening of a struct consists of replacing all structs with its fields.
-    /// Flattening of an enum consists of putting all fields in order
+    /// Flattening of an enum consists of the tag, followed by the fields of the variant with the most fields
     ///
     /// # Examples
     /// ## Flattening of struct
@@ -179,18 +204,207 @@ pub trait Serializable {
     ///
     /// # fn main() {
     /// assert_eq!(InnerEnum::FLATTENED_FIELDS_NUMBER, 2);
-    /// assert_eq!(OuterEnum::FLATTENED_FIELDS_NUMBER, 4);
+    /// assert_eq!(OuterEnum::FLATTENED_FIELDS_NUMBER, 3);
     /// # }
     /// ```
     const FLATTENED_FIELDS_NUMBER: usize;
 
     fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult;
     fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult;
+
+    /// A value to deserialize into, every part of it is overwritten by `deserialize`
+    ///
+    /// Unions select their variant with it when the tag is deserialized, before the value of the variant.
+    ///
+    /// This method has no default, so manual implementations of `Serializable` must add it. A primitive field
+    /// type can return `from_bits(0)` and a composite type the placeholders of its fields.
+    fn placeholder() -> Self where Self: Sized;
 }
 
+/// A composite Uavcan type, this is what a DSDL definition compiles into
+///
+/// When derived, the signatures are computed at compile time from the field types unless they're given explicitly with
+/// `#[DSDLSignature = "0x..."]` and `#[DataTypeSignature = "0x..."]`. The full data type name is set with `#[FullName = "..."]`
+/// (defaults to the name of the type) and fields can be marked with `#[CastMode = "truncated"]`.
+///
+/// Service types must have their signatures given explicitly, as the request and response are derived separately.
+///
+/// Tuple structs name their fields `field0`, `field1`, ... in the DSDL definition. Generic structs and type aliases are
+/// supported as every field type is described through its [`DsdlField`](trait.DsdlField.html) implementation, and a field
+/// type without one is reported as a compile error pointing at the field. Types without generic parameters also get the
+/// normalized definition as the associated const `DSDL_DEFINITION: &'static str`, which is what
+/// `dsdl_compiler::export` writes to `.uavcan` files.
+///
+/// Generic types don't get `DSDL_DEFINITION`, as the length of their definition depends on the type parameters and stable
+/// Rust can't concatenate it at compile time. Their definition is still available from `DSDL_DEFINITION_PARTS` and
+/// `write_dsdl_definition`, which is what `dsdl_compiler::export` uses.
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate uavcan;
+/// #[derive(UavcanStruct)]
+/// struct Generic<T> {
+///     value: T,
+/// }
+/// # fn main() {
+/// let _ = Generic::<u8>::DSDL_DEFINITION;
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate uavcan;
+/// #[derive(UavcanStruct)]
+/// struct Unsupported {
+///     text: String,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// # Examples
+/// ```
+/// # #[macro_use]
+/// # extern crate uavcan;
+/// # use uavcan::Struct;
+/// # use uavcan::types::*;
+/// #[derive(UavcanStruct)]
+/// #[FullName = "uavcan.protocol.debug.LogLevel"]
+/// struct LogLevel {
+///     value: u3,
+/// }
+///
+/// #[derive(UavcanStruct)]
+/// #[FullName = "uavcan.protocol.debug.LogMessage"]
+/// struct LogMessage {
+///     level: LogLevel,
+///     source: Dynamic<[u8; 31]>,
+///     text: Dynamic<[u8; 90]>,
+/// }
+///
+/// # fn main() {
+/// assert_eq!(LogMessage::DSDL_SIGNATURE, 0xe9862b78d38762ba);
+/// assert_eq!(LogMessage::DATA_TYPE_SIGNATURE, 0xd654a48e0c049d75);
+///
+/// assert_eq!(LogMessage::DSDL_DEFINITION, "uavcan.protocol.debug.LogLevel level\n\
+///                                         saturated uint8[<=31] source\n\
+///                                         saturated uint8[<=90] text\n");
+///
+/// let mut definition = String::new();
+/// LogMessage::write_dsdl_definition(&mut definition).unwrap();
+/// assert_eq!(definition, LogMessage::DSDL_DEFINITION);
+/// # }
+/// ```
 pub trait Struct: Sized + Serializable {
+    /// The full data type name, including namespace (e.g. `uavcan.protocol.NodeStatus`)
+    const FULL_NAME: &'static str;
+
+    /// The normalized DSDL definition (the content of the `.uavcan` file) split into parts
+    ///
+    /// The names of nested types are parts on their own, concatenating all parts gives the definition.
+    /// When derived for a type without type parameters, the concatenated definition is also available as the associated const `DSDL_DEFINITION`.
+    const DSDL_DEFINITION_PARTS: &'static [&'static str];
+
+    /// The CRC-64-WE of the normalized DSDL definition
     const DSDL_SIGNATURE: u64;
+
+    /// The DSDL signature extended with the data type signatures of all nested types
     const DATA_TYPE_SIGNATURE: u64;
+
+    /// Writes the equivalent `.uavcan` text of the type
+    fn write_dsdl_definition<W: fmt::Write>(writer: &mut W) -> fmt::Result {
+        for part in Self::DSDL_DEFINITION_PARTS {
+            writer.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// How the type of a field is written in a DSDL definition
+///
+/// `#[derive(UavcanStruct)]` builds the definition and the data type signature of a type from the `DsdlField` of its fields,
+/// so fields can have any type implementing it (including type aliases and type parameters). It's implemented for
+/// the primitive types, arrays, `Option`, the bit arrays and every `Struct`.
+///
+/// # Examples
+/// ```
+/// # #[macro_use]
+/// # extern crate uavcan;
+/// # use uavcan::DsdlField;
+/// # use uavcan::types::*;
+/// # fn main() {
+/// assert_eq!(<Dynamic<[u2; 4]> as DsdlField>::DSDL_TYPE, "uint2");
+/// assert_eq!(<Dynamic<[u2; 4]> as DsdlField>::DSDL_ARRAY, "[<=4]");
+/// assert!(<void3 as DsdlField>::VOID);
+/// # }
+/// ```
+pub trait DsdlField {
+    /// The DSDL type of the field, or of its elements for arrays, like `uint2`, `void3` or the full name of a composite type
+    const DSDL_TYPE: &'static str;
+
+    /// The array part of the field, like `[4]` or `[<=4]`, empty if the field is not an array
+    const DSDL_ARRAY: &'static str = "";
+
+    /// True if the type (or element type) is primitive, then the field has a cast mode unless it's void
+    const PRIMITIVE: bool;
+
+    /// True if the type (or element type) is void, then the field has no name
+    const VOID: bool = false;
+
+    /// The data type signature of the composite type (or element type), which extends the signature of the containing type
+    const NESTED_SIGNATURE: Option<u64> = None;
+}
+
+impl<T: Struct> DsdlField for T {
+    const DSDL_TYPE: &'static str = T::FULL_NAME;
+    const PRIMITIVE: bool = false;
+    const NESTED_SIGNATURE: Option<u64> = Some(T::DATA_TYPE_SIGNATURE);
+}
+
+/// Returns the length of the definition made up of `parts`
+///
+/// This function is only exposed so `Struct` can be derived.
+#[doc(hidden)]
+pub const fn dsdl_definition_length(parts: &[&str]) -> usize {
+    let mut length = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        length += parts[i].len();
+        i += 1;
+    }
+    length
+}
+
+/// Concatenates the definition `parts` at compile time, `N` must be their total length
+///
+/// This function is only exposed so `Struct` can be derived.
+#[doc(hidden)]
+pub const fn dsdl_definition_bytes<const N: usize>(parts: &[&str]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let mut length = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let part = parts[i].as_bytes();
+        let mut j = 0;
+        while j < part.len() {
+            bytes[length] = part[j];
+            length += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    assert!(length == N, "The length of the definition must match the parts");
+    bytes
+}
+
+/// Converts the concatenated definition back into a string
+///
+/// This function is only exposed so `Struct` can be derived.
+#[doc(hidden)]
+pub const fn dsdl_definition_str(bytes: &'static [u8]) -> &'static str {
+    match lib::core::str::from_utf8(bytes) {
+        Ok(definition) => definition,
+        Err(_) => panic!("The parts of the definition are strings, so their concatenation is valid UTF-8"),
+    }
 }
 
 pub trait Message: Struct {
@@ -311,6 +525,7 @@ impl<T: Struct> Frame<T> {
 mod tests {
 
     use *;
+    use types::*;
 
     // Implementing some types common for several tests
     
@@ -346,6 +561,148 @@ mod tests {
         }
     }
 
-    
-    
+    #[test]
+    fn derive_signature_with_void() {
+        #[derive(UavcanStruct)]
+        #[FullName = "uavcan.equipment.actuator.Status"]
+        struct Status {
+            actuator_id: u8,
+            position: f16,
+            force: f16,
+            speed: f16,
+            _v0: void1,
+            power_rating_pct: u7,
+        }
+
+        assert_eq!(Status::DSDL_SIGNATURE, 0x5e9bba44faf1ea04);
+        assert_eq!(Status::DATA_TYPE_SIGNATURE, 0x5e9bba44faf1ea04);
+    }
+
+    #[test]
+    fn derive_signature_union() {
+        #[derive(UavcanStruct)]
+        #[FullName = "uavcan.protocol.param.Empty"]
+        struct Empty {}
+
+        #[derive(UavcanStruct)]
+        #[FullName = "uavcan.protocol.param.Value"]
+        #[allow(dead_code)]
+        enum ParamValue {
+            Empty(Empty),
+            IntegerValue(i64),
+            RealValue(f32),
+            BooleanValue(u8),
+            StringValue(Dynamic<[u8; 128]>),
+        }
+
+        assert_eq!(ParamValue::DSDL_SIGNATURE, 0xc3d96f448f2b00a1);
+    }
+
+    #[test]
+    #[cfg(feature="std")]
+    fn derive_dsdl_definition() {
+        #[derive(UavcanStruct)]
+        #[FullName = "test.TruncatedCommand"]
+        struct TruncatedCommand {
+            #[CastMode = "truncated"]
+            cmd: Dynamic<[i14; 20]>,
+        }
+
+        #[derive(UavcanStruct)]
+        struct Padded {
+            _v0: void5,
+            commands: [TruncatedCommand; 2],
+        }
+
+        let mut definition = String::new();
+        Padded::write_dsdl_definition(&mut definition).unwrap();
+        assert_eq!(definition, "void5\n\
+                                test.TruncatedCommand[2] commands\n");
+
+        let mut definition = String::new();
+        TruncatedCommand::write_dsdl_definition(&mut definition).unwrap();
+        assert_eq!(definition, "truncated int14[<=20] cmd\n");
+    }
+
+    #[test]
+    fn derive_explicit_signature() {
+        #[derive(UavcanStruct)]
+        #[DSDLSignature = "0x1234"]
+        struct Explicit {
+            value: u8,
+        }
+
+        assert_eq!(Explicit::DSDL_SIGNATURE, 0x1234);
+        assert_eq!(Explicit::DATA_TYPE_SIGNATURE, 0x1234);
+    }
+
+    #[test]
+    #[cfg(feature="std")]
+    fn derive_tuple_struct() {
+        #[derive(UavcanStruct)]
+        #[FullName = "test.Pair"]
+        struct Pair(u2, #[CastMode = "truncated"] Dynamic<[f16; 3]>);
+
+        let mut definition = String::new();
+        Pair::write_dsdl_definition(&mut definition).unwrap();
+        assert_eq!(definition, "saturated uint2 field0\ntruncated float16[<=3] field1\n");
+
+        let mut buffer = [0u8; 8];
+        let mut serializer = serializer::Serializer::from_structure(Pair(u2::new(3), Dynamic::<[f16; 3]>::with_data(&[f16::from_f32(1.0)])));
+        serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut buffer));
+        assert_eq!(buffer[0] >> 6, 3);
+    }
+
+    #[test]
+    #[cfg(feature="std")]
+    fn derive_generic_dsdl_definition() {
+        #[derive(UavcanStruct)]
+        #[FullName = "test.Pair"]
+        struct Pair<T> {
+            first: T,
+            second: T,
+        }
+
+        #[derive(UavcanStruct)]
+        #[FullName = "test.Pair"]
+        struct Concrete {
+            first: u2,
+            second: u2,
+        }
+
+        // generic types don't get `DSDL_DEFINITION`, but write the same definition
+        let mut definition = String::new();
+        <Pair<u2>>::write_dsdl_definition(&mut definition).unwrap();
+        assert_eq!(definition, Concrete::DSDL_DEFINITION);
+    }
+
+    #[test]
+    fn derive_generic_struct() {
+        // type aliases are resolved by the compiler, like the type parameters
+        type Health = u2;
+
+        #[derive(UavcanStruct)]
+        #[FullName = "test.Generic"]
+        struct Generic<T, U> where U: Copy {
+            health: Health,
+            value: T,
+            values: [U; 2],
+        }
+
+        #[derive(UavcanStruct)]
+        #[FullName = "test.Generic"]
+        struct Concrete {
+            health: u2,
+            value: Dynamic<[u8; 4]>,
+            values: [i3; 2],
+        }
+
+        assert_eq!(<Generic<Dynamic<[u8; 4]>, i3>>::DSDL_SIGNATURE, Concrete::DSDL_SIGNATURE);
+        assert_eq!(<Generic<Dynamic<[u8; 4]>, i3>>::BIT_LENGTH_MIN, Concrete::BIT_LENGTH_MIN);
+        assert_eq!(<Generic<Concrete, i3> as Struct>::DATA_TYPE_SIGNATURE, {
+            let mut crc = crc::SignatureCRC::extend(<Generic<Concrete, i3>>::DSDL_SIGNATURE);
+            crc.add_nested_signature(Concrete::DATA_TYPE_SIGNATURE);
+            crc.value()
+        });
+    }
 }
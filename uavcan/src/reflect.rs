@@ -0,0 +1,324 @@
+//! Runtime reflection of composite types
+//!
+//! `Reflect` describes the fields and constants of a type as they're defined in DSDL, and walks the fields of a value
+//! with a `Visitor`. This lets pretty printers, CSV exporters and GUIs handle any message without knowing its type.
+//! The DSDL compiler implements `Reflect` for the types it generates when `CompileConfig::reflect` is set.
+//!
+//! # Examples
+//! ```
+//! # #[macro_use]
+//! # extern crate uavcan;
+//! # use uavcan::Struct;
+//! # use uavcan::types::*;
+//! use uavcan::reflect::*;
+//!
+//! #[derive(UavcanStruct)]
+//! #[FullName = "uavcan.protocol.debug.LogLevel"]
+//! struct LogLevel {
+//!     value: u3,
+//! }
+//!
+//! impl Reflect for LogLevel {
+//!     const FIELDS: &'static [FieldInfo] = &[
+//!         FieldInfo{name: "value", ty: "uint3", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+//!     ];
+//!     const CONSTANTS: &'static [ConstantInfo] = &[
+//!         ConstantInfo{name: "ERROR", ty: "uint3", value: Value::Unsigned(3)},
+//!     ];
+//!     const UNION: bool = false;
+//!
+//!     fn visit<V: Visitor>(&self, visitor: &mut V) {
+//!         self.value.visit_field(&Self::FIELDS[0], visitor);
+//!     }
+//! }
+//!
+//! // prints every primitive value as `name = value`
+//! struct Printer(String);
+//!
+//! impl Visitor for Printer {
+//!     fn visit_value(&mut self, field: &'static FieldInfo, value: Value) {
+//!         self.0 += &format!("{} = {}\n", field.name, value);
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut printer = Printer(String::new());
+//! LogLevel{value: u3::new(3)}.visit(&mut printer);
+//! assert_eq!(printer.0, "value = 3\n");
+//! # }
+//! ```
+
+use lib::core::fmt;
+
+use Struct;
+use types::f16;
+
+/// A composite type whose fields and constants can be inspected at runtime
+///
+/// The full name of the type is `Struct::FULL_NAME`.
+pub trait Reflect: Struct {
+    /// The fields in the order they're defined in DSDL, for unions these are the variants
+    const FIELDS: &'static [FieldInfo];
+
+    /// The constants in the order they're defined in DSDL
+    const CONSTANTS: &'static [ConstantInfo];
+
+    /// True if the type is a union, then only the active field is visited
+    const UNION: bool;
+
+    /// Visits the fields of the value in order, descending into nested composite types and arrays
+    fn visit<V: Visitor>(&self, visitor: &mut V);
+}
+
+/// A field as it's defined in DSDL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// The DSDL name of the field, empty for void fields
+    pub name: &'static str,
+
+    /// The DSDL type of the field (or of its elements), like `uint2`, `float16`, `void3` or `uavcan.protocol.NodeStatus`
+    pub ty: &'static str,
+
+    /// Whether the field is an array, and which kind
+    pub array: ArrayKind,
+
+    /// The cast mode of primitive fields, composite and void fields don't have one
+    pub cast_mode: Option<CastMode>,
+}
+
+/// The kind of array a field is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayKind {
+    /// Not an array
+    Scalar,
+
+    /// A static array (`[N]`) of the given length
+    Static(usize),
+
+    /// A dynamic array (`[<=N]`) of the given maximum length, `[<N]` is given as `[<=N-1]`
+    Dynamic(usize),
+}
+
+/// How a value that doesn't fit a primitive field is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastMode {
+    Saturated,
+    Truncated,
+}
+
+/// A constant as it's defined in DSDL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConstantInfo {
+    /// The DSDL name of the constant
+    pub name: &'static str,
+
+    /// The DSDL type of the constant, like `uint2`
+    pub ty: &'static str,
+
+    /// The value of the constant
+    pub value: Value,
+}
+
+/// A primitive value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Bool(bool),
+
+    /// The value of an unsigned integer (`uintN`)
+    Unsigned(u64),
+
+    /// The value of a signed integer (`intN`)
+    Signed(i64),
+
+    /// The value of a float (`floatN`)
+    Float(f64),
+
+    /// A void field, which doesn't carry a value
+    Void,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Unsigned(value) => write!(f, "{}", value),
+            Value::Signed(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Void => write!(f, "void"),
+        }
+    }
+}
+
+/// Walks the fields of a value, used with `Reflect::visit`
+///
+/// Only `visit_value` must be implemented, the other methods mark where arrays and nested composite types start and end.
+pub trait Visitor {
+    /// Visits a primitive field, or an element of an array of primitives
+    fn visit_value(&mut self, field: &'static FieldInfo, value: Value);
+
+    /// Visits an array of `uint8`, which often holds text, by default its elements are visited like any other array
+    fn visit_bytes(&mut self, field: &'static FieldInfo, bytes: &[u8]) {
+        self.enter_array(field, bytes.len());
+        for byte in bytes {
+            self.visit_value(field, Value::Unsigned(u64::from(*byte)));
+        }
+        self.leave_array(field);
+    }
+
+    /// Called before the elements of an array are visited, `length` is the current length of dynamic arrays
+    fn enter_array(&mut self, _field: &'static FieldInfo, _length: usize) {}
+
+    /// Called after the elements of an array are visited
+    fn leave_array(&mut self, _field: &'static FieldInfo) {}
+
+    /// Called before the fields of a nested composite value (or an element of an array of them) are visited
+    fn enter_struct(&mut self, _field: &'static FieldInfo, _full_name: &'static str) {}
+
+    /// Called after the fields of a nested composite value are visited
+    fn leave_struct(&mut self, _field: &'static FieldInfo) {}
+}
+
+/// A field type that can be visited, implemented for the primitive types, arrays and composite types implementing `Reflect`
+pub trait VisitField {
+    fn visit_field<V: Visitor>(&self, field: &'static FieldInfo, visitor: &mut V);
+
+    /// Visits the elements of an array of this type
+    fn visit_elements<V: Visitor>(elements: &[Self], field: &'static FieldInfo, visitor: &mut V) where Self: Sized {
+        visitor.enter_array(field, elements.len());
+        for element in elements {
+            element.visit_field(field, visitor);
+        }
+        visitor.leave_array(field);
+    }
+}
+
+impl<T: Reflect> VisitField for T {
+    fn visit_field<V: Visitor>(&self, field: &'static FieldInfo, visitor: &mut V) {
+        visitor.enter_struct(field, T::FULL_NAME);
+        self.visit(visitor);
+        visitor.leave_struct(field);
+    }
+}
+
+macro_rules! impl_visit_field{
+    ($variant:ident, [$($type:ident),*]) => {$(
+        impl VisitField for $type {
+            fn visit_field<V: Visitor>(&self, field: &'static FieldInfo, visitor: &mut V) {
+                visitor.visit_value(field, Value::$variant((*self).into()));
+            }
+        }
+    )*};
+}
+
+impl_visit_field!(Bool, [bool]);
+impl_visit_field!(Unsigned, [u16, u32, u64]);
+impl_visit_field!(Signed, [i8, i16, i32, i64]);
+impl_visit_field!(Float, [f32, f64]);
+
+impl VisitField for u8 {
+    fn visit_field<V: Visitor>(&self, field: &'static FieldInfo, visitor: &mut V) {
+        visitor.visit_value(field, Value::Unsigned(u64::from(*self)));
+    }
+
+    fn visit_elements<V: Visitor>(elements: &[Self], field: &'static FieldInfo, visitor: &mut V) {
+        visitor.visit_bytes(field, elements);
+    }
+}
+
+impl VisitField for f16 {
+    fn visit_field<V: Visitor>(&self, field: &'static FieldInfo, visitor: &mut V) {
+        visitor.visit_value(field, Value::Float(self.to_f64()));
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use super::*;
+
+    use std::string::String;
+
+    use types::*;
+
+    #[derive(UavcanStruct)]
+    #[FullName = "uavcan.protocol.NodeStatus"]
+    struct NodeStatus {
+        uptime_sec: u32,
+        health: u2,
+    }
+
+    impl Reflect for NodeStatus {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo{name: "uptime_sec", ty: "uint32", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "health", ty: "uint2", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+        ];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = false;
+
+        fn visit<V: Visitor>(&self, visitor: &mut V) {
+            self.uptime_sec.visit_field(&Self::FIELDS[0], visitor);
+            self.health.visit_field(&Self::FIELDS[1], visitor);
+        }
+    }
+
+    #[derive(UavcanStruct)]
+    #[FullName = "com.example.Status"]
+    struct Status {
+        _v0: void3,
+        gain: [f16; 2],
+        nodes: Dynamic<[NodeStatus; 4]>,
+    }
+
+    impl Reflect for Status {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo{name: "", ty: "void3", array: ArrayKind::Scalar, cast_mode: None},
+            FieldInfo{name: "gain", ty: "float16", array: ArrayKind::Static(2), cast_mode: Some(CastMode::Truncated)},
+            FieldInfo{name: "nodes", ty: "uavcan.protocol.NodeStatus", array: ArrayKind::Dynamic(4), cast_mode: None},
+        ];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = false;
+
+        fn visit<V: Visitor>(&self, visitor: &mut V) {
+            self._v0.visit_field(&Self::FIELDS[0], visitor);
+            self.gain.visit_field(&Self::FIELDS[1], visitor);
+            self.nodes.visit_field(&Self::FIELDS[2], visitor);
+        }
+    }
+
+    /// Records the calls to the visitor
+    struct Recorder(String);
+
+    impl Visitor for Recorder {
+        fn visit_value(&mut self, field: &'static FieldInfo, value: Value) {
+            self.0 += &format!("{}={} ", field.name, value);
+        }
+        fn enter_array(&mut self, field: &'static FieldInfo, length: usize) {
+            self.0 += &format!("{}[{}] ", field.name, length);
+        }
+        fn leave_array(&mut self, _field: &'static FieldInfo) {
+            self.0 += "] ";
+        }
+        fn enter_struct(&mut self, field: &'static FieldInfo, full_name: &'static str) {
+            self.0 += &format!("{}:{}{{ ", field.name, full_name);
+        }
+        fn leave_struct(&mut self, _field: &'static FieldInfo) {
+            self.0 += "} ";
+        }
+    }
+
+    #[test]
+    fn visit_fields() {
+        let mut nodes = Dynamic::<[NodeStatus; 4]>::new();
+        nodes.push(NodeStatus{uptime_sec: 10, health: u2::new(1)});
+        nodes.push(NodeStatus{uptime_sec: 20, health: u2::new(3)});
+        let status = Status{_v0: void3{}, gain: [f16::from_f32(0.5), f16::from_f32(-2.0)], nodes};
+
+        let mut recorder = Recorder(String::new());
+        status.visit(&mut recorder);
+        assert_eq!(
+            recorder.0,
+            "=void gain[2] gain=0.5 gain=-2 ] nodes[2] \
+             nodes:uavcan.protocol.NodeStatus{ uptime_sec=10 health=1 } \
+             nodes:uavcan.protocol.NodeStatus{ uptime_sec=20 health=3 } ] "
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Serde support for fields of primitive types
+//!
+//! `ux` and `f16` are defined in other crates, so they can't implement `Serialize` and `Deserialize` here.
+//! Instead, types generated with serde support serialize their primitive fields (and arrays of them)
+//! with `#[serde(with = "::uavcan_rs::serde_field")]`: `uint2` as its underlying `u8`, `float16` as `f32`
+//! and arrays as sequences. Deserializing a value that doesn't fit the field is an error.
+//!
+//! `Dynamic` arrays and void types implement `Serialize` and `Deserialize` directly, and optional fields
+//! (`Option<T>`, compiled from `T[<=1]`) are serialized as options.
+
+use lib::core::fmt;
+use lib::core::marker::PhantomData;
+
+use serde::de;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use types::f16;
+use types::FromNative;
+
+/// A field type that can be serialized with `serde_field`
+pub trait SerdeField: Sized {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Serializes a field, used by `#[serde(with = "::uavcan_rs::serde_field")]`
+pub fn serialize<T: SerdeField, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    value.serialize_field(serializer)
+}
+
+/// Deserializes a field, used by `#[serde(with = "::uavcan_rs::serde_field")]`
+pub fn deserialize<'de, T: SerdeField, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    T::deserialize_field(deserializer)
+}
+
+/// An element of an array, serialized with `SerdeField`
+#[derive(Debug, PartialEq)]
+pub(crate) struct Element<T>(pub T);
+
+impl<T: SerdeField> Serialize for Element<&T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_field(serializer)
+    }
+}
+
+impl<'de, T: SerdeField> Deserialize<'de> for Element<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_field(deserializer).map(Element)
+    }
+}
+
+/// Serializes the elements of an array as a sequence
+pub(crate) fn serialize_elements<T: SerdeField, S: Serializer>(elements: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+    for element in elements {
+        seq.serialize_element(&Element(element))?;
+    }
+    seq.end()
+}
+
+/// Deserializes a sequence of at most `max_length` elements, passing them to `push`, and returns the number of elements
+pub(crate) fn deserialize_elements<'de, T, D, F>(deserializer: D, max_length: usize, push: F) -> Result<usize, D::Error>
+    where T: Deserialize<'de>, D: Deserializer<'de>, F: FnMut(T) {
+    deserializer.deserialize_seq(ElementsVisitor{max_length, push, element: PhantomData})
+}
+
+struct ElementsVisitor<T, F> {
+    max_length: usize,
+    push: F,
+    element: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T)> de::Visitor<'de> for ElementsVisitor<T, F> {
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of at most {} elements", self.max_length)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(mut self, mut seq: A) -> Result<usize, A::Error> {
+        let mut length = 0;
+        while let Some(element) = seq.next_element()? {
+            if length == self.max_length {
+                return Err(de::Error::invalid_length(length + 1, &self));
+            }
+            (self.push)(element);
+            length += 1;
+        }
+        Ok(length)
+    }
+}
+
+macro_rules! impl_native{
+    ($($type:ident),*) => {$(
+        impl SerdeField for $type {
+            fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.serialize(serializer)
+            }
+            fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $type::deserialize(deserializer)
+            }
+        }
+    )*};
+}
+
+impl_native!(bool, u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl SerdeField for f16 {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self.to_f32())
+    }
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = f32::deserialize(deserializer)?;
+        f16::from_native(value).map_err(|_| de::Error::invalid_value(de::Unexpected::Float(f64::from(value)), &"a float that fits in f16"))
+    }
+}
+
+/// Serialized as an option rather than as an array of at most one element
+impl<T: SerdeField> SerdeField for Option<T> {
+    fn serialize_field<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Some(ref element) => serializer.serialize_some(&Element(element)),
+            None => serializer.serialize_none(),
+        }
+    }
+    fn deserialize_field<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::<Element<T>>::deserialize(deserializer)?.map(|Element(element)| element))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_test::{assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, assert_tokens, Token};
+
+    use types::*;
+
+    #[test]
+    fn primitive_fields() {
+        assert_ser_tokens(&Element(&u2::new(3)), &[Token::U8(3)]);
+        assert_de_tokens(&Element(u2::new(3)), &[Token::U8(3)]);
+        assert_de_tokens_error::<Element<u2>>(&[Token::U8(4)], "invalid value: integer `4`, expected an integer that fits in u2");
+
+        assert_ser_tokens(&Element(&i3::new(-4)), &[Token::I8(-4)]);
+        assert_de_tokens_error::<Element<i3>>(&[Token::I8(-5)], "invalid value: integer `-5`, expected an integer that fits in i3");
+
+        assert_ser_tokens(&Element(&f16::from_f32(0.5)), &[Token::F32(0.5)]);
+        assert_de_tokens(&Element(f16::from_f32(0.5)), &[Token::F32(0.5)]);
+
+        assert_tokens(&void3{}, &[Token::Unit]);
+    }
+
+    #[test]
+    fn array_fields() {
+        let tokens = [Token::Seq{len: Some(2)}, Token::U8(1), Token::U8(2), Token::SeqEnd];
+        assert_ser_tokens(&Element(&[u2::new(1), u2::new(2)]), &tokens);
+        assert_de_tokens(&Element([u2::new(1), u2::new(2)]), &tokens);
+        assert_de_tokens_error::<Element<[u2; 2]>>(&[Token::Seq{len: Some(1)}, Token::U8(1), Token::SeqEnd], "invalid length 1, expected a sequence of 2 elements");
+
+        let dynamic = Dynamic::<[u2; 3]>::with_data(&[u2::new(1), u2::new(2)]);
+        assert_ser_tokens(&Element(&dynamic), &tokens);
+        assert_de_tokens(&Element(dynamic), &tokens);
+    }
+
+    #[test]
+    fn optional_fields() {
+        assert_ser_tokens(&Element(&Some(u2::new(3))), &[Token::Some, Token::U8(3)]);
+        assert_de_tokens(&Element(Some(u2::new(3))), &[Token::Some, Token::U8(3)]);
+        assert_ser_tokens(&Element(&None::<u2>), &[Token::None]);
+        assert_de_tokens(&Element(None::<u2>), &[Token::None]);
+        assert_de_tokens_error::<Element<Option<u2>>>(&[Token::Some, Token::U8(4)], "invalid value: integer `4`, expected an integer that fits in u2");
+    }
+
+    #[test]
+    fn dynamic_arrays() {
+        let tokens = [Token::Seq{len: Some(2)}, Token::U8(1), Token::U8(2), Token::SeqEnd];
+        assert_tokens(&Dynamic::<[u8; 3]>::with_data(&[1, 2]), &tokens);
+        assert_tokens(&Dynamic::<[u8; 3]>::new(), &[Token::Seq{len: Some(0)}, Token::SeqEnd]);
+        assert_de_tokens_error::<Dynamic<[u8; 3]>>(
+            &[Token::Seq{len: Some(4)}, Token::U8(1), Token::U8(2), Token::U8(3), Token::U8(4)],
+            "invalid length 4, expected a sequence of at most 3 elements",
+        );
+    }
+}
@@ -76,6 +76,21 @@ impl<'a> SerializationBuffer<'a> {
     }
 
     
+    /// Pushes `bit_length` bits the way they'd be pushed one at a time, starting with the least significant bit
+    ///
+    /// `push_bits` writes a value most significant bit first within every byte, so a word of flags is reordered to
+    /// serialize like the same number of `bool`.
+    pub(crate) fn push_bit_sequence(&mut self, bit_length: usize, bits: u64) {
+        let value = sequence_to_value(bits, bit_length, self.stop_bit_index % 8);
+        self.push_bits(bit_length, value);
+    }
+
+    /// Pops `bit_length` bits the way they'd be popped one at a time, the first bit ends up as the least significant bit
+    pub(crate) fn pop_bit_sequence(&mut self, bit_length: usize) -> u64 {
+        let offset = self.start_bit_index % 8;
+        value_to_sequence(self.pop_bits(bit_length), bit_length, offset)
+    }
+
     pub fn push_bits(&mut self, bit_length: usize, bits: u64) {
         assert!(bit_length <= 64);
         assert!(self.stop_bit_index + bit_length <= self.data.len()*8);
@@ -122,6 +137,43 @@ impl<'a> SerializationBuffer<'a> {
 
 
 
+/// Reverses the order of the bits within every byte
+fn reverse_bits_in_bytes(bits: u128) -> u128 {
+    bits.reverse_bits().swap_bytes()
+}
+
+/// Reorders a sequence of bits into the value `push_bits` writes in the same order, when it starts `offset` bits into a byte
+fn sequence_to_value(bits: u64, bit_length: usize, offset: usize) -> u64 {
+    let bits = if bit_length == 64 { bits } else { bits & ((1 << bit_length) - 1) };
+    let mut reordered = reverse_bits_in_bytes(u128::from(bits) << offset);
+
+    // `push_bits` takes the bits of a partly filled last byte from the bottom of the value
+    let end = offset + bit_length;
+    if !end.is_multiple_of(8) {
+        let last = end / 8 * 8;
+        let byte = (reordered >> last) & 0xff;
+        reordered = (reordered & !(0xff << last)) | ((byte >> (8 - end % 8)) << last);
+    }
+
+    // the first byte only holds the bits after the offset
+    (((reordered >> 8) << (8 - offset)) | (reordered & 0xff)) as u64
+}
+
+/// The inverse of `sequence_to_value`, for a value read by `pop_bits`
+fn value_to_sequence(value: u64, bit_length: usize, offset: usize) -> u64 {
+    let value = u128::from(value);
+    let mut reordered = (value & ((1 << (8 - offset)) - 1)) | ((value >> (8 - offset)) << 8);
+
+    let end = offset + bit_length;
+    if !end.is_multiple_of(8) {
+        let last = end / 8 * 8;
+        let byte = (reordered >> last) & 0xff;
+        reordered = (reordered & !(0xff << last)) | ((byte << (8 - end % 8)) << last);
+    }
+
+    (reverse_bits_in_bytes(reordered) >> offset) as u64
+}
+
 pub struct Serializer<T: Struct> {
     structure: T,
     field_index: usize,
@@ -246,9 +298,9 @@ mod tests {
         let uint16: u16 = 257;
         let int16: i16 = -1;
         let int7: i7 = i7::new(-64);
-        let float16: f16 = f16::from_f32(3.141592);
+        let float16: f16 = f16::from_f32(lib::core::f32::consts::PI);
         let float32: f32 = 1.0;
-        let float64: f64 = 2.718281828459045235360;
+        let float64: f64 = lib::core::f64::consts::E;
 
         let mut data = [0u8; 4];
         let mut buffer = SerializationBuffer::with_empty_buffer(&mut data);
@@ -284,7 +336,7 @@ mod tests {
         
         buffer.stop_bit_index = 0;
         float16.serialize(&mut 0, &mut 0, false, &mut buffer);
-        assert_eq!(buffer.data[0..2], [f16::from_f32(3.141592).as_bits() as u8, (f16::from_f32(3.141592).as_bits() >> 8) as u8]);
+        assert_eq!(buffer.data[0..2], [float16.to_bits() as u8, (float16.to_bits() >> 8) as u8]);
             
         buffer.stop_bit_index = 0;
         float32.serialize(&mut 0, &mut 0, false, &mut buffer);
@@ -502,6 +554,154 @@ mod tests {
 
     }
 
+    #[test]
+    fn optional_fields() {
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct OptionStruct {
+            a: Option<u8>,
+            b: Option<u16>,
+        }
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct DynamicStruct {
+            a: Dynamic<[u8; 1]>,
+            b: Dynamic<[u16; 1]>,
+        }
+
+        assert_eq!(OptionStruct::DSDL_DEFINITION, DynamicStruct::DSDL_DEFINITION);
+        assert_eq!(OptionStruct::FLATTENED_FIELDS_NUMBER, DynamicStruct::FLATTENED_FIELDS_NUMBER);
+
+        let serialize_option = |value: OptionStruct| {
+            let mut serializer: Serializer<OptionStruct> = Serializer::from_structure(value);
+            let mut array: [u8; 4] = [0; 4];
+            serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut array));
+            array
+        };
+        let serialize_dynamic = |value: DynamicStruct| {
+            let mut serializer: Serializer<DynamicStruct> = Serializer::from_structure(value);
+            let mut array: [u8; 4] = [0; 4];
+            serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut array));
+            array
+        };
+
+        // the last field is tail array optimized, and the others are misaligned by the length bit
+        assert_eq!(
+            serialize_option(OptionStruct{a: Some(0xff), b: Some(0x1234)}),
+            serialize_dynamic(DynamicStruct{a: Dynamic::<[u8; 1]>::with_data(&[0xff]), b: Dynamic::<[u16; 1]>::with_data(&[0x1234])}),
+        );
+        assert_eq!(
+            serialize_option(OptionStruct{a: None, b: Some(0x1234)}),
+            serialize_dynamic(DynamicStruct{a: Dynamic::<[u8; 1]>::new(), b: Dynamic::<[u16; 1]>::with_data(&[0x1234])}),
+        );
+    }
+
+    #[test]
+    fn unions() {
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct Empty {}
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        enum Value {
+            Empty(Empty),
+            Integer(u8),
+            Text(Dynamic<[u8; 4]>),
+        }
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct UnionStruct {
+            a: Value,
+            b: Value,
+        }
+
+        // the same wire format, with the tags as fields
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct TagStruct {
+            a_tag: u2,
+            a: u8,
+            b_tag: u2,
+            b: Dynamic<[u8; 4]>,
+        }
+
+        assert_eq!(Value::FLATTENED_FIELDS_NUMBER, 1 + 5);
+        assert_eq!(Value::BIT_LENGTH_MIN, 2);
+
+        let serialize_union = |value: UnionStruct| {
+            let mut serializer: Serializer<UnionStruct> = Serializer::from_structure(value);
+            let mut array: [u8; 5] = [0; 5];
+            serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut array));
+            array
+        };
+        let serialize_tags = |value: TagStruct| {
+            let mut serializer: Serializer<TagStruct> = Serializer::from_structure(value);
+            let mut array: [u8; 5] = [0; 5];
+            serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut array));
+            array
+        };
+
+        // the last union is tail array optimized as well
+        assert_eq!(
+            serialize_union(UnionStruct{a: Value::Integer(10), b: Value::Text(Dynamic::<[u8; 4]>::with_data(&[1, 2]))}),
+            serialize_tags(TagStruct{a_tag: u2::new(1), a: 10, b_tag: u2::new(2), b: Dynamic::<[u8; 4]>::with_data(&[1, 2])}),
+        );
+
+        let mut serializer: Serializer<UnionStruct> = Serializer::from_structure(UnionStruct{a: Value::Empty(Empty{}), b: Value::Integer(0xff)});
+        let mut array: [u8; 2] = [0; 2];
+        let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
+        assert_eq!(serializer.serialize(&mut buffer), SerializationResult::Finished);
+        assert_eq!(buffer.bit_length(), 2 + 2 + 8);
+    }
+
+    #[test]
+    fn bit_arrays() {
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct BitStruct {
+            a: u3,
+            b: BitArray<[bool; 70]>,
+            c: DynamicBitArray<[bool; 100]>,
+        }
+
+        #[derive(Debug, PartialEq, Clone, UavcanStruct)]
+        struct BoolStruct {
+            a: u3,
+            b: [bool; 70],
+            c: Dynamic<[bool; 100]>,
+        }
+
+        assert_eq!(BitStruct::DSDL_DEFINITION, BoolStruct::DSDL_DEFINITION);
+        assert_eq!(BitStruct::BIT_LENGTH_MIN, BoolStruct::BIT_LENGTH_MIN);
+
+        let mut bits = [false; 100];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = i % 3 == 0 || i % 7 == 0;
+        }
+        let bit_struct = BitStruct{
+            a: u3::new(5),
+            b: BitArray::from_native(&bits[..70]).unwrap(),
+            c: DynamicBitArray::with_data(&bits[..90]),
+        };
+        let bool_struct = BoolStruct{
+            a: u3::new(5),
+            b: <[bool; 70]>::from_native(&bits[..70]).unwrap(),
+            c: Dynamic::<[bool; 100]>::with_data(&bits[..90]),
+        };
+
+        let mut bit_serializer: Serializer<BitStruct> = Serializer::from_structure(bit_struct);
+        let mut bool_serializer: Serializer<BoolStruct> = Serializer::from_structure(bool_struct);
+
+        // serialized a few bytes at a time, like into frames, so the bits are split between buffers
+        loop {
+            let mut bit_array = [0u8; 5];
+            let mut bool_array = [0u8; 5];
+            let bit_result = bit_serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut bit_array));
+            let bool_result = bool_serializer.serialize(&mut SerializationBuffer::with_empty_buffer(&mut bool_array));
+            assert_eq!(bit_array, bool_array);
+            assert_eq!(bit_result, bool_result);
+            if bit_result == SerializationResult::Finished {
+                break;
+            }
+        }
+    }
+
     #[test]
     fn dynamic_array_of_structs() {
         #[derive(Debug, PartialEq, Clone, UavcanStruct)]
@@ -537,7 +737,7 @@ mod tests {
         let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
 
         serializer.serialize(&mut buffer);
-        assert_eq!(buffer.data, [0, 3, f16::from_f32(1.0).as_bits() as u8, (f16::from_f32(1.0).as_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).as_bits() as u8), (f16::from_f32(1.0).as_bits() >> 8) as u8]);                   
+        assert_eq!(buffer.data, [0, 3, f16::from_f32(1.0).to_bits() as u8, (f16::from_f32(1.0).to_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).to_bits() as u8), (f16::from_f32(1.0).to_bits() >> 8) as u8]);                   
 
     }
 
@@ -573,7 +773,7 @@ mod tests {
         let mut buffer = SerializationBuffer::with_empty_buffer(&mut array);
 
         serializer.serialize(&mut buffer);
-        assert_eq!(buffer.data, [0, 3, f16::from_f32(1.0).as_bits() as u8, (f16::from_f32(1.0).as_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).as_bits() as u8), (f16::from_f32(1.0).as_bits() >> 8) as u8]);                   
+        assert_eq!(buffer.data, [0, 3, f16::from_f32(1.0).to_bits() as u8, (f16::from_f32(1.0).to_bits() >> 8) as u8, 1, 3, (f16::from_f32(1.0).to_bits() as u8), (f16::from_f32(1.0).to_bits() >> 8) as u8]);                   
 
     }
 
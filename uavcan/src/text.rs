@@ -0,0 +1,837 @@
+//! A human readable text format for composite types
+//!
+//! Values are written like YAML, the way the pyuavcan and libuavcan tools print them: one `name: value` line per field,
+//! with the fields of nested composite types indented below their name. `uintN` and `intN` are written as integers,
+//! `Dynamic` arrays of `uint8` as quoted strings when all their bytes are printable ASCII, other arrays of primitive types
+//! as `[1, 2, 3]` and arrays of composite types as a list of `- ` items. A union is written as its active variant,
+//! and void fields are skipped.
+//!
+//! `write` formats any type implementing `Reflect`, `parse` reads the same format back into a type implementing `Parse`.
+//! The DSDL compiler implements `Parse`, `Display` and `FromStr` for the types it generates when `CompileConfig::text` is set.
+//!
+//! # Examples
+//! ```
+//! # #[macro_use]
+//! # extern crate uavcan;
+//! # use uavcan::Struct;
+//! # use uavcan::types::*;
+//! use std::fmt;
+//! use uavcan::reflect::*;
+//! use uavcan::text::{self, Parse, Parser, ParseError};
+//!
+//! #[derive(Debug, PartialEq, UavcanStruct)]
+//! #[FullName = "com.example.Greeting"]
+//! struct Greeting {
+//!     mood: u3,
+//!     text: Dynamic<[u8; 16]>,
+//! }
+//!
+//! impl Reflect for Greeting {
+//!     const FIELDS: &'static [FieldInfo] = &[
+//!         FieldInfo{name: "mood", ty: "uint3", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+//!         FieldInfo{name: "text", ty: "uint8", array: ArrayKind::Dynamic(16), cast_mode: Some(CastMode::Saturated)},
+//!     ];
+//!     const CONSTANTS: &'static [ConstantInfo] = &[];
+//!     const UNION: bool = false;
+//!
+//!     fn visit<V: Visitor>(&self, visitor: &mut V) {
+//!         self.mood.visit_field(&Self::FIELDS[0], visitor);
+//!         self.text.visit_field(&Self::FIELDS[1], visitor);
+//!     }
+//! }
+//!
+//! impl Parse for Greeting {
+//!     fn parse_fields(parser: &mut Parser) -> Result<Self, ParseError> {
+//!         Ok(Greeting{mood: parser.field(&Self::FIELDS[0])?, text: parser.field(&Self::FIELDS[1])?})
+//!     }
+//! }
+//!
+//! impl fmt::Display for Greeting {
+//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//!         text::write(self, f)
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let greeting = Greeting{mood: u3::new(5), text: Dynamic::<[u8; 16]>::with_data(b"hello")};
+//! assert_eq!(greeting.to_string(), "mood: 5\ntext: \"hello\"");
+//! assert_eq!(text::parse::<Greeting>("mood: 5\ntext: \"hello\""), Ok(greeting));
+//! # }
+//! ```
+
+use lib::core::str;
+
+use reflect::{ArrayKind, FieldInfo, Reflect, Value, Visitor};
+use types::{f16, FromNative};
+
+/// Re-exported so the `Display` and `FromStr` implementations emitted by the DSDL compiler work without `std`
+#[doc(hidden)]
+pub use lib::core::fmt;
+#[doc(hidden)]
+pub use lib::core::str::FromStr;
+
+/// A composite type that can be parsed from the text format
+pub trait Parse: Reflect {
+    /// Parses the fields of the type in the order they're defined in DSDL, or the active variant of a union
+    fn parse_fields(parser: &mut Parser) -> Result<Self, ParseError>;
+}
+
+/// A field type that can be parsed from the text format, implemented for the primitive types, arrays and types implementing `Parse`
+pub trait TextField: Sized {
+    /// Parses a value written on the same line as the name of its field
+    fn parse_inline(text: &str, field: &'static FieldInfo) -> Result<Self, ParseErrorKind>;
+
+    /// Parses a value written in the block indented below the name of its field
+    fn parse_block(parser: &mut Parser, field: &'static FieldInfo) -> Result<Self, ParseError> {
+        Err(parser.error(ParseErrorKind::InvalidValue(field.name)))
+    }
+
+    /// Parses an element of an array written as a list of `- ` items
+    fn parse_item(parser: &mut Parser, field: &'static FieldInfo) -> Result<Self, ParseError> {
+        match parser.item() {
+            Some(text) => {
+                parser.next_line();
+                Self::parse_inline(text, field).map_err(|kind| parser.error(kind))
+            },
+            None => Self::parse_block(parser, field),
+        }
+    }
+
+    /// Converts a character of a string into an element of an array, only `uint8` arrays can be written as strings
+    fn from_byte(_byte: u8) -> Option<Self> {
+        None
+    }
+}
+
+/// The reason a text couldn't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The line is neither a `name: value` pair nor an element of an array
+    Syntax,
+    /// The line is indented more than the fields around it
+    Indentation,
+    /// Another field, or the end of the text, was found where this field was expected
+    ExpectedField(&'static str),
+    /// The field isn't a variant of the union
+    ExpectedVariant,
+    /// The field doesn't belong to the type, or is repeated
+    UnexpectedField,
+    /// The value can't be parsed as the type of the field, or doesn't fit it
+    InvalidValue(&'static str),
+    /// The array has more elements than the field can hold, or a static array has fewer
+    WrongLength(&'static str),
+}
+
+/// An error parsing the text format, with the line (counting from 1) that caused it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseErrorKind::Syntax => write!(f, "expected `name: value` or `- value`"),
+            ParseErrorKind::Indentation => write!(f, "unexpected indentation"),
+            ParseErrorKind::ExpectedField(field) => write!(f, "expected field `{}`", field),
+            ParseErrorKind::ExpectedVariant => write!(f, "expected a variant of the union"),
+            ParseErrorKind::UnexpectedField => write!(f, "unexpected field"),
+            ParseErrorKind::InvalidValue(field) => write!(f, "invalid value for field `{}`", field),
+            ParseErrorKind::WrongLength(field) => write!(f, "the length of field `{}` doesn't fit the array", field),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+/// Writes a value in the text format, used by the `Display` implementations of generated types
+pub fn write<T: Reflect>(value: &T, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut writer = Writer{
+        f,
+        result: Ok(()),
+        indent: 0,
+        first_line: true,
+        item: false,
+        empty_struct: false,
+        first_element: false,
+    };
+    value.visit(&mut writer);
+    writer.result
+}
+
+/// Parses a value written in the text format, used by the `FromStr` implementations of generated types
+pub fn parse<T: Parse>(text: &str) -> Result<T, ParseError> {
+    let mut parser = Parser::new(text);
+    parser.indent = parser.next.map_or(0, |line| line.indent);
+    let value = T::parse_fields(&mut parser)?;
+    match parser.next {
+        Some(line) if line.indent == parser.indent => Err(ParseError{line: line.number, kind: ParseErrorKind::UnexpectedField}),
+        Some(line) => Err(ParseError{line: line.number, kind: ParseErrorKind::Indentation}),
+        None => Ok(value),
+    }
+}
+
+/// Writes the fields of a value as they're visited
+struct Writer<'a, 'b: 'a> {
+    f: &'a mut fmt::Formatter<'b>,
+    result: fmt::Result,
+
+    /// The indentation of the fields being written
+    indent: usize,
+
+    /// Nothing has been written yet
+    first_line: bool,
+
+    /// The next line is the first of an element of an array of composite types, and starts with `- `
+    item: bool,
+
+    /// The composite value whose name was written last doesn't have any fields (yet)
+    empty_struct: bool,
+
+    /// The next value is the first element of an array written on a single line
+    first_element: bool,
+}
+
+impl<'a, 'b> Writer<'a, 'b> {
+    fn write(&mut self, args: fmt::Arguments) {
+        if self.result.is_ok() {
+            self.result = self.f.write_fmt(args);
+        }
+    }
+
+    fn start_line(&mut self) {
+        if !self.first_line {
+            self.write(format_args!("\n"));
+        }
+        self.first_line = false;
+        self.empty_struct = false;
+        let indent = self.indent;
+        if self.item {
+            self.item = false;
+            self.write(format_args!("{:1$}- ", "", indent - 2));
+        } else {
+            self.write(format_args!("{:1$}", "", indent));
+        }
+    }
+
+    fn start_field(&mut self, name: &str) {
+        self.start_line();
+        self.write(format_args!("{}:", name));
+    }
+
+    fn write_value(&mut self, field: &'static FieldInfo, value: Value) {
+        match value {
+            // floats narrower than `float64` are written with the fewest digits that parse back to the same value
+            Value::Float(value) if field.ty != "float64" => self.write(format_args!("{}", value as f32)),
+            value => self.write(format_args!("{}", value)),
+        }
+    }
+}
+
+impl<'a, 'b> Visitor for Writer<'a, 'b> {
+    fn visit_value(&mut self, field: &'static FieldInfo, value: Value) {
+        if field.array != ArrayKind::Scalar {
+            if !self.first_element {
+                self.write(format_args!(", "));
+            }
+            self.first_element = false;
+            self.write_value(field, value);
+        } else if !field.name.is_empty() {
+            self.start_field(field.name);
+            self.write(format_args!(" "));
+            self.write_value(field, value);
+        }
+    }
+
+    fn visit_bytes(&mut self, field: &'static FieldInfo, bytes: &[u8]) {
+        let printable = bytes.iter().all(|byte| (b' '..=b'~').contains(byte));
+        if let (ArrayKind::Dynamic(_), true) = (field.array, printable) {
+            self.start_field(field.name);
+            self.write(format_args!(" \""));
+            for byte in bytes {
+                match *byte {
+                    b'"' | b'\\' => self.write(format_args!("\\{}", *byte as char)),
+                    byte => self.write(format_args!("{}", byte as char)),
+                }
+            }
+            self.write(format_args!("\""));
+        } else {
+            self.enter_array(field, bytes.len());
+            for byte in bytes {
+                self.visit_value(field, Value::Unsigned(u64::from(*byte)));
+            }
+            self.leave_array(field);
+        }
+    }
+
+    fn enter_array(&mut self, field: &'static FieldInfo, length: usize) {
+        self.start_field(field.name);
+        if is_composite(field) {
+            if length == 0 {
+                self.write(format_args!(" []"));
+            }
+            self.indent += 2;
+        } else {
+            self.write(format_args!(" ["));
+            self.first_element = true;
+        }
+    }
+
+    fn leave_array(&mut self, field: &'static FieldInfo) {
+        if is_composite(field) {
+            self.indent -= 2;
+        } else {
+            self.write(format_args!("]"));
+        }
+    }
+
+    fn enter_struct(&mut self, field: &'static FieldInfo, _full_name: &'static str) {
+        if field.array == ArrayKind::Scalar {
+            self.start_field(field.name);
+            self.empty_struct = true;
+        } else {
+            self.item = true;
+        }
+        self.indent += 2;
+    }
+
+    fn leave_struct(&mut self, field: &'static FieldInfo) {
+        if field.array == ArrayKind::Scalar && self.empty_struct {
+            self.write(format_args!(" {{}}"));
+            self.empty_struct = false;
+        } else if field.array != ArrayKind::Scalar && self.item {
+            self.start_line();
+            self.write(format_args!("{{}}"));
+        }
+        self.indent -= 2;
+    }
+}
+
+/// Composite types are named by their full name, which always contains the namespace
+fn is_composite(field: &FieldInfo) -> bool {
+    field.ty.contains('.')
+}
+
+/// Reads the fields of a value from the text format, line by line
+pub struct Parser<'a> {
+    lines: str::Lines<'a>,
+
+    /// The number of lines read from `lines`
+    read: usize,
+
+    /// The next line that isn't blank or a comment
+    next: Option<Line<'a>>,
+
+    /// The last line that was parsed, errors in values are reported at it
+    line: usize,
+
+    /// The indentation of the fields being parsed
+    indent: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    content: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut parser = Parser{lines: text.lines(), read: 0, next: None, line: 0, indent: 0};
+        parser.next_line();
+        parser
+    }
+
+    /// Parses the next field, which must be `field`
+    pub fn field<T: TextField>(&mut self, field: &'static FieldInfo) -> Result<T, ParseError> {
+        let (line, value) = match self.peek()? {
+            Some(line) => match split_field(line) {
+                Ok((name, value)) if name == field.name => (line, value),
+                Ok(_) => return Err(ParseError{line: line.number, kind: ParseErrorKind::ExpectedField(field.name)}),
+                Err(error) => return Err(error),
+            },
+            None => return Err(self.end_error(ParseErrorKind::ExpectedField(field.name))),
+        };
+        self.next_line();
+        if value.is_empty() {
+            T::parse_block(self, field)
+        } else {
+            T::parse_inline(value, field).map_err(|kind| ParseError{line: line.number, kind})
+        }
+    }
+
+    /// Returns the index of the variant of a union the next field is, a void variant is written as nothing at all
+    pub fn variant(&mut self, variants: &'static [FieldInfo]) -> Result<usize, ParseError> {
+        let (name, error) = match self.peek()? {
+            Some(line) => (split_field(line)?.0, ParseError{line: line.number, kind: ParseErrorKind::ExpectedVariant}),
+            None => ("", self.end_error(ParseErrorKind::ExpectedVariant)),
+        };
+        variants.iter().position(|variant| variant.name == name).ok_or(error)
+    }
+
+    /// Parses the fields of a composite value in the block indented below the current line
+    pub(crate) fn block<T, F: FnOnce(&mut Self) -> Result<T, ParseError>>(&mut self, parse_fields: F) -> Result<T, ParseError> {
+        let outer = self.indent;
+        self.indent = match self.next {
+            Some(line) if line.indent > outer => line.indent,
+            _ => outer + 1,
+        };
+        let value = parse_fields(self)?;
+        if let Some(line) = self.peek()? {
+            return Err(ParseError{line: line.number, kind: ParseErrorKind::UnexpectedField});
+        }
+        self.indent = outer;
+        Ok(value)
+    }
+
+    /// Parses the elements of an array in the block indented below the current line, passing them to `push`
+    pub(crate) fn items<T, F>(&mut self, field: &'static FieldInfo, mut push: F) -> Result<(), ParseError>
+        where T: TextField, F: FnMut(T) -> Result<(), ParseErrorKind> {
+        self.block(|parser| {
+            while parser.peek()?.is_some_and(|line| line.content == "-" || line.content.starts_with("- ")) {
+                let element = T::parse_item(parser, field)?;
+                push(element).map_err(|kind| parser.error(kind))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Removes the `- ` marking the next element of an array, and returns the text after it
+    ///
+    /// The text is left as the next line, at the indentation of the fields of the element.
+    /// When the marker is alone on its line, the line is consumed and `None` is returned.
+    pub(crate) fn item(&mut self) -> Option<&'a str> {
+        let line = self.next.as_mut().expect("An item is only parsed after it's found");
+        let content = line.content;
+        let rest = &content[1..];
+        let text = rest.trim_start();
+        if text.is_empty() {
+            self.next_line();
+            None
+        } else {
+            line.indent += 1 + rest.len() - text.len();
+            line.content = text;
+            Some(text)
+        }
+    }
+
+    /// Consumes the next line
+    pub(crate) fn next_line(&mut self) {
+        if let Some(line) = self.next {
+            self.line = line.number;
+        }
+        self.next = None;
+        for line in &mut self.lines {
+            self.read += 1;
+            let content = line.trim();
+            if !content.is_empty() && !content.starts_with('#') {
+                self.next = Some(Line{number: self.read, indent: line.len() - line.trim_start().len(), content});
+                break;
+            }
+        }
+    }
+
+    /// An error at the last line that was parsed
+    pub(crate) fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError{line: self.line, kind}
+    }
+
+    /// The next line, if it's one of the fields being parsed
+    fn peek(&self) -> Result<Option<Line<'a>>, ParseError> {
+        match self.next {
+            Some(line) if line.indent > self.indent => Err(ParseError{line: line.number, kind: ParseErrorKind::Indentation}),
+            Some(line) if line.indent == self.indent => Ok(Some(line)),
+            _ => Ok(None),
+        }
+    }
+
+    /// An error at the line after the fields being parsed
+    fn end_error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError{line: self.next.map_or(self.read, |line| line.number), kind}
+    }
+}
+
+/// Splits a `name: value` line, the value is empty when it's written in the block below
+fn split_field<'a>(line: Line<'a>) -> Result<(&'a str, &'a str), ParseError> {
+    let syntax_error = ParseError{line: line.number, kind: ParseErrorKind::Syntax};
+    let colon = line.content.find(':').ok_or(syntax_error)?;
+    let value = &line.content[colon + 1..];
+    if !value.is_empty() && !value.starts_with(' ') {
+        return Err(syntax_error);
+    }
+    Ok((line.content[..colon].trim_end(), value.trim()))
+}
+
+/// Parses the elements of an array written on a single line, as `[1, 2, 3]` or as a quoted string, passing them to `push`
+pub(crate) fn parse_elements<T, F>(text: &str, field: &'static FieldInfo, mut push: F) -> Result<(), ParseErrorKind>
+    where T: TextField, F: FnMut(T) -> Result<(), ParseErrorKind> {
+    let invalid = ParseErrorKind::InvalidValue(field.name);
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        let mut bytes = text[1..text.len() - 1].bytes();
+        while let Some(byte) = bytes.next() {
+            let byte = match byte {
+                b'\\' => match bytes.next() {
+                    Some(escaped) if escaped == b'\\' || escaped == b'"' => escaped,
+                    _ => return Err(invalid),
+                },
+                b'"' => return Err(invalid),
+                byte => byte,
+            };
+            push(T::from_byte(byte).ok_or(invalid)?)?;
+        }
+        Ok(())
+    } else if text.starts_with('[') && text.ends_with(']') {
+        let elements = text[1..text.len() - 1].trim();
+        if !elements.is_empty() {
+            for element in elements.split(',') {
+                push(T::parse_inline(element.trim(), field)?)?;
+            }
+        }
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+impl<T: Parse> TextField for T {
+    fn parse_inline(text: &str, field: &'static FieldInfo) -> Result<Self, ParseErrorKind> {
+        if text == "{}" {
+            parse("").map_err(|error| error.kind)
+        } else {
+            Err(ParseErrorKind::InvalidValue(field.name))
+        }
+    }
+
+    fn parse_block(parser: &mut Parser, _field: &'static FieldInfo) -> Result<Self, ParseError> {
+        parser.block(T::parse_fields)
+    }
+
+    fn parse_item(parser: &mut Parser, field: &'static FieldInfo) -> Result<Self, ParseError> {
+        match parser.item() {
+            Some("{}") => {
+                parser.next_line();
+                Self::parse_inline("{}", field).map_err(|kind| parser.error(kind))
+            },
+            _ => parser.block(T::parse_fields),
+        }
+    }
+}
+
+macro_rules! impl_text_field{
+    ($($type:ident),*) => {$(
+        impl TextField for $type {
+            fn parse_inline(text: &str, field: &'static FieldInfo) -> Result<Self, ParseErrorKind> {
+                text.parse().map_err(|_| ParseErrorKind::InvalidValue(field.name))
+            }
+        }
+    )*};
+}
+
+impl_text_field!(bool, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl TextField for u8 {
+    fn parse_inline(text: &str, field: &'static FieldInfo) -> Result<Self, ParseErrorKind> {
+        text.parse().map_err(|_| ParseErrorKind::InvalidValue(field.name))
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        Some(byte)
+    }
+}
+
+impl TextField for f16 {
+    fn parse_inline(text: &str, field: &'static FieldInfo) -> Result<Self, ParseErrorKind> {
+        text.parse::<f32>().ok()
+            .and_then(|value| f16::from_native(value).ok())
+            .ok_or(ParseErrorKind::InvalidValue(field.name))
+    }
+}
+
+#[cfg(all(test, feature="std"))]
+mod tests {
+    use super::*;
+
+    use std::string::{String, ToString};
+
+    use reflect::*;
+    use types::*;
+
+    #[derive(Debug, Clone, PartialEq, UavcanStruct)]
+    #[FullName = "uavcan.protocol.NodeStatus"]
+    struct NodeStatus {
+        uptime_sec: u32,
+        health: u2,
+    }
+
+    impl Reflect for NodeStatus {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo{name: "uptime_sec", ty: "uint32", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "health", ty: "uint2", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+        ];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = false;
+
+        fn visit<V: Visitor>(&self, visitor: &mut V) {
+            self.uptime_sec.visit_field(&Self::FIELDS[0], visitor);
+            self.health.visit_field(&Self::FIELDS[1], visitor);
+        }
+    }
+
+    impl Parse for NodeStatus {
+        fn parse_fields(parser: &mut Parser) -> Result<Self, ParseError> {
+            Ok(NodeStatus{uptime_sec: parser.field(&Self::FIELDS[0])?, health: parser.field(&Self::FIELDS[1])?})
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, UavcanStruct)]
+    #[FullName = "com.example.Empty"]
+    struct Empty {}
+
+    impl Reflect for Empty {
+        const FIELDS: &'static [FieldInfo] = &[];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = false;
+
+        fn visit<V: Visitor>(&self, _visitor: &mut V) {}
+    }
+
+    impl Parse for Empty {
+        fn parse_fields(_parser: &mut Parser) -> Result<Self, ParseError> {
+            Ok(Empty{})
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, UavcanStruct)]
+    #[FullName = "com.example.Reading"]
+    enum Reading {
+        Count(i13),
+        Label(Dynamic<[u8; 8]>),
+        Status(NodeStatus),
+        _V0(void2),
+    }
+
+    impl Reflect for Reading {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo{name: "count", ty: "int13", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "label", ty: "uint8", array: ArrayKind::Dynamic(8), cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "status", ty: "uavcan.protocol.NodeStatus", array: ArrayKind::Scalar, cast_mode: None},
+            FieldInfo{name: "", ty: "void2", array: ArrayKind::Scalar, cast_mode: None},
+        ];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = true;
+
+        fn visit<V: Visitor>(&self, visitor: &mut V) {
+            match *self {
+                Reading::Count(ref value) => value.visit_field(&Self::FIELDS[0], visitor),
+                Reading::Label(ref value) => value.visit_field(&Self::FIELDS[1], visitor),
+                Reading::Status(ref value) => value.visit_field(&Self::FIELDS[2], visitor),
+                Reading::_V0(ref value) => value.visit_field(&Self::FIELDS[3], visitor),
+            }
+        }
+    }
+
+    impl Parse for Reading {
+        fn parse_fields(parser: &mut Parser) -> Result<Self, ParseError> {
+            match parser.variant(Self::FIELDS)? {
+                0 => Ok(Reading::Count(parser.field(&Self::FIELDS[0])?)),
+                1 => Ok(Reading::Label(parser.field(&Self::FIELDS[1])?)),
+                2 => Ok(Reading::Status(parser.field(&Self::FIELDS[2])?)),
+                3 => Ok(Reading::_V0(Default::default())),
+                _ => unreachable!("The variant is one of the fields"),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, UavcanStruct)]
+    #[FullName = "com.example.Report"]
+    struct Report {
+        name: Dynamic<[u8; 16]>,
+        _v0: void3,
+        gains: [f16; 2],
+        raw: Dynamic<[u8; 4]>,
+        id: [u8; 2],
+        nodes: Dynamic<[NodeStatus; 3]>,
+        reading: Reading,
+        markers: Dynamic<[Empty; 2]>,
+        ratio: f64,
+    }
+
+    impl Reflect for Report {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo{name: "name", ty: "uint8", array: ArrayKind::Dynamic(16), cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "", ty: "void3", array: ArrayKind::Scalar, cast_mode: None},
+            FieldInfo{name: "gains", ty: "float16", array: ArrayKind::Static(2), cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "raw", ty: "uint8", array: ArrayKind::Dynamic(4), cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "id", ty: "uint8", array: ArrayKind::Static(2), cast_mode: Some(CastMode::Saturated)},
+            FieldInfo{name: "nodes", ty: "uavcan.protocol.NodeStatus", array: ArrayKind::Dynamic(3), cast_mode: None},
+            FieldInfo{name: "reading", ty: "com.example.Reading", array: ArrayKind::Scalar, cast_mode: None},
+            FieldInfo{name: "markers", ty: "com.example.Empty", array: ArrayKind::Dynamic(2), cast_mode: None},
+            FieldInfo{name: "ratio", ty: "float64", array: ArrayKind::Scalar, cast_mode: Some(CastMode::Saturated)},
+        ];
+        const CONSTANTS: &'static [ConstantInfo] = &[];
+        const UNION: bool = false;
+
+        fn visit<V: Visitor>(&self, visitor: &mut V) {
+            self.name.visit_field(&Self::FIELDS[0], visitor);
+            self._v0.visit_field(&Self::FIELDS[1], visitor);
+            self.gains.visit_field(&Self::FIELDS[2], visitor);
+            self.raw.visit_field(&Self::FIELDS[3], visitor);
+            self.id.visit_field(&Self::FIELDS[4], visitor);
+            self.nodes.visit_field(&Self::FIELDS[5], visitor);
+            self.reading.visit_field(&Self::FIELDS[6], visitor);
+            self.markers.visit_field(&Self::FIELDS[7], visitor);
+            self.ratio.visit_field(&Self::FIELDS[8], visitor);
+        }
+    }
+
+    impl Parse for Report {
+        fn parse_fields(parser: &mut Parser) -> Result<Self, ParseError> {
+            Ok(Report{
+                name: parser.field(&Self::FIELDS[0])?,
+                _v0: Default::default(),
+                gains: parser.field(&Self::FIELDS[2])?,
+                raw: parser.field(&Self::FIELDS[3])?,
+                id: parser.field(&Self::FIELDS[4])?,
+                nodes: parser.field(&Self::FIELDS[5])?,
+                reading: parser.field(&Self::FIELDS[6])?,
+                markers: parser.field(&Self::FIELDS[7])?,
+                ratio: parser.field(&Self::FIELDS[8])?,
+            })
+        }
+    }
+
+    /// Formats a value with `write`
+    struct Text<'a, T: 'a>(&'a T);
+
+    impl<'a, T: Reflect> fmt::Display for Text<'a, T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write(self.0, f)
+        }
+    }
+
+    fn report() -> Report {
+        let mut nodes = Dynamic::<[NodeStatus; 3]>::new();
+        nodes.push(NodeStatus{uptime_sec: 10, health: u2::new(1)});
+        nodes.push(NodeStatus{uptime_sec: 20, health: u2::new(3)});
+        Report{
+            name: Dynamic::<[u8; 16]>::with_data(b"say \"hi\""),
+            _v0: void3{},
+            gains: [f16::from_f32(0.1), f16::from_f32(-2.0)],
+            raw: Dynamic::<[u8; 4]>::with_data(&[0, 255]),
+            id: [65, 66],
+            nodes,
+            reading: Reading::Status(NodeStatus{uptime_sec: 5, health: u2::new(0)}),
+            markers: Dynamic::<[Empty; 2]>::with_data(&[Empty{}]),
+            ratio: 0.1,
+        }
+    }
+
+    const REPORT: &str = "\
+name: \"say \\\"hi\\\"\"
+gains: [0.099975586, -2]
+raw: [0, 255]
+id: [65, 66]
+nodes:
+  - uptime_sec: 10
+    health: 1
+  - uptime_sec: 20
+    health: 3
+reading:
+  status:
+    uptime_sec: 5
+    health: 0
+markers:
+  - {}
+ratio: 0.1";
+
+    #[test]
+    fn write_text() {
+        assert_eq!(Text(&report()).to_string(), REPORT);
+
+        assert_eq!(Text(&Reading::Count(i13::new(-1000))).to_string(), "count: -1000");
+        assert_eq!(Text(&Reading::Label(Dynamic::<[u8; 8]>::new())).to_string(), "label: \"\"");
+        assert_eq!(Text(&Reading::_V0(void2{})).to_string(), "");
+        assert_eq!(Text(&Empty{}).to_string(), "");
+
+        let mut report = report();
+        report.nodes = Dynamic::<[NodeStatus; 3]>::new();
+        report.reading = Reading::Count(i13::new(7));
+        assert!(Text(&report).to_string().contains("\nnodes: []\nreading:\n  count: 7\n"));
+    }
+
+    #[test]
+    fn parse_text() {
+        assert_eq!(parse::<Report>(REPORT), Ok(report()));
+        assert_eq!(parse::<Reading>("count: -1000"), Ok(Reading::Count(i13::new(-1000))));
+        assert_eq!(parse::<Reading>(""), Ok(Reading::_V0(void2{})));
+        assert_eq!(parse::<Empty>(""), Ok(Empty{}));
+
+        // comments, blank lines, any consistent indentation and inline forms
+        let text = "\
+# a report
+name: \"say \\\"hi\\\"\"
+gains: [0.1, -2.0]
+
+raw: [0, 255]
+id: \"AB\"
+nodes: []
+reading:
+    status:
+        uptime_sec: 5
+        health: 0
+markers:
+  -
+  - {}
+ratio: 0.1
+";
+        let mut expected = report();
+        expected.nodes = Dynamic::<[NodeStatus; 3]>::new();
+        expected.markers.push(Empty{});
+        assert_eq!(parse::<Report>(text), Ok(expected));
+    }
+
+    #[test]
+    fn optional_fields() {
+        static FIELD: FieldInfo = FieldInfo{name: "value", ty: "uint16", array: ArrayKind::Dynamic(1), cast_mode: Some(CastMode::Saturated)};
+        assert_eq!(Option::<u16>::parse_inline("[5]", &FIELD), Ok(Some(5)));
+        assert_eq!(Option::<u16>::parse_inline("[]", &FIELD), Ok(None));
+        assert_eq!(Option::<u16>::parse_inline("[5, 6]", &FIELD), Err(ParseErrorKind::WrongLength("value")));
+    }
+
+    #[test]
+    fn parse_errors() {
+        fn error<T>(line: usize, kind: ParseErrorKind) -> Result<T, ParseError> {
+            Err(ParseError{line, kind})
+        }
+
+        assert_eq!(parse::<NodeStatus>("uptime_sec: 1"), error(1, ParseErrorKind::ExpectedField("health")));
+        assert_eq!(parse::<NodeStatus>("uptime_sec: 1\nmode: 2"), error(2, ParseErrorKind::ExpectedField("health")));
+        assert_eq!(parse::<NodeStatus>("uptime_sec: 1\nhealth: 4"), error(2, ParseErrorKind::InvalidValue("health")));
+        assert_eq!(parse::<NodeStatus>("uptime_sec: 1\nhealth: 1\nhealth: 1"), error(3, ParseErrorKind::UnexpectedField));
+        assert_eq!(parse::<NodeStatus>("uptime_sec: 1\n  health: 1"), error(2, ParseErrorKind::Indentation));
+        assert_eq!(parse::<NodeStatus>("uptime_sec 1"), error(1, ParseErrorKind::Syntax));
+        assert_eq!(parse::<Reading>("value: 1"), error(1, ParseErrorKind::ExpectedVariant));
+        assert_eq!(parse::<Reading>("label: \"too long!!\""), error(1, ParseErrorKind::WrongLength("label")));
+        assert_eq!(parse::<Reading>("label: [1, 2"), error(1, ParseErrorKind::InvalidValue("label")));
+        assert_eq!(parse::<Reading>("status:\n  uptime_sec: 1"), error(2, ParseErrorKind::ExpectedField("health")));
+
+        let text = REPORT.replace("gains: [0.099975586, -2]", "gains: [1]");
+        assert_eq!(parse::<Report>(&text), error(2, ParseErrorKind::WrongLength("gains")));
+        let text = REPORT.replace("  - uptime_sec: 20\n    health: 3", "  - uptime_sec: 20\n    health: 3\n  - {}\n  - {}");
+        assert_eq!(parse::<Report>(&text), error(10, ParseErrorKind::ExpectedField("uptime_sec")));
+        let text = REPORT.replace("    health: 3", "    health: 3\n  - uptime_sec: 30\n    health: 0\n  -\n    uptime_sec: 40\n    health: 0");
+        assert_eq!(parse::<Report>(&text), error(14, ParseErrorKind::WrongLength("nodes")));
+
+        assert_eq!(
+            ParseError{line: 2, kind: ParseErrorKind::ExpectedField("health")}.to_string(),
+            "line 2: expected field `health`"
+        );
+        assert_eq!(String::from("line 1: unexpected field"), ParseError{line: 1, kind: ParseErrorKind::UnexpectedField}.to_string());
+    }
+}
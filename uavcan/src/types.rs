@@ -9,10 +9,18 @@ pub use half::f16;
 use lib;
 use lib::core::fmt;
 use lib::core::cmp;
+use lib::core::hash::{
+    Hash,
+    Hasher,
+};
 use lib::core::ops::{
     Index,
     IndexMut,
+    Deref,
+    DerefMut,
 };
+use lib::core::iter::FromIterator;
+use lib::core::str::FromStr;
 
 use bit_field::BitField;
 
@@ -35,6 +43,105 @@ trait PrimitiveType : Sized + Copy + ::Serializable {
 }
     
 
+/// The reason a native value could not be converted into a Uavcan type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeError {
+    /// The value can't be represented with the bits available
+    OutOfRange,
+    /// The slice is longer than the array can hold, or doesn't match the length of a static array
+    WrongLength,
+}
+
+impl NativeError {
+    /// Attribute the error to the field with the given name
+    pub fn in_field(self, field: &'static str) -> FieldError {
+        match self {
+            NativeError::OutOfRange => FieldError::OutOfRange(field),
+            NativeError::WrongLength => FieldError::WrongLength(field),
+        }
+    }
+}
+
+/// An error building a generated struct from native values, naming the field that caused it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldError {
+    OutOfRange(&'static str),
+    WrongLength(&'static str),
+    /// The field was never set on the builder
+    Missing(&'static str),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldError::OutOfRange(field) => write!(f, "the value of field `{}` is out of range", field),
+            FieldError::WrongLength(field) => write!(f, "the length of field `{}` doesn't fit the array", field),
+            FieldError::Missing(field) => write!(f, "field `{}` was not set", field),
+        }
+    }
+}
+
+/// A `Dynamic` array didn't have room for more elements
+///
+/// `Dynamic::try_push` hands back the element that didn't fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError<T = ()> {
+    element: T,
+}
+
+impl<T> CapacityError<T> {
+    pub fn new(element: T) -> Self {
+        CapacityError{element}
+    }
+
+    /// Returns the element that didn't fit
+    pub fn element(self) -> T {
+        self.element
+    }
+}
+
+impl<T> fmt::Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the dynamic array is full")
+    }
+}
+
+/// Range checked conversion from a native Rust type into a Uavcan type
+///
+/// This is what the constructors and builders emitted by the DSDL compiler use to accept `u8` for a `u2`,
+/// `f32` for a `f16` and slices (or `&str`) for arrays.
+///
+/// # Examples
+/// ```
+/// use uavcan::types::*;
+///
+/// assert_eq!(u2::from_native(3u8), Ok(u2::new(3)));
+/// assert_eq!(u2::from_native(4u8), Err(NativeError::OutOfRange));
+///
+/// let name = Dynamic::<[u8; 80]>::from_native("node".as_bytes()).unwrap();
+/// assert_eq!(name.as_ref(), "node".as_bytes());
+/// ```
+pub trait FromNative<T>: Sized {
+    fn from_native(value: T) -> Result<Self, NativeError>;
+}
+
+impl<T> FromNative<T> for T {
+    fn from_native(value: T) -> Result<Self, NativeError> {
+        Ok(value)
+    }
+}
+
+impl FromNative<f32> for f16 {
+    fn from_native(value: f32) -> Result<Self, NativeError> {
+        let converted = f16::from_f32(value);
+        if converted.is_infinite() && !value.is_infinite() {
+            Err(NativeError::OutOfRange)
+        } else {
+            Ok(converted)
+        }
+    }
+}
+
 /// The Uavcan dynamic array type
 ///
 /// # Examples
@@ -47,6 +154,15 @@ trait PrimitiveType : Sized + Copy + ::Serializable {
 /// assert_eq!(dynamic_array.length(), 13);
 /// assert_eq!(str::from_utf8(dynamic_array.as_ref()).unwrap(), "dynamic array");
 ///
+/// // `u8` arrays convert to and from strings, and every array derefs to a slice
+/// let mut name: Dynamic<[u8; 8]> = "node".parse().unwrap();
+/// name.try_push(b'1').unwrap();
+/// assert_eq!(name.as_str(), Ok("node1"));
+/// assert!(name.starts_with(b"node"));
+/// assert!("too long name".parse::<Dynamic<[u8; 8]>>().is_err());
+///
+/// let doubled: Dynamic<[u16; 4]> = name.iter().take(2).map(|&c| u16::from(c) * 2).collect();
+/// assert_eq!(doubled.as_ref(), &[220, 222]);
 /// ```
 pub struct Dynamic<T> {
     array: lib::core::mem::ManuallyDrop<T>,
@@ -54,6 +170,12 @@ pub struct Dynamic<T> {
     deserialized_length: usize,
 }
 
+/// An iterator moving the elements out of a `Dynamic` array
+pub struct IntoIter<T> {
+    array: Dynamic<T>,
+    index: usize,
+}
+
 macro_rules! impl_array{
     {[$(($size:expr, $length_bits:expr)), *]} => {$(impl_array!(($size, $length_bits));)*};
     {($size:expr, $length_bits:expr)} => {
@@ -102,10 +224,20 @@ macro_rules! impl_array{
                 *bit = 0;
                 DeserializationResult::Finished
             }
+
+            fn placeholder() -> Self {
+                lib::core::array::from_fn(|_| T::placeholder())
+            }
         }
 
 
         
+        impl BitLength for [bool; $size] {
+            const LENGTH: usize = $size;
+            const LENGTH_BITS: usize = $length_bits;
+            type Words = [u64; words($size)];
+        }
+
         impl<T> Dynamic<[T; $size]> {
             pub const LENGTH_BITS: usize = $length_bits;
             pub const MAX_LENGTH: usize = $size;
@@ -129,11 +261,70 @@ macro_rules! impl_array{
                 s
             }
 
+            /// Constructs a new `Dynamic` array with cloned data, failing if `data` is longer than `MAX_LENGTH`
+            pub fn try_with_data(data: &[T]) -> Result<Self, CapacityError> where T: Clone {
+                if data.len() > Self::MAX_LENGTH {
+                    return Err(CapacityError::new(()));
+                }
+                Ok(Self::with_data(data))
+            }
+
             /// Push an item to the end of the `Dynamic` array. Size will increase by one after this operation.
             pub fn push(&mut self, item: T) {
                 assert!(self.current_length < Self::MAX_LENGTH, "Can't push data to full array");
                 unsafe{lib::core::ptr::write(&mut self.array[self.current_length] as *mut T, item)};
-                self.current_length += 1;                
+                self.current_length += 1;
+            }
+
+            /// Push an item to the end of the `Dynamic` array, handing it back if the array is full
+            pub fn try_push(&mut self, item: T) -> Result<(), CapacityError<T>> {
+                if self.current_length == Self::MAX_LENGTH {
+                    return Err(CapacityError::new(item));
+                }
+                self.push(item);
+                Ok(())
+            }
+
+            /// Removes the last element and returns it, or `None` if the array is empty
+            pub fn pop(&mut self) -> Option<T> {
+                if self.current_length == 0 {
+                    return None;
+                }
+                self.current_length -= 1;
+                Some(unsafe{lib::core::ptr::read(&self.array[self.current_length])})
+            }
+
+            /// Inserts an element at `index`, shifting all elements after it to the right
+            ///
+            /// Panics if `index` is larger than the length or the array is full.
+            pub fn insert(&mut self, index: usize, item: T) {
+                assert!(index <= self.current_length, "Dynamic::insert() index out of bounds");
+                assert!(self.current_length < Self::MAX_LENGTH, "Can't insert data into full array");
+                unsafe {
+                    let p = self.array.as_mut_ptr().add(index);
+                    lib::core::ptr::copy(p, p.add(1), self.current_length - index);
+                    lib::core::ptr::write(p, item);
+                }
+                self.current_length += 1;
+            }
+
+            /// Removes and returns the element at `index`, shifting all elements after it to the left
+            ///
+            /// Panics if `index` is out of bounds.
+            pub fn remove(&mut self, index: usize) -> T {
+                assert!(index < self.current_length, "Dynamic::remove() index out of bounds");
+                self.current_length -= 1;
+                unsafe {
+                    let p = self.array.as_mut_ptr().add(index);
+                    let item = lib::core::ptr::read(p);
+                    lib::core::ptr::copy(p.add(1), p, self.current_length - index);
+                    item
+                }
+            }
+
+            /// Removes all elements, dropping them
+            pub fn clear(&mut self) {
+                self.shrink(0);
             }
 
             /// Returns the current length for the dynamic array
@@ -181,6 +372,24 @@ macro_rules! impl_array{
                 self.array[0..self.current_length].iter_mut()
             }
 
+            /// Pushes an element parsed from text, failing when the array is full
+            fn push_parsed(&mut self, element: T, field: &'static ::reflect::FieldInfo) -> Result<(), ::text::ParseErrorKind> {
+                if self.current_length == Self::MAX_LENGTH {
+                    return Err(::text::ParseErrorKind::WrongLength(field.name));
+                }
+                self.push(element);
+                Ok(())
+            }
+
+            /// Moves the elements into a static array, which they must fill
+            fn into_static(self, field: &'static ::reflect::FieldInfo) -> Result<[T; $size], ::text::ParseErrorKind> {
+                if self.current_length != Self::MAX_LENGTH {
+                    return Err(::text::ParseErrorKind::WrongLength(field.name));
+                }
+                // the elements are all initialized, and dropping `Dynamic` doesn't drop them
+                Ok(unsafe{ lib::core::ptr::read(&*self.array) })
+            }
+
         }
 
         impl<T: ::Serializable> ::Serializable for Dynamic<[T; $size]> {
@@ -247,7 +456,9 @@ macro_rules! impl_array{
                 if *flattened_field == 0 {
                     
                     let buffer_len = buffer.bit_length();
-                    if buffer_len + *bit < Self::LENGTH_BITS {
+                    if buffer_len == 0 {
+                        return DeserializationResult::BufferInsufficient
+                    } else if buffer_len + *bit < Self::LENGTH_BITS {
                         self.deserialized_length.set_bits(*bit as u8..(*bit+buffer_len) as u8, buffer.pop_bits(buffer_len) as usize);
                         *bit += buffer_len;
                         return DeserializationResult::BufferInsufficient
@@ -260,16 +471,19 @@ macro_rules! impl_array{
                 
                 while *flattened_field < Self::FLATTENED_FIELDS_NUMBER {
                     let element = (*flattened_field - 1) / T::FLATTENED_FIELDS_NUMBER;
+                    // the length is checked before every element, since it can be zero
+                    if !tail_array_optimization && element >= self.deserialized_length {
+                        break;
+                    }
                     let mut element_field = (*flattened_field - 1) % T::FLATTENED_FIELDS_NUMBER;
+                    // the element isn't initialized before it's deserialized, so it starts as a placeholder
+                    if element_field == 0 && *bit == 0 {
+                        unsafe{lib::core::ptr::write(&mut self.array[element] as *mut T, T::placeholder())};
+                    }
                     match self.array[element].deserialize(&mut element_field, bit, false, buffer) {
                         DeserializationResult::Finished => {
                             *flattened_field = element*T::FLATTENED_FIELDS_NUMBER + 1 + element_field;
                             self.current_length = element+1;
-                            if !tail_array_optimization && self.current_length == self.deserialized_length {
-                                *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
-                                *bit = 0;
-                                return DeserializationResult::Finished;
-                            }
                         },
                         DeserializationResult::BufferInsufficient => {
                             *flattened_field = element*T::FLATTENED_FIELDS_NUMBER + 1 + element_field;
@@ -279,12 +493,15 @@ macro_rules! impl_array{
                     }
                 }
                 
-                *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
                 self.current_length = (*flattened_field - 1) / T::FLATTENED_FIELDS_NUMBER;
+                *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
                 *bit = 0;
                 DeserializationResult::Finished
             }
-            
+
+            fn placeholder() -> Self {
+                Self::new()
+            }
         }
 
         impl<T> Index<usize> for Dynamic<[T; $size]> {
@@ -337,6 +554,14 @@ macro_rules! impl_array{
             }
         }
         
+        impl<T: cmp::Eq> cmp::Eq for Dynamic<[T; $size]> {}
+
+        impl<T: Hash> Hash for Dynamic<[T; $size]> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.as_ref().hash(state)
+            }
+        }
+        
         // This is needed since it can't be derived for arrays larger than 32 yet
         impl<T: fmt::Debug> fmt::Debug for Dynamic<[T; $size]> {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -348,6 +573,32 @@ macro_rules! impl_array{
             }
         }
         
+        impl<'a, T: FromNative<N> + Copy + Default, N: Clone> FromNative<&'a [N]> for [T; $size] {
+            fn from_native(value: &'a [N]) -> Result<Self, NativeError> {
+                if value.len() != $size {
+                    return Err(NativeError::WrongLength);
+                }
+                let mut array = [T::default(); $size];
+                for (element, native) in array.iter_mut().zip(value) {
+                    *element = T::from_native(native.clone())?;
+                }
+                Ok(array)
+            }
+        }
+
+        impl<'a, T: FromNative<N>, N: Clone> FromNative<&'a [N]> for Dynamic<[T; $size]> {
+            fn from_native(value: &'a [N]) -> Result<Self, NativeError> {
+                if value.len() > $size {
+                    return Err(NativeError::WrongLength);
+                }
+                let mut array = Self::new();
+                for native in value {
+                    array.push(T::from_native(native.clone())?);
+                }
+                Ok(array)
+            }
+        }
+
         impl<T: Clone> Clone for Dynamic<[T; $size]> {
             fn clone(&self) -> Self {
                 let mut a = Self::new();
@@ -357,236 +608,1378 @@ macro_rules! impl_array{
                 a
             }
         }
-        
-    };
-}
-
-impl<T> Drop for Dynamic<T> {
-    fn drop(&mut self) {
-        // Since const generics doesn't work we can't deconstruct elements inside arrays when it's beeing dropped
-        // This might in extreme cases cause memory leaks and other weirdness from deconstructors not running
-        // This isn't good but not UD or unsafe either.
-        // Fix as soon as const generics lands
-    }
-}
-        
 
+        impl<T> Deref for Dynamic<[T; $size]> {
+            type Target = [T];
 
-impl_array!([(1, 1), (2, 2), (3, 2), (4, 3), (5, 3), (6, 3), (7, 3), (8, 4), (9, 4)]);
-impl_array!([(10, 4), (11, 4), (12, 4), (13, 4), (14, 4), (15, 4), (16, 5), (17, 5), (18, 5), (19, 5)]);
-impl_array!([(20, 5), (21, 5), (22, 5), (23, 5), (24, 5), (25, 5), (26, 5), (27, 5), (28, 5), (29, 5)]);
-impl_array!([(30, 5), (31, 5), (32, 6), (33, 6), (34, 6), (35, 6), (36, 6), (37, 6), (38, 6), (39, 6)]);
-impl_array!([(40, 6), (41, 6), (42, 6), (43, 6), (44, 6), (45, 6), (46, 6), (47, 6), (48, 6), (49, 6)]);
-impl_array!([(50, 6), (51, 6), (52, 6), (53, 6), (54, 6), (55, 6), (56, 6), (57, 6), (58, 6), (59, 6)]);
-impl_array!([(60, 6), (61, 6), (62, 6), (63, 6), (64, 7), (65, 7), (66, 7), (67, 7), (68, 7), (69, 7)]);
-impl_array!([(70, 7), (71, 7), (72, 7), (73, 7), (74, 7), (75, 7), (76, 7), (77, 7), (78, 7), (79, 7)]);
-impl_array!([(80, 7), (81, 7), (82, 7), (83, 7), (84, 7), (85, 7), (86, 7), (87, 7), (88, 7), (89, 7)]);
-impl_array!([(90, 7), (91, 7), (92, 7), (93, 7), (94, 7), (95, 7), (96, 7), (97, 7), (98, 7), (99, 7)]);
+            fn deref(&self) -> &[T] {
+                &self.array[0..self.current_length]
+            }
+        }
 
-impl_array!([(100, 7), (101, 7), (102, 7), (103, 7), (104, 7), (105, 7), (106, 7), (107, 7), (108, 7), (109, 7)]);
-impl_array!([(110, 7), (111, 7), (112, 7), (113, 7), (114, 7), (115, 7), (116, 7), (117, 7), (118, 7), (119, 7)]);
-impl_array!([(120, 7), (121, 7), (122, 7), (123, 7), (124, 7), (125, 7), (126, 7), (127, 7), (128, 8), (129, 8)]);
-impl_array!([(130, 8), (131, 8), (132, 8), (133, 8), (134, 8), (135, 8), (136, 8), (137, 8), (138, 8), (139, 8)]);
-impl_array!([(140, 8), (141, 8), (142, 8), (143, 8), (144, 8), (145, 8), (146, 8), (147, 8), (148, 8), (149, 8)]);
-impl_array!([(150, 8), (151, 8), (152, 8), (153, 8), (154, 8), (155, 8), (156, 8), (157, 8), (158, 8), (159, 8)]);
-impl_array!([(160, 8), (161, 8), (162, 8), (163, 8), (164, 8), (165, 8), (166, 8), (167, 8), (168, 8), (169, 8)]);
-impl_array!([(170, 8), (171, 8), (172, 8), (173, 8), (174, 8), (175, 8), (176, 8), (177, 8), (178, 8), (179, 8)]);
-impl_array!([(180, 8), (181, 8), (182, 8), (183, 8), (184, 8), (185, 8), (186, 8), (187, 8), (188, 8), (189, 8)]);
-impl_array!([(190, 8), (191, 8), (192, 8), (193, 8), (194, 8), (195, 8), (196, 8), (197, 8), (198, 8), (199, 8)]);
+        impl<T> DerefMut for Dynamic<[T; $size]> {
+            fn deref_mut(&mut self) -> &mut [T] {
+                &mut self.array[0..self.current_length]
+            }
+        }
 
-impl_array!([(200, 8), (201, 8), (202, 8), (203, 8), (204, 8), (205, 8), (206, 8), (207, 8), (208, 8), (209, 8)]);
-impl_array!([(210, 8), (211, 8), (212, 8), (213, 8), (214, 8), (215, 8), (216, 8), (217, 8), (218, 8), (219, 8)]);
-impl_array!([(220, 8), (221, 8), (222, 8), (223, 8), (224, 8), (225, 8), (226, 8), (227, 8), (228, 8), (229, 8)]);
-impl_array!([(230, 8), (231, 8), (232, 8), (233, 8), (234, 8), (235, 8), (236, 8), (237, 8), (238, 8), (239, 8)]);
-impl_array!([(240, 8), (241, 8), (242, 8), (243, 8), (244, 8), (245, 8), (246, 8), (247, 8), (248, 8), (249, 8)]);
-impl_array!([(250, 8), (251, 8), (252, 8), (253, 8), (254, 8), (255, 8), (256, 9)]);
+        /// Panics if the iterator yields more than `MAX_LENGTH` elements
+        impl<T> FromIterator<T> for Dynamic<[T; $size]> {
+            fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+                let mut array = Self::new();
+                array.extend(iter);
+                array
+            }
+        }
 
+        /// Panics if the array overflows
+        impl<T> Extend<T> for Dynamic<[T; $size]> {
+            fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+                for element in iter {
+                    self.push(element);
+                }
+            }
+        }
 
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void1{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void2{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void3{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void4{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void5{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void6{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void7{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void8{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void9{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void10{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void11{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void12{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void13{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void14{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void15{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void16{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void17{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void18{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void19{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void20{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void21{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void22{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void23{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void24{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void25{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void26{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void27{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void28{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void29{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void30{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void31{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void32{}
-
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void33{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void34{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void35{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void36{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void37{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void38{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void39{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void40{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void41{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void42{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void43{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void44{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void45{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void46{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void47{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void48{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void49{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void50{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void51{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void52{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void53{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void54{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void55{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void56{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void57{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void58{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void59{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void60{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void61{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void62{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void63{}
-#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq)] pub struct void64{}
+        impl<T> IntoIterator for Dynamic<[T; $size]> {
+            type Item = T;
+            type IntoIter = IntoIter<[T; $size]>;
 
+            fn into_iter(self) -> Self::IntoIter {
+                IntoIter{array: self, index: 0}
+            }
+        }
 
-macro_rules! impl_serializeable {
-    {$type:ident, $bits:expr} => {
-        impl ::Serializable for $type {
-            const BIT_LENGTH_MIN: usize = $bits;
+        impl<'a, T> IntoIterator for &'a Dynamic<[T; $size]> {
+            type Item = &'a T;
+            type IntoIter = lib::core::slice::Iter<'a, T>;
 
-            const FLATTENED_FIELDS_NUMBER: usize = 1;
-            
-            fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
-                assert_eq!(*flattened_field, 0);
-                let type_bits_remaining = $bits - *bit;
-                let buffer_bits_remaining = buffer.bits_remaining();
-                
-                if type_bits_remaining == 0 {
-                    *bit = 0;
-                    *flattened_field = 1;
-                    SerializationResult::Finished
-                } else if buffer_bits_remaining == 0 {
-                    SerializationResult::BufferFull
-                } else if buffer_bits_remaining >= type_bits_remaining {
-                    buffer.push_bits(type_bits_remaining, PrimitiveType::to_bits(*self) >> *bit);
-                    *bit = 0;
-                    *flattened_field = 1;
-                    SerializationResult::Finished
-                } else {
-                    buffer.push_bits(buffer_bits_remaining, PrimitiveType::to_bits(*self) >> *bit);
-                    *bit += buffer_bits_remaining;
-                    SerializationResult::BufferFull
-                }
-            }
-            
-            fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
-                assert_eq!(*flattened_field, 0);
-                let buffer_len = buffer.bit_length();
-                if buffer_len == 0 && *bit == $bits {
-                    *bit = 0;
-                    *flattened_field = 1;
-                    DeserializationResult::Finished
-                } else if buffer_len == 0 && *bit < $bits {
-                    DeserializationResult::BufferInsufficient
-                } else if buffer_len < $bits - *bit {
-                    *self = PrimitiveType::from_bits(PrimitiveType::to_bits(*self) | (buffer.pop_bits(buffer_len) << *bit));
-                    *bit += buffer_len;
-                    DeserializationResult::BufferInsufficient
-                } else {
-                    *self = PrimitiveType::from_bits(PrimitiveType::to_bits(*self) | (buffer.pop_bits($bits-*bit) << *bit));
-                    *bit = 0;
-                    *flattened_field = 1;
-                    DeserializationResult::Finished
-                }
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
             }
-            
         }
 
-    };
-}
+        impl<'a, T> IntoIterator for &'a mut Dynamic<[T; $size]> {
+            type Item = &'a mut T;
+            type IntoIter = lib::core::slice::IterMut<'a, T>;
 
-macro_rules! impl_ux{
-    {[$(($type:ident, $bits:expr)),*], $underlying_type:ident} => {$(impl_ux!($type, $bits, $underlying_type);)*};
-    ($type:ident, $bits:expr, $underlying_type:ident) => {
-        impl PrimitiveType for $type {
-            fn from_bits(v: u64) -> Self {
-                $type::new(v as $underlying_type)
-            }
-            fn to_bits(self) -> u64 {
-                u64::from(self)
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter_mut()
             }
         }
-        impl_serializeable!($type, $bits);
-    };
-}
 
-macro_rules! impl_ix{
-    {[$(($type:ident, $bits:expr)),*], $underlying_type:ident} => {$(impl_ix!($type, $bits, $underlying_type);)*};
-    ($type:ident, $bits:expr, $underlying_type:ident) => {
-        impl PrimitiveType for $type {
-            fn from_bits(v: u64) -> Self {
-                $type::new(v as $underlying_type)
+        impl<T> Iterator for IntoIter<[T; $size]> {
+            type Item = T;
+
+            fn next(&mut self) -> Option<T> {
+                if self.index == self.array.current_length {
+                    return None;
+                }
+                // the element is moved out, and dropping `Dynamic` doesn't drop it again
+                let element = unsafe{ lib::core::ptr::read(&self.array.array[self.index]) };
+                self.index += 1;
+                Some(element)
             }
-            fn to_bits(self) -> u64 {
-                i64::from(self) as u64
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let remaining = self.array.current_length - self.index;
+                (remaining, Some(remaining))
             }
         }
-        impl_serializeable!($type, $bits);
-    };
-}
 
-macro_rules! impl_vx{
-    {[$(($type:ident, $bits:expr)),*]} => {$(impl_vx!($type, $bits);)*};
-    ($type:ident, $bits:expr) => {
-        impl PrimitiveType for $type {
-            fn from_bits(_v: u64) -> Self {
-                $type{}
-            }
-            fn to_bits(self) -> u64 {
-                0
+        impl Dynamic<[u8; $size]> {
+            /// Returns the bytes as a string slice, if they're valid UTF-8
+            pub fn as_str(&self) -> Result<&str, lib::core::str::Utf8Error> {
+                lib::core::str::from_utf8(self.as_ref())
             }
         }
-        impl_serializeable!($type, $bits);
-    };
-}
-
-impl_ux!([(u2, 2), (u3, 3), (u4, 4), (u5, 5), (u6, 6), (u7, 7)], u8);
 
-impl_ux!([(u9, 9), (u10, 10), (u11, 11), (u12, 12), (u13, 13), (u14, 14), (u15, 15)], u16);
+        impl FromStr for Dynamic<[u8; $size]> {
+            type Err = CapacityError;
 
-impl_ux!([(u17, 17), (u18, 18), (u19, 19), (u20, 20), (u21, 21), (u22, 22), (u23, 23), (u24, 24),
-          (u25, 25), (u26, 26), (u27, 27), (u28, 28), (u29, 29), (u30, 30), (u31, 31)], u32);
+            /// Copies the bytes of `s`, failing if it's longer than `MAX_LENGTH`
+            fn from_str(s: &str) -> Result<Self, CapacityError> {
+                Self::try_with_data(s.as_bytes())
+            }
+        }
 
-impl_ux!([(u33, 33), (u34, 34), (u35, 35), (u36, 36), (u37, 37), (u38, 38), (u39, 39), (u40, 40),
-          (u41, 41), (u42, 42), (u43, 43), (u44, 44), (u45, 45), (u46, 46), (u47, 47), (u48, 48),
-          (u49, 49), (u50, 50), (u51, 51), (u52, 52), (u53, 53), (u54, 54), (u55, 55), (u56, 56),
-          (u57, 57), (u58, 58), (u59, 59), (u60, 60), (u61, 61), (u62, 62), (u63, 63)], u64);
+        impl<T: ::DsdlField> ::DsdlField for [T; $size] {
+            const DSDL_TYPE: &'static str = T::DSDL_TYPE;
+            const DSDL_ARRAY: &'static str = concat!("[", stringify!($size), "]");
+            const PRIMITIVE: bool = T::PRIMITIVE;
+            const VOID: bool = T::VOID;
+            const NESTED_SIGNATURE: Option<u64> = T::NESTED_SIGNATURE;
+        }
 
+        impl<T: ::DsdlField> ::DsdlField for Dynamic<[T; $size]> {
+            const DSDL_TYPE: &'static str = T::DSDL_TYPE;
+            const DSDL_ARRAY: &'static str = concat!("[<=", stringify!($size), "]");
+            const PRIMITIVE: bool = T::PRIMITIVE;
+            const VOID: bool = T::VOID;
+            const NESTED_SIGNATURE: Option<u64> = T::NESTED_SIGNATURE;
+        }
 
+        impl ::DsdlField for BitArray<[bool; $size]> {
+            const DSDL_TYPE: &'static str = "bool";
+            const DSDL_ARRAY: &'static str = concat!("[", stringify!($size), "]");
+            const PRIMITIVE: bool = true;
+        }
 
-impl_ix!([(i2, 2), (i3, 3), (i4, 4), (i5, 5), (i6, 6), (i7, 7)], i8);
+        impl ::DsdlField for DynamicBitArray<[bool; $size]> {
+            const DSDL_TYPE: &'static str = "bool";
+            const DSDL_ARRAY: &'static str = concat!("[<=", stringify!($size), "]");
+            const PRIMITIVE: bool = true;
+        }
 
-impl_ix!([(i9, 9), (i10, 10), (i11, 11), (i12, 12), (i13, 13), (i14, 14), (i15, 15)], i16);
+        impl<T: ::reflect::VisitField> ::reflect::VisitField for [T; $size] {
+            fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+                T::visit_elements(self, field, visitor);
+            }
+        }
+
+        impl<T: ::reflect::VisitField> ::reflect::VisitField for Dynamic<[T; $size]> {
+            fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+                T::visit_elements(self.as_ref(), field, visitor);
+            }
+        }
+
+        impl<T: ::text::TextField> ::text::TextField for [T; $size] {
+            fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+                Dynamic::<[T; $size]>::parse_inline(text, field)?.into_static(field)
+            }
+            fn parse_block(parser: &mut ::text::Parser, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseError> {
+                let array = Dynamic::<[T; $size]>::parse_block(parser, field)?;
+                array.into_static(field).map_err(|kind| parser.error(kind))
+            }
+        }
+
+        impl<T: ::text::TextField> ::text::TextField for Dynamic<[T; $size]> {
+            fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+                let mut array = Self::new();
+                ::text::parse_elements(text, field, |element| array.push_parsed(element, field))?;
+                Ok(array)
+            }
+            fn parse_block(parser: &mut ::text::Parser, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseError> {
+                let mut array = Self::new();
+                parser.items(field, |element| array.push_parsed(element, field))?;
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<T: ::serde::Serialize> ::serde::Serialize for Dynamic<[T; $size]> {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de, T: ::serde::Deserialize<'de>> ::serde::Deserialize<'de> for Dynamic<[T; $size]> {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut array = Self::new();
+                ::serde_field::deserialize_elements(deserializer, $size, |element| array.push(element))?;
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<T: ::serde_field::SerdeField + Copy + Default> ::serde_field::SerdeField for [T; $size] {
+            fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde_field::serialize_elements(self, serializer)
+            }
+            fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut array = [T::default(); $size];
+                let mut elements = array.iter_mut();
+                let length = ::serde_field::deserialize_elements(deserializer, $size, |::serde_field::Element(element)| {
+                    *elements.next().expect("The number of elements is checked while deserializing") = element;
+                })?;
+                if length != $size {
+                    return Err(::serde::de::Error::invalid_length(length, &concat!("a sequence of ", stringify!($size), " elements")));
+                }
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<T: ::serde_field::SerdeField> ::serde_field::SerdeField for Dynamic<[T; $size]> {
+            fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde_field::serialize_elements(self.as_ref(), serializer)
+            }
+            fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut array = Self::new();
+                ::serde_field::deserialize_elements(deserializer, $size, |::serde_field::Element(element)| array.push(element))?;
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "arbitrary")]
+        impl<'a, T: ::arbitrary::Arbitrary<'a>> ::arbitrary::Arbitrary<'a> for Dynamic<[T; $size]> {
+            fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+                let mut array = Self::new();
+                for _ in 0..u.int_in_range(0..=$size)? {
+                    array.push(T::arbitrary(u)?);
+                }
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "arbitrary")]
+        impl<T: ::arbitrary_field::ArbitraryField + Copy + Default> ::arbitrary_field::ArbitraryField for [T; $size] {
+            fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                let mut array = [T::default(); $size];
+                for element in array.iter_mut() {
+                    *element = T::arbitrary_field(u)?;
+                }
+                Ok(array)
+            }
+        }
+
+        #[cfg(feature = "arbitrary")]
+        impl<T: ::arbitrary_field::ArbitraryField> ::arbitrary_field::ArbitraryField for Dynamic<[T; $size]> {
+            fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                let mut array = Self::new();
+                for _ in 0..u.int_in_range(0..=$size)? {
+                    array.push(T::arbitrary_field(u)?);
+                }
+                Ok(array)
+            }
+        }
+        
+    };
+}
+
+/// `Option<T>` has the same wire format as `Dynamic<[T; 1]>`, which is how DSDL expresses optional values (`T[<=1]`)
+impl<T: ::Serializable> ::Serializable for Option<T> {
+    const BIT_LENGTH_MIN: usize = 1;
+    const FLATTENED_FIELDS_NUMBER: usize = T::FLATTENED_FIELDS_NUMBER + 1;
+
+    fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
+        if buffer.bits_remaining() == 0 {
+            return SerializationResult::BufferFull;
+        }
+
+        // check for tail optimization
+        if T::BIT_LENGTH_MIN >= 8 && last_field && *flattened_field == 0 {
+            *flattened_field = 1;
+        }
+
+        // the length is a single bit, so it always fits when there's room in the buffer
+        if *flattened_field == 0 {
+            buffer.push_bits(1, self.is_some() as u64);
+            *flattened_field = 1;
+            *bit = 0;
+        }
+
+        if let Some(ref element) = *self {
+            let mut element_field = *flattened_field - 1;
+            let result = element.serialize(&mut element_field, bit, false, buffer);
+            *flattened_field = element_field + 1;
+            if result == SerializationResult::BufferFull {
+                return SerializationResult::BufferFull;
+            }
+        }
+
+        *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+        *bit = 0;
+        SerializationResult::Finished
+    }
+
+    fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+
+        // check for tail optimization
+        let tail_array_optimization = last_field && (T::BIT_LENGTH_MIN >= 8);
+
+        if tail_array_optimization && *flattened_field == 0 {
+            *flattened_field = 1;
+        }
+
+        // deserialize length
+        if *flattened_field == 0 {
+            if buffer.bit_length() == 0 {
+                return DeserializationResult::BufferInsufficient;
+            }
+            let length = buffer.pop_bits(1);
+            *flattened_field = 1;
+            *bit = 0;
+            if length == 0 {
+                *self = None;
+                *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+                return DeserializationResult::Finished;
+            }
+        }
+
+        // with tail array optimization, there's only an element if the transfer doesn't end before it's complete.
+        // `Deserializer` tells that the transfer ended by deserializing an empty buffer.
+        if tail_array_optimization && buffer.bit_length() == 0 {
+            *self = None;
+            return DeserializationResult::BufferInsufficient;
+        }
+
+        // like the elements of `Dynamic`, the element starts as a placeholder before it's deserialized
+        if *flattened_field == 1 && *bit == 0 {
+            *self = Some(T::placeholder());
+        }
+
+        let mut element_field = *flattened_field - 1;
+        let result = self.as_mut().expect("The element is created before it's deserialized").deserialize(&mut element_field, bit, false, buffer);
+        *flattened_field = element_field + 1;
+        if result == DeserializationResult::BufferInsufficient {
+            return DeserializationResult::BufferInsufficient;
+        }
+
+        *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+        *bit = 0;
+        DeserializationResult::Finished
+    }
+
+    fn placeholder() -> Self {
+        None
+    }
+}
+
+impl<T: ::DsdlField> ::DsdlField for Option<T> {
+    const DSDL_TYPE: &'static str = T::DSDL_TYPE;
+    const DSDL_ARRAY: &'static str = "[<=1]";
+    const PRIMITIVE: bool = T::PRIMITIVE;
+    const VOID: bool = T::VOID;
+    const NESTED_SIGNATURE: Option<u64> = T::NESTED_SIGNATURE;
+}
+
+impl<T: ::reflect::VisitField> ::reflect::VisitField for Option<T> {
+    fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+        match *self {
+            Some(ref element) => T::visit_elements(lib::core::slice::from_ref(element), field, visitor),
+            None => T::visit_elements(&[], field, visitor),
+        }
+    }
+}
+
+/// Written and parsed like an array of at most one element
+impl<T: ::text::TextField> ::text::TextField for Option<T> {
+    fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+        Ok(Dynamic::<[T; 1]>::parse_inline(text, field)?.pop())
+    }
+    fn parse_block(parser: &mut ::text::Parser, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseError> {
+        Ok(Dynamic::<[T; 1]>::parse_block(parser, field)?.pop())
+    }
+}
+
+impl<T> Drop for Dynamic<T> {
+    fn drop(&mut self) {
+        // Since const generics doesn't work we can't deconstruct elements inside arrays when it's beeing dropped
+        // This might in extreme cases cause memory leaks and other weirdness from deconstructors not running
+        // This isn't good but not UD or unsafe either.
+        // Fix as soon as const generics lands
+    }
+}
+
+/// The length of a `BitArray` or `DynamicBitArray`, given as `[bool; N]`
+///
+/// Implemented for the same lengths as `Dynamic`.
+pub trait BitLength {
+    /// The (maximum) number of bits in the array
+    const LENGTH: usize;
+
+    /// The number of bits used to serialize the length of a `DynamicBitArray`
+    const LENGTH_BITS: usize;
+
+    /// The packed storage, the bits are stored from the least significant bit of the first word
+    #[doc(hidden)]
+    type Words: Copy + Default + AsRef<[u64]> + AsMut<[u64]>;
+}
+
+/// The number of words storing `length` bits
+const fn words(length: usize) -> usize {
+    length.div_ceil(64)
+}
+
+/// A static array of `bool`, stored packed as bits
+///
+/// It has the same wire format as `[bool; N]`, but takes an eighth of the memory and is serialized a word at a time.
+/// The DSDL compiler uses it for `bool[N]` with `CompileConfig::bit_arrays`.
+///
+/// # Examples
+/// ```
+/// use uavcan::types::*;
+///
+/// let mut flags = BitArray::<[bool; 100]>::new();
+/// flags.set(3, true);
+/// flags.set(99, true);
+///
+/// assert!(flags.get(3));
+/// assert!(!flags[4]);
+/// assert_eq!(flags.iter().filter(|&flag| flag).count(), 2);
+/// ```
+pub struct BitArray<T: BitLength> {
+    words: T::Words,
+}
+
+/// A dynamic array of `bool`, stored packed as bits
+///
+/// It has the same wire format as `Dynamic<[bool; N]>`, and is used by the DSDL compiler for `bool[<=N]`
+/// with `CompileConfig::bit_arrays`.
+///
+/// # Examples
+/// ```
+/// use uavcan::types::*;
+///
+/// let mut flags = DynamicBitArray::<[bool; 16]>::with_data(&[true, false]);
+/// flags.push(true);
+///
+/// assert_eq!(flags.length(), 3);
+/// assert_eq!(flags.pop(), Some(true));
+/// assert_eq!(flags.iter().collect::<Vec<bool>>(), vec![true, false]);
+/// ```
+pub struct DynamicBitArray<T: BitLength> {
+    words: T::Words,
+    current_length: usize,
+}
+
+/// An iterator over the bits of a `BitArray` or `DynamicBitArray`
+pub struct Bits<'a> {
+    words: &'a [u64],
+    index: usize,
+    length: usize,
+}
+
+/// A mask of the `bits` least significant bits
+fn low_mask(bits: usize) -> u64 {
+    if bits == 64 { !0 } else { (1 << bits) - 1 }
+}
+
+fn get_bit(words: &[u64], index: usize) -> bool {
+    words[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn set_bit(words: &mut [u64], index: usize, value: bool) {
+    if value {
+        words[index / 64] |= 1 << (index % 64);
+    } else {
+        words[index / 64] &= !(1 << (index % 64));
+    }
+}
+
+/// Serializes the first `length` bits, continuing from `bit`, as many at a time as the words and buffer allow
+fn serialize_bits(words: &[u64], length: usize, bit: &mut usize, buffer: &mut SerializationBuffer) -> SerializationResult {
+    while *bit < length {
+        let buffer_bits_remaining = buffer.bits_remaining();
+        if buffer_bits_remaining == 0 {
+            return SerializationResult::BufferFull;
+        }
+        let offset = *bit % 64;
+        let bits = cmp::min(cmp::min(64 - offset, length - *bit), buffer_bits_remaining);
+        buffer.push_bit_sequence(bits, (words[*bit / 64] >> offset) & low_mask(bits));
+        *bit += bits;
+    }
+    SerializationResult::Finished
+}
+
+/// Deserializes the first `length` bits, continuing from `bit`, as many at a time as the words and buffer allow
+fn deserialize_bits(words: &mut [u64], length: usize, bit: &mut usize, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+    while *bit < length {
+        let buffer_len = buffer.bit_length();
+        if buffer_len == 0 {
+            return DeserializationResult::BufferInsufficient;
+        }
+        let offset = *bit % 64;
+        let bits = cmp::min(cmp::min(64 - offset, length - *bit), buffer_len);
+        let word = &mut words[*bit / 64];
+        *word = (*word & !(low_mask(bits) << offset)) | (buffer.pop_bit_sequence(bits) << offset);
+        *bit += bits;
+    }
+    DeserializationResult::Finished
+}
+
+impl<'a> Iterator for Bits<'a> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.index == self.length {
+            return None;
+        }
+        self.index += 1;
+        Some(get_bit(self.words, self.index - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.length - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: BitLength> BitArray<T> {
+    pub const LENGTH: usize = T::LENGTH;
+
+    /// Constructs a new `BitArray` with all bits cleared
+    pub fn new() -> Self {
+        Self{words: T::Words::default()}
+    }
+
+    /// Returns the number of bits, which is always `LENGTH`
+    pub fn length(&self) -> usize {
+        T::LENGTH
+    }
+
+    /// Returns the bit at `index`, panics if `index` is out of bounds
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < T::LENGTH, "BitArray::get() index out of bounds");
+        get_bit(self.words.as_ref(), index)
+    }
+
+    /// Sets the bit at `index`, panics if `index` is out of bounds
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < T::LENGTH, "BitArray::set() index out of bounds");
+        set_bit(self.words.as_mut(), index, value);
+    }
+
+    pub fn iter(&self) -> Bits<'_> {
+        Bits{words: self.words.as_ref(), index: 0, length: T::LENGTH}
+    }
+}
+
+impl<T: BitLength> DynamicBitArray<T> {
+    pub const LENGTH_BITS: usize = T::LENGTH_BITS;
+    pub const MAX_LENGTH: usize = T::LENGTH;
+
+    /// Constructs a new empty `DynamicBitArray`
+    pub fn new() -> Self {
+        Self{words: T::Words::default(), current_length: 0}
+    }
+
+    /// Constructs a new `DynamicBitArray` holding `data`, panics if `data` is longer than `MAX_LENGTH`
+    pub fn with_data(data: &[bool]) -> Self {
+        let mut s = Self::new();
+        for &bit in data {
+            s.push(bit);
+        }
+        s
+    }
+
+    /// Constructs a new `DynamicBitArray` holding `data`, failing if `data` is longer than `MAX_LENGTH`
+    pub fn try_with_data(data: &[bool]) -> Result<Self, CapacityError> {
+        if data.len() > Self::MAX_LENGTH {
+            return Err(CapacityError::new(()));
+        }
+        Ok(Self::with_data(data))
+    }
+
+    /// Returns the current length of the array
+    pub fn length(&self) -> usize {
+        self.current_length
+    }
+
+    /// Returns the bit at `index`, panics if `index` is out of bounds
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.current_length, "DynamicBitArray::get() index out of bounds");
+        get_bit(self.words.as_ref(), index)
+    }
+
+    /// Sets the bit at `index`, panics if `index` is out of bounds
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.current_length, "DynamicBitArray::set() index out of bounds");
+        set_bit(self.words.as_mut(), index, value);
+    }
+
+    /// Push a bit to the end of the array, panics if the array is full
+    pub fn push(&mut self, value: bool) {
+        assert!(self.current_length < Self::MAX_LENGTH, "Can't push data to full array");
+        self.current_length += 1;
+        let index = self.current_length - 1;
+        self.set(index, value);
+    }
+
+    /// Push a bit to the end of the array, handing it back if the array is full
+    pub fn try_push(&mut self, value: bool) -> Result<(), CapacityError<bool>> {
+        if self.current_length == Self::MAX_LENGTH {
+            return Err(CapacityError::new(value));
+        }
+        self.push(value);
+        Ok(())
+    }
+
+    /// Removes the last bit and returns it, or `None` if the array is empty
+    pub fn pop(&mut self) -> Option<bool> {
+        if self.current_length == 0 {
+            return None;
+        }
+        let value = self.get(self.current_length - 1);
+        // bits past the length are kept cleared, which lets equality and hashing compare whole words
+        self.set(self.current_length - 1, false);
+        self.current_length -= 1;
+        Some(value)
+    }
+
+    /// Removes all bits
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn iter(&self) -> Bits<'_> {
+        Bits{words: self.words.as_ref(), index: 0, length: self.current_length}
+    }
+
+    /// Pushes a bit parsed from text, failing when the array is full
+    fn push_parsed(&mut self, value: bool, field: &'static ::reflect::FieldInfo) -> Result<(), ::text::ParseErrorKind> {
+        self.try_push(value).map_err(|_| ::text::ParseErrorKind::WrongLength(field.name))
+    }
+
+    /// Converts into a static array, which the bits must fill
+    fn into_static(self, field: &'static ::reflect::FieldInfo) -> Result<BitArray<T>, ::text::ParseErrorKind> {
+        if self.current_length != Self::MAX_LENGTH {
+            return Err(::text::ParseErrorKind::WrongLength(field.name));
+        }
+        Ok(BitArray{words: self.words})
+    }
+}
+
+impl<T: BitLength> ::Serializable for BitArray<T> {
+    const BIT_LENGTH_MIN: usize = T::LENGTH;
+    const FLATTENED_FIELDS_NUMBER: usize = 1;
+
+    fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
+        assert_eq!(*flattened_field, 0);
+        let result = serialize_bits(self.words.as_ref(), T::LENGTH, bit, buffer);
+        if result == SerializationResult::Finished {
+            *flattened_field = 1;
+            *bit = 0;
+        }
+        result
+    }
+
+    fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+        assert_eq!(*flattened_field, 0);
+        let result = deserialize_bits(self.words.as_mut(), T::LENGTH, bit, buffer);
+        if result == DeserializationResult::Finished {
+            *flattened_field = 1;
+            *bit = 0;
+        }
+        result
+    }
+
+    fn placeholder() -> Self {
+        Self::new()
+    }
+}
+
+/// The length is serialized first, there's never a tail array optimization since a `bool` is shorter than a byte
+impl<T: BitLength> ::Serializable for DynamicBitArray<T> {
+    const BIT_LENGTH_MIN: usize = T::LENGTH_BITS;
+    const FLATTENED_FIELDS_NUMBER: usize = 2;
+
+    fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
+        if *flattened_field == 0 {
+            let buffer_bits_remaining = buffer.bits_remaining();
+            if buffer_bits_remaining == 0 {
+                return SerializationResult::BufferFull;
+            }
+            let bits = cmp::min(T::LENGTH_BITS - *bit, buffer_bits_remaining);
+            buffer.push_bits(bits, (self.current_length as u64 >> *bit) & low_mask(bits));
+            *bit += bits;
+            if *bit < T::LENGTH_BITS {
+                return SerializationResult::BufferFull;
+            }
+            *flattened_field = 1;
+            *bit = 0;
+        }
+
+        let result = serialize_bits(self.words.as_ref(), self.current_length, bit, buffer);
+        if result == SerializationResult::Finished {
+            *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+            *bit = 0;
+        }
+        result
+    }
+
+    fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+        if *flattened_field == 0 {
+            if *bit == 0 {
+                self.clear();
+            }
+            let buffer_len = buffer.bit_length();
+            if buffer_len == 0 {
+                return DeserializationResult::BufferInsufficient;
+            }
+            let bits = cmp::min(T::LENGTH_BITS - *bit, buffer_len);
+            self.current_length |= (buffer.pop_bits(bits) as usize) << *bit;
+            *bit += bits;
+            if *bit < T::LENGTH_BITS {
+                return DeserializationResult::BufferInsufficient;
+            }
+            // a malformed length can be larger than the array, `Dynamic` stops after its last element as well
+            self.current_length = cmp::min(self.current_length, Self::MAX_LENGTH);
+            *flattened_field = 1;
+            *bit = 0;
+        }
+
+        let result = deserialize_bits(self.words.as_mut(), self.current_length, bit, buffer);
+        if result == DeserializationResult::Finished {
+            *flattened_field = Self::FLATTENED_FIELDS_NUMBER;
+            *bit = 0;
+        }
+        result
+    }
+
+    fn placeholder() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns a reference to a static `true` or `false`, since single bits can't be borrowed
+impl<T: BitLength> Index<usize> for BitArray<T> {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        if self.get(index) { &true } else { &false }
+    }
+}
+
+/// Returns a reference to a static `true` or `false`, since single bits can't be borrowed
+impl<T: BitLength> Index<usize> for DynamicBitArray<T> {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        if self.get(index) { &true } else { &false }
+    }
+}
+
+impl<T: BitLength> Default for BitArray<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: BitLength> Default for DynamicBitArray<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: BitLength> Clone for BitArray<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: BitLength> Clone for DynamicBitArray<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: BitLength> Copy for BitArray<T> {}
+
+impl<T: BitLength> Copy for DynamicBitArray<T> {}
+
+impl<T: BitLength> cmp::PartialEq for BitArray<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.words.as_ref() == other.words.as_ref()
+    }
+}
+
+impl<T: BitLength> cmp::PartialEq for DynamicBitArray<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.current_length == other.current_length && self.words.as_ref() == other.words.as_ref()
+    }
+}
+
+impl<T: BitLength> cmp::Eq for BitArray<T> {}
+
+impl<T: BitLength> cmp::Eq for DynamicBitArray<T> {}
+
+impl<T: BitLength> Hash for BitArray<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.words.as_ref().hash(state)
+    }
+}
+
+impl<T: BitLength> Hash for DynamicBitArray<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.current_length.hash(state);
+        self.words.as_ref().hash(state)
+    }
+}
+
+impl<T: BitLength> fmt::Debug for BitArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: BitLength> fmt::Debug for DynamicBitArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T: BitLength> IntoIterator for &'a BitArray<T> {
+    type Item = bool;
+    type IntoIter = Bits<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: BitLength> IntoIterator for &'a DynamicBitArray<T> {
+    type Item = bool;
+    type IntoIter = Bits<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Panics if the iterator yields more than `MAX_LENGTH` bits
+impl<T: BitLength> FromIterator<bool> for DynamicBitArray<T> {
+    fn from_iter<I: IntoIterator<Item=bool>>(iter: I) -> Self {
+        let mut array = Self::new();
+        array.extend(iter);
+        array
+    }
+}
+
+/// Panics if the array overflows
+impl<T: BitLength> Extend<bool> for DynamicBitArray<T> {
+    fn extend<I: IntoIterator<Item=bool>>(&mut self, iter: I) {
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+impl<'a, T: BitLength> FromNative<&'a [bool]> for BitArray<T> {
+    fn from_native(value: &'a [bool]) -> Result<Self, NativeError> {
+        if value.len() != T::LENGTH {
+            return Err(NativeError::WrongLength);
+        }
+        let mut array = Self::new();
+        for (index, &bit) in value.iter().enumerate() {
+            array.set(index, bit);
+        }
+        Ok(array)
+    }
+}
+
+impl<'a, T: BitLength> FromNative<&'a [bool]> for DynamicBitArray<T> {
+    fn from_native(value: &'a [bool]) -> Result<Self, NativeError> {
+        Self::try_with_data(value).map_err(|_| NativeError::WrongLength)
+    }
+}
+
+/// Visited like an array of `bool`
+impl<T: BitLength> ::reflect::VisitField for BitArray<T> {
+    fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+        visit_bits(self.iter(), field, visitor);
+    }
+}
+
+/// Visited like an array of `bool`
+impl<T: BitLength> ::reflect::VisitField for DynamicBitArray<T> {
+    fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+        visit_bits(self.iter(), field, visitor);
+    }
+}
+
+fn visit_bits<V: ::reflect::Visitor>(bits: Bits<'_>, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+    visitor.enter_array(field, bits.length);
+    for bit in bits {
+        visitor.visit_value(field, ::reflect::Value::Bool(bit));
+    }
+    visitor.leave_array(field);
+}
+
+/// Written and parsed like an array of `bool`
+impl<T: BitLength> ::text::TextField for BitArray<T> {
+    fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+        DynamicBitArray::<T>::parse_inline(text, field)?.into_static(field)
+    }
+    fn parse_block(parser: &mut ::text::Parser, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseError> {
+        let array = DynamicBitArray::<T>::parse_block(parser, field)?;
+        array.into_static(field).map_err(|kind| parser.error(kind))
+    }
+}
+
+/// Written and parsed like an array of `bool`
+impl<T: BitLength> ::text::TextField for DynamicBitArray<T> {
+    fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+        let mut array = Self::new();
+        ::text::parse_elements(text, field, |bit| array.push_parsed(bit, field))?;
+        Ok(array)
+    }
+    fn parse_block(parser: &mut ::text::Parser, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseError> {
+        let mut array = Self::new();
+        parser.items(field, |bit| array.push_parsed(bit, field))?;
+        Ok(array)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: BitLength> ::serde::Serialize for BitArray<T> {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: BitLength> ::serde::Serialize for DynamicBitArray<T> {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: BitLength> ::serde::Deserialize<'de> for BitArray<T> {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut array = DynamicBitArray::<T>::new();
+        let length = ::serde_field::deserialize_elements(deserializer, T::LENGTH, |bit: bool| array.push(bit))?;
+        if length != T::LENGTH {
+            return Err(::serde::de::Error::invalid_length(length, &"a sequence filling the bit array"));
+        }
+        Ok(BitArray{words: array.words})
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: BitLength> ::serde::Deserialize<'de> for DynamicBitArray<T> {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut array = Self::new();
+        ::serde_field::deserialize_elements(deserializer, T::LENGTH, |bit: bool| array.push(bit))?;
+        Ok(array)
+    }
+}
+
+/// `bool` is serialized natively, so this is the same as `Serialize` and `Deserialize`
+#[cfg(feature = "serde")]
+impl<T: BitLength> ::serde_field::SerdeField for BitArray<T> {
+    fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ::serde::Serialize::serialize(self, serializer)
+    }
+    fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ::serde::Deserialize::deserialize(deserializer)
+    }
+}
+
+/// `bool` is serialized natively, so this is the same as `Serialize` and `Deserialize`
+#[cfg(feature = "serde")]
+impl<T: BitLength> ::serde_field::SerdeField for DynamicBitArray<T> {
+    fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ::serde::Serialize::serialize(self, serializer)
+    }
+    fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ::serde::Deserialize::deserialize(deserializer)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: BitLength> ::arbitrary::Arbitrary<'a> for BitArray<T> {
+    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        let mut array = Self::new();
+        for index in 0..T::LENGTH {
+            array.set(index, <bool as ::arbitrary::Arbitrary>::arbitrary(u)?);
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: BitLength> ::arbitrary::Arbitrary<'a> for DynamicBitArray<T> {
+    fn arbitrary(u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+        let mut array = Self::new();
+        for _ in 0..u.int_in_range(0..=T::LENGTH)? {
+            array.push(<bool as ::arbitrary::Arbitrary>::arbitrary(u)?);
+        }
+        Ok(array)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<T: BitLength> ::arbitrary_field::ArbitraryField for BitArray<T> {
+    fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+        ::arbitrary::Arbitrary::arbitrary(u)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<T: BitLength> ::arbitrary_field::ArbitraryField for DynamicBitArray<T> {
+    fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+        ::arbitrary::Arbitrary::arbitrary(u)
+    }
+}
+        
+
+
+impl_array!([(1, 1), (2, 2), (3, 2), (4, 3), (5, 3), (6, 3), (7, 3), (8, 4), (9, 4)]);
+impl_array!([(10, 4), (11, 4), (12, 4), (13, 4), (14, 4), (15, 4), (16, 5), (17, 5), (18, 5), (19, 5)]);
+impl_array!([(20, 5), (21, 5), (22, 5), (23, 5), (24, 5), (25, 5), (26, 5), (27, 5), (28, 5), (29, 5)]);
+impl_array!([(30, 5), (31, 5), (32, 6), (33, 6), (34, 6), (35, 6), (36, 6), (37, 6), (38, 6), (39, 6)]);
+impl_array!([(40, 6), (41, 6), (42, 6), (43, 6), (44, 6), (45, 6), (46, 6), (47, 6), (48, 6), (49, 6)]);
+impl_array!([(50, 6), (51, 6), (52, 6), (53, 6), (54, 6), (55, 6), (56, 6), (57, 6), (58, 6), (59, 6)]);
+impl_array!([(60, 6), (61, 6), (62, 6), (63, 6), (64, 7), (65, 7), (66, 7), (67, 7), (68, 7), (69, 7)]);
+impl_array!([(70, 7), (71, 7), (72, 7), (73, 7), (74, 7), (75, 7), (76, 7), (77, 7), (78, 7), (79, 7)]);
+impl_array!([(80, 7), (81, 7), (82, 7), (83, 7), (84, 7), (85, 7), (86, 7), (87, 7), (88, 7), (89, 7)]);
+impl_array!([(90, 7), (91, 7), (92, 7), (93, 7), (94, 7), (95, 7), (96, 7), (97, 7), (98, 7), (99, 7)]);
+
+impl_array!([(100, 7), (101, 7), (102, 7), (103, 7), (104, 7), (105, 7), (106, 7), (107, 7), (108, 7), (109, 7)]);
+impl_array!([(110, 7), (111, 7), (112, 7), (113, 7), (114, 7), (115, 7), (116, 7), (117, 7), (118, 7), (119, 7)]);
+impl_array!([(120, 7), (121, 7), (122, 7), (123, 7), (124, 7), (125, 7), (126, 7), (127, 7), (128, 8), (129, 8)]);
+impl_array!([(130, 8), (131, 8), (132, 8), (133, 8), (134, 8), (135, 8), (136, 8), (137, 8), (138, 8), (139, 8)]);
+impl_array!([(140, 8), (141, 8), (142, 8), (143, 8), (144, 8), (145, 8), (146, 8), (147, 8), (148, 8), (149, 8)]);
+impl_array!([(150, 8), (151, 8), (152, 8), (153, 8), (154, 8), (155, 8), (156, 8), (157, 8), (158, 8), (159, 8)]);
+impl_array!([(160, 8), (161, 8), (162, 8), (163, 8), (164, 8), (165, 8), (166, 8), (167, 8), (168, 8), (169, 8)]);
+impl_array!([(170, 8), (171, 8), (172, 8), (173, 8), (174, 8), (175, 8), (176, 8), (177, 8), (178, 8), (179, 8)]);
+impl_array!([(180, 8), (181, 8), (182, 8), (183, 8), (184, 8), (185, 8), (186, 8), (187, 8), (188, 8), (189, 8)]);
+impl_array!([(190, 8), (191, 8), (192, 8), (193, 8), (194, 8), (195, 8), (196, 8), (197, 8), (198, 8), (199, 8)]);
+
+impl_array!([(200, 8), (201, 8), (202, 8), (203, 8), (204, 8), (205, 8), (206, 8), (207, 8), (208, 8), (209, 8)]);
+impl_array!([(210, 8), (211, 8), (212, 8), (213, 8), (214, 8), (215, 8), (216, 8), (217, 8), (218, 8), (219, 8)]);
+impl_array!([(220, 8), (221, 8), (222, 8), (223, 8), (224, 8), (225, 8), (226, 8), (227, 8), (228, 8), (229, 8)]);
+impl_array!([(230, 8), (231, 8), (232, 8), (233, 8), (234, 8), (235, 8), (236, 8), (237, 8), (238, 8), (239, 8)]);
+impl_array!([(240, 8), (241, 8), (242, 8), (243, 8), (244, 8), (245, 8), (246, 8), (247, 8), (248, 8), (249, 8)]);
+impl_array!([(250, 8), (251, 8), (252, 8), (253, 8), (254, 8), (255, 8), (256, 9)]);
+
+
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void1{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void2{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void3{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void4{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void5{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void6{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void7{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void8{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void9{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void10{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void11{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void12{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void13{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void14{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void15{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void16{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void17{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void18{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void19{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void20{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void21{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void22{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void23{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void24{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void25{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void26{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void27{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void28{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void29{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void30{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void31{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void32{}
+
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void33{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void34{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void35{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void36{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void37{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void38{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void39{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void40{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void41{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void42{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void43{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void44{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void45{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void46{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void47{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void48{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void49{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void50{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void51{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void52{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void53{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void54{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void55{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void56{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void57{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void58{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void59{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void60{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void61{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void62{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void63{}
+#[allow(non_camel_case_types)] #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)] pub struct void64{}
+
+
+macro_rules! impl_serializeable {
+    {$type:ident, $bits:expr} => {
+        impl ::Serializable for $type {
+            const BIT_LENGTH_MIN: usize = $bits;
+
+            const FLATTENED_FIELDS_NUMBER: usize = 1;
+            
+            fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
+                assert_eq!(*flattened_field, 0);
+                let type_bits_remaining = $bits - *bit;
+                let buffer_bits_remaining = buffer.bits_remaining();
+                
+                if type_bits_remaining == 0 {
+                    *bit = 0;
+                    *flattened_field = 1;
+                    SerializationResult::Finished
+                } else if buffer_bits_remaining == 0 {
+                    SerializationResult::BufferFull
+                } else if buffer_bits_remaining >= type_bits_remaining {
+                    buffer.push_bits(type_bits_remaining, PrimitiveType::to_bits(*self) >> *bit);
+                    *bit = 0;
+                    *flattened_field = 1;
+                    SerializationResult::Finished
+                } else {
+                    buffer.push_bits(buffer_bits_remaining, PrimitiveType::to_bits(*self) >> *bit);
+                    *bit += buffer_bits_remaining;
+                    SerializationResult::BufferFull
+                }
+            }
+            
+            fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, _last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+                assert_eq!(*flattened_field, 0);
+                // the bits are or'ed into the value, which must not keep any bits from before (dynamic arrays aren't initialized)
+                if *bit == 0 {
+                    *self = PrimitiveType::from_bits(0);
+                }
+                let buffer_len = buffer.bit_length();
+                if buffer_len == 0 && *bit == $bits {
+                    *bit = 0;
+                    *flattened_field = 1;
+                    DeserializationResult::Finished
+                } else if buffer_len == 0 && *bit < $bits {
+                    DeserializationResult::BufferInsufficient
+                } else if buffer_len < $bits - *bit {
+                    *self = PrimitiveType::from_bits(PrimitiveType::to_bits(*self) | (buffer.pop_bits(buffer_len) << *bit));
+                    *bit += buffer_len;
+                    DeserializationResult::BufferInsufficient
+                } else {
+                    *self = PrimitiveType::from_bits(PrimitiveType::to_bits(*self) | (buffer.pop_bits($bits-*bit) << *bit));
+                    *bit = 0;
+                    *flattened_field = 1;
+                    DeserializationResult::Finished
+                }
+            }
+
+            fn placeholder() -> Self {
+                PrimitiveType::from_bits(0)
+            }
+        }
+
+    };
+}
+
+/// Used by the DSDL compiler to implement `Default` for structs containing arrays longer than 32 elements
+#[doc(hidden)]
+pub use lib::core::array::from_fn as __array_from_fn;
+
+macro_rules! impl_ux{
+    {[$(($type:ident, $bits:expr)),*], $underlying_type:ident} => {$(impl_ux!($type, $bits, $underlying_type);)*};
+    ($type:ident, $bits:expr, $underlying_type:ident) => {
+        impl PrimitiveType for $type {
+            fn from_bits(v: u64) -> Self {
+                $type::new(v as $underlying_type)
+            }
+            fn to_bits(self) -> u64 {
+                u64::from(self)
+            }
+        }
+        impl FromNative<$underlying_type> for $type {
+            fn from_native(value: $underlying_type) -> Result<Self, NativeError> {
+                if u64::from(value) < u64::from($type::MIN) || u64::from(value) > u64::from($type::MAX) {
+                    Err(NativeError::OutOfRange)
+                } else {
+                    Ok($type::new(value))
+                }
+            }
+        }
+        impl ::reflect::VisitField for $type {
+            fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+                visitor.visit_value(field, ::reflect::Value::Unsigned(u64::from(*self)));
+            }
+        }
+        impl ::text::TextField for $type {
+            fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+                text.parse::<$underlying_type>().ok()
+                    .and_then(|value| $type::from_native(value).ok())
+                    .ok_or(::text::ParseErrorKind::InvalidValue(field.name))
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl ::serde_field::SerdeField for $type {
+            fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde::Serialize::serialize(&$underlying_type::from(*self), serializer)
+            }
+            fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$underlying_type as ::serde::Deserialize>::deserialize(deserializer)?;
+                $type::from_native(value).map_err(|_| ::serde::de::Error::invalid_value(
+                    ::serde::de::Unexpected::Unsigned(u64::from(value)),
+                    &concat!("an integer that fits in ", stringify!($type)),
+                ))
+            }
+        }
+        #[cfg(feature = "arbitrary")]
+        impl ::arbitrary_field::ArbitraryField for $type {
+            fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                let value = u.int_in_range($underlying_type::from($type::MIN)..=$underlying_type::from($type::MAX))?;
+                Ok($type::new(value))
+            }
+        }
+        impl ::DsdlField for $type {
+            const DSDL_TYPE: &'static str = concat!("uint", stringify!($bits));
+            const PRIMITIVE: bool = true;
+        }
+        impl_serializeable!($type, $bits);
+    };
+}
+
+macro_rules! impl_ix{
+    {[$(($type:ident, $bits:expr)),*], $underlying_type:ident} => {$(impl_ix!($type, $bits, $underlying_type);)*};
+    ($type:ident, $bits:expr, $underlying_type:ident) => {
+        impl PrimitiveType for $type {
+            fn from_bits(v: u64) -> Self {
+                // the sign bit is the highest of the `$bits` bits
+                $type::new((((v << (64 - $bits)) as i64) >> (64 - $bits)) as $underlying_type)
+            }
+            fn to_bits(self) -> u64 {
+                (i64::from(self) as u64) & ((1 << $bits) - 1)
+            }
+        }
+        impl FromNative<$underlying_type> for $type {
+            fn from_native(value: $underlying_type) -> Result<Self, NativeError> {
+                if i64::from(value) < i64::from($type::MIN) || i64::from(value) > i64::from($type::MAX) {
+                    Err(NativeError::OutOfRange)
+                } else {
+                    Ok($type::new(value))
+                }
+            }
+        }
+        impl ::reflect::VisitField for $type {
+            fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+                visitor.visit_value(field, ::reflect::Value::Signed(i64::from(*self)));
+            }
+        }
+        impl ::text::TextField for $type {
+            fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+                text.parse::<$underlying_type>().ok()
+                    .and_then(|value| $type::from_native(value).ok())
+                    .ok_or(::text::ParseErrorKind::InvalidValue(field.name))
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl ::serde_field::SerdeField for $type {
+            fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde::Serialize::serialize(&$underlying_type::from(*self), serializer)
+            }
+            fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$underlying_type as ::serde::Deserialize>::deserialize(deserializer)?;
+                $type::from_native(value).map_err(|_| ::serde::de::Error::invalid_value(
+                    ::serde::de::Unexpected::Signed(i64::from(value)),
+                    &concat!("an integer that fits in ", stringify!($type)),
+                ))
+            }
+        }
+        #[cfg(feature = "arbitrary")]
+        impl ::arbitrary_field::ArbitraryField for $type {
+            fn arbitrary_field(u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                let value = u.int_in_range($underlying_type::from($type::MIN)..=$underlying_type::from($type::MAX))?;
+                Ok($type::new(value))
+            }
+        }
+        impl ::DsdlField for $type {
+            const DSDL_TYPE: &'static str = concat!("int", stringify!($bits));
+            const PRIMITIVE: bool = true;
+        }
+        impl_serializeable!($type, $bits);
+    };
+}
+
+macro_rules! impl_vx{
+    {[$(($type:ident, $bits:expr)),*]} => {$(impl_vx!($type, $bits);)*};
+    ($type:ident, $bits:expr) => {
+        impl PrimitiveType for $type {
+            fn from_bits(_v: u64) -> Self {
+                $type{}
+            }
+            fn to_bits(self) -> u64 {
+                0
+            }
+        }
+        impl ::reflect::VisitField for $type {
+            fn visit_field<V: ::reflect::Visitor>(&self, field: &'static ::reflect::FieldInfo, visitor: &mut V) {
+                visitor.visit_value(field, ::reflect::Value::Void);
+            }
+        }
+        impl ::text::TextField for $type {
+            fn parse_inline(text: &str, field: &'static ::reflect::FieldInfo) -> Result<Self, ::text::ParseErrorKind> {
+                if text == "void" {
+                    Ok($type{})
+                } else {
+                    Err(::text::ParseErrorKind::InvalidValue(field.name))
+                }
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl ::serde::Serialize for $type {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_unit()
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl<'de> ::serde::Deserialize<'de> for $type {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <() as ::serde::Deserialize>::deserialize(deserializer).map(|()| $type{})
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl ::serde_field::SerdeField for $type {
+            fn serialize_field<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde::Serialize::serialize(self, serializer)
+            }
+            fn deserialize_field<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                ::serde::Deserialize::deserialize(deserializer)
+            }
+        }
+        #[cfg(feature = "arbitrary")]
+        impl<'a> ::arbitrary::Arbitrary<'a> for $type {
+            fn arbitrary(_u: &mut ::arbitrary::Unstructured<'a>) -> ::arbitrary::Result<Self> {
+                Ok($type{})
+            }
+            fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+                (0, Some(0))
+            }
+        }
+        #[cfg(feature = "arbitrary")]
+        impl ::arbitrary_field::ArbitraryField for $type {
+            fn arbitrary_field(_u: &mut ::arbitrary::Unstructured) -> ::arbitrary::Result<Self> {
+                Ok($type{})
+            }
+        }
+        impl ::DsdlField for $type {
+            const DSDL_TYPE: &'static str = stringify!($type);
+            const PRIMITIVE: bool = true;
+            const VOID: bool = true;
+        }
+        impl_serializeable!($type, $bits);
+    };
+}
+
+impl_ux!([(u2, 2), (u3, 3), (u4, 4), (u5, 5), (u6, 6), (u7, 7)], u8);
+
+impl_ux!([(u9, 9), (u10, 10), (u11, 11), (u12, 12), (u13, 13), (u14, 14), (u15, 15)], u16);
+
+impl_ux!([(u17, 17), (u18, 18), (u19, 19), (u20, 20), (u21, 21), (u22, 22), (u23, 23), (u24, 24),
+          (u25, 25), (u26, 26), (u27, 27), (u28, 28), (u29, 29), (u30, 30), (u31, 31)], u32);
+
+impl_ux!([(u33, 33), (u34, 34), (u35, 35), (u36, 36), (u37, 37), (u38, 38), (u39, 39), (u40, 40),
+          (u41, 41), (u42, 42), (u43, 43), (u44, 44), (u45, 45), (u46, 46), (u47, 47), (u48, 48),
+          (u49, 49), (u50, 50), (u51, 51), (u52, 52), (u53, 53), (u54, 54), (u55, 55), (u56, 56),
+          (u57, 57), (u58, 58), (u59, 59), (u60, 60), (u61, 61), (u62, 62), (u63, 63)], u64);
+
+
+
+impl_ix!([(i2, 2), (i3, 3), (i4, 4), (i5, 5), (i6, 6), (i7, 7)], i8);
+
+impl_ix!([(i9, 9), (i10, 10), (i11, 11), (i12, 12), (i13, 13), (i14, 14), (i15, 15)], i16);
 
 impl_ix!([(i17, 17), (i18, 18), (i19, 19), (i20, 20), (i21, 21), (i22, 22), (i23, 23), (i24, 24),
           (i25, 25), (i26, 26), (i27, 27), (i28, 28), (i29, 29), (i30, 30), (i31, 31)], i32);
@@ -694,7 +2087,7 @@ impl PrimitiveType for f16 {
         f16::from_bits(v as u16)
     }
     fn to_bits(self) -> u64 {
-        u64::from(f16::as_bits(self))
+        u64::from(f16::to_bits(self))
     }
 }
 impl_serializeable!(f16, 16);
@@ -750,6 +2143,19 @@ impl PrimitiveType for bool {
 }
 impl_serializeable!(bool, 1);
 
+macro_rules! impl_dsdl_field {
+    {$(($type:ident, $dsdl_type:expr)),*} => {$(
+        impl ::DsdlField for $type {
+            const DSDL_TYPE: &'static str = $dsdl_type;
+            const PRIMITIVE: bool = true;
+        }
+    )*};
+}
+
+impl_dsdl_field!((bool, "bool"), (u8, "uint8"), (u16, "uint16"), (u32, "uint32"), (u64, "uint64"));
+impl_dsdl_field!((i8, "int8"), (i16, "int16"), (i32, "int32"), (i64, "int64"));
+impl_dsdl_field!((f16, "float16"), (f32, "float32"), (f64, "float64"));
+
 
 
 
@@ -786,4 +2192,185 @@ mod tests {
         a.push(120);
         assert_eq!(a.as_ref(), &[12, 120]);
     }
+
+    #[test]
+    fn dynamic_array_try_push() {
+        let mut a = Dynamic::<[u8; 2]>::try_with_data(&[1]).unwrap();
+        assert_eq!(a.try_push(2), Ok(()));
+        assert_eq!(a.try_push(3).map_err(CapacityError::element), Err(3));
+        assert_eq!(a.as_ref(), &[1, 2]);
+        assert_eq!(Dynamic::<[u8; 2]>::try_with_data(&[1, 2, 3]), Err(CapacityError::new(())));
+    }
+
+    #[test]
+    fn dynamic_array_pop_insert_remove() {
+        let mut a = Dynamic::<[u8; 5]>::with_data(&[1, 2, 3]);
+        a.insert(0, 0);
+        a.insert(4, 4);
+        assert_eq!(a.as_ref(), &[0, 1, 2, 3, 4]);
+
+        assert_eq!(a.remove(2), 2);
+        assert_eq!(a.as_ref(), &[0, 1, 3, 4]);
+
+        assert_eq!(a.pop(), Some(4));
+        assert_eq!(a.as_ref(), &[0, 1, 3]);
+
+        a.clear();
+        assert_eq!(a.pop(), None);
+        assert_eq!(a.length(), 0);
+    }
+
+    #[test]
+    fn dynamic_array_iterators() {
+        let mut a: Dynamic<[u8; 6]> = (1..4).collect();
+        a.extend([4, 5].iter().cloned());
+        assert_eq!(a.as_ref(), &[1, 2, 3, 4, 5]);
+
+        for element in &mut a {
+            *element *= 2;
+        }
+        assert_eq!((&a).into_iter().sum::<u8>(), 30);
+        assert_eq!(a.len(), 5);
+        assert_eq!(a.into_iter().collect::<Dynamic<[u8; 6]>>().as_ref(), &[2, 4, 6, 8, 10]);
+    }
+
+    #[test]
+    fn dynamic_array_str() {
+        let a: Dynamic<[u8; 6]> = "uavcan".parse().unwrap();
+        assert_eq!(a.as_str(), Ok("uavcan"));
+        assert!("uavcan!".parse::<Dynamic<[u8; 6]>>().is_err());
+        assert!(Dynamic::<[u8; 6]>::with_data(&[0xff]).as_str().is_err());
+    }
+
+    #[test]
+    #[cfg(feature="std")]
+    fn dynamic_array_eq_hash() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let hash = |d: &Dynamic<[u8; 15]>| {
+            let mut hasher = DefaultHasher::new();
+            d.hash(&mut hasher);
+            hasher.finish()
+        };
+        let d1 = Dynamic::<[u8; 15]>::with_data(&[1, 2, 3]);
+        let mut d2 = Dynamic::<[u8; 15]>::with_data(&[1, 2, 3, 4]);
+        assert_ne!(hash(&d1), hash(&d2));
+
+        // elements beyond the length don't count
+        d2.set_length(3);
+        assert_eq!(d1, d2);
+        assert_eq!(hash(&d1), hash(&d2));
+    }
+
+    #[test]
+    fn bit_array_get_set() {
+        let mut bits = BitArray::<[bool; 130]>::new();
+        bits.set(0, true);
+        bits.set(64, true);
+        bits.set(129, true);
+        bits.set(0, false);
+
+        assert_eq!(bits.iter().count(), 130);
+        assert!(bits.iter().enumerate().filter(|&(_, bit)| bit).map(|(i, _)| i).eq([64, 129].iter().cloned()));
+        assert!(bits[64] && !bits[63]);
+
+        assert!(BitArray::<[bool; 3]>::from_native(&[true, false][..]).is_err());
+        assert!(BitArray::<[bool; 2]>::from_native(&[true, false][..]).unwrap().iter().eq([true, false].iter().cloned()));
+    }
+
+    #[test]
+    fn dynamic_bit_array_push_pop() {
+        let mut bits = DynamicBitArray::<[bool; 3]>::new();
+        bits.push(true);
+        bits.push(false);
+        assert_eq!(bits.try_push(true), Ok(()));
+        assert_eq!(bits.try_push(true), Err(CapacityError::new(true)));
+        assert_eq!(bits.length(), 3);
+
+        assert_eq!(bits.pop(), Some(true));
+        bits.set(1, true);
+        assert!(bits.iter().eq([true, true].iter().cloned()));
+
+        // popped bits are cleared, so they don't count for equality
+        assert_eq!(bits, DynamicBitArray::with_data(&[true, true]));
+        bits.clear();
+        assert_eq!(bits.pop(), None);
+        assert!(DynamicBitArray::<[bool; 3]>::try_with_data(&[true; 4]).is_err());
+        assert_eq!((0..3).map(|i| i == 1).collect::<DynamicBitArray<[bool; 3]>>(), DynamicBitArray::with_data(&[false, true, false]));
+    }
+
+    #[test]
+    fn from_native_range_checked() {
+        assert_eq!(u2::from_native(3u8), Ok(u2::new(3)));
+        assert_eq!(u2::from_native(4u8), Err(NativeError::OutOfRange));
+        assert_eq!(i4::from_native(-8i8), Ok(i4::new(-8)));
+        assert_eq!(i4::from_native(-9i8), Err(NativeError::OutOfRange));
+        assert_eq!(u33::from_native(1u64 << 33), Err(NativeError::OutOfRange));
+        assert_eq!(f16::from_native(1.5f32), Ok(f16::from_f32(1.5)));
+        assert_eq!(f16::from_native(1e6f32), Err(NativeError::OutOfRange));
+    }
+
+    #[test]
+    fn from_native_arrays() {
+        assert_eq!(<[u3; 3]>::from_native(&[1u8, 2, 7][..]), Ok([u3::new(1), u3::new(2), u3::new(7)]));
+        assert_eq!(<[u3; 3]>::from_native(&[1u8, 2][..]), Err(NativeError::WrongLength));
+        assert_eq!(<[u3; 3]>::from_native(&[1u8, 2, 8][..]), Err(NativeError::OutOfRange));
+
+        let d = Dynamic::<[u8; 15]>::from_native("uavcan".as_bytes()).unwrap();
+        assert_eq!(d.as_ref(), "uavcan".as_bytes());
+        assert_eq!(Dynamic::<[u8; 2]>::from_native(&[1u8, 2, 3][..]), Err(NativeError::WrongLength));
+    }
+
+    #[test]
+    fn signed_bits() {
+        assert_eq!(i3::new(-4).to_bits(), 0b100);
+        assert_eq!(i3::from_bits(0b100), i3::new(-4));
+        assert_eq!(i3::from_bits(0b011), i3::new(3));
+        assert_eq!(i13::from_bits(i13::new(-1000).to_bits()), i13::new(-1000));
+    }
+
+    #[test]
+    fn option_element_starts_as_placeholder() {
+        use lib::core::num::NonZeroU8;
+        use Serializable;
+
+        // all zero bits isn't a valid `Scaled`, its placeholder has a scale of one
+        #[derive(Debug, PartialEq)]
+        struct Scaled {
+            value: u8,
+            scale: NonZeroU8,
+        }
+
+        impl Serializable for Scaled {
+            const BIT_LENGTH_MIN: usize = 8;
+            const FLATTENED_FIELDS_NUMBER: usize = 1;
+
+            fn serialize(&self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut SerializationBuffer) -> SerializationResult {
+                self.value.serialize(flattened_field, bit, last_field, buffer)
+            }
+
+            fn deserialize(&mut self, flattened_field: &mut usize, bit: &mut usize, last_field: bool, buffer: &mut DeserializationBuffer) -> DeserializationResult {
+                self.value.deserialize(flattened_field, bit, last_field, buffer)
+            }
+
+            fn placeholder() -> Self {
+                Scaled{value: 0, scale: NonZeroU8::new(1).unwrap()}
+            }
+        }
+
+        let mut data = [0u8; 2];
+        let sent = Some(Scaled{value: 43, scale: NonZeroU8::new(5).unwrap()});
+        assert_eq!(sent.serialize(&mut 0, &mut 0, false, &mut SerializationBuffer::with_empty_buffer(&mut data)), SerializationResult::Finished);
+
+        let mut received: Option<Scaled> = None;
+        let mut buffer = DeserializationBuffer::with_full_buffer(&mut data);
+        assert_eq!(received.deserialize(&mut 0, &mut 0, false, &mut buffer), DeserializationResult::Finished);
+        assert_eq!(received, Some(Scaled{value: 43, scale: NonZeroU8::new(1).unwrap()}));
+    }
+
+    #[test]
+    fn native_error_in_field() {
+        assert_eq!(NativeError::OutOfRange.in_field("mode"), FieldError::OutOfRange("mode"));
+        assert_eq!(NativeError::WrongLength.in_field("name"), FieldError::WrongLength("name"));
+    }
 }